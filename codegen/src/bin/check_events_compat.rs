@@ -0,0 +1,42 @@
+//! Lists the changes of `schema/events.json` since `schema/events.snapshot.json`, and fails
+//! if any of them breaks the consumers without increasing the version of its event.
+
+use legend_saga_codegen::compat::{compare, Compatibility, SNAPSHOT_PATH};
+use legend_saga_codegen::{parse, workspace_root, SCHEMA_PATH};
+use std::fs;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let root = workspace_root();
+    let read = |path: &str| {
+        fs::read_to_string(root.join(path))
+            .map_err(|e| format!("cannot read {path}: {e}"))
+            .and_then(|schema| parse(&schema))
+    };
+    let (snapshot, schema) = match (read(SNAPSHOT_PATH), read(SCHEMA_PATH)) {
+        (Ok(snapshot), Ok(schema)) => (snapshot, schema),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let changes = compare(&snapshot, &schema);
+    if changes.is_empty() {
+        println!("{SCHEMA_PATH} has no changes since {SNAPSHOT_PATH}");
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+    if changes
+        .iter()
+        .any(|change| change.compatibility == Compatibility::Breaking)
+    {
+        eprintln!(
+            "breaking changes since {SNAPSHOT_PATH}, increase the version of the events and \
+             register upcasters, or revert them"
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,487 @@
+//! Changes of the payloads that break the consumers in other languages. Two versions of the
+//! schema are compared by their wire format: the current one and the snapshot committed at
+//! [`SNAPSHOT_PATH`], taken from the last release with `make snapshot-events`.
+
+use crate::{base_type, Schema, TypeDef};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// The snapshot of the schema, relative to the workspace root.
+pub const SNAPSHOT_PATH: &str = "schema/events.snapshot.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    /// Breaks the consumers of the event.
+    Breaking,
+    /// Breaks the consumers, but the version of the event was increased so they upcast
+    /// the payloads of the previous one.
+    Versioned,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compatibility::Compatible => "compatible",
+            Compatibility::Breaking => "breaking",
+            Compatibility::Versioned => "versioned",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Wire name of the event.
+    pub event: String,
+    /// Wire path of the field in the payload, e.g. `participants[].userId`. Empty when the
+    /// change is the event itself.
+    pub path: String,
+    pub description: String,
+    pub compatibility: Compatibility,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "[{}] {}", self.compatibility, self.event)?,
+            path => write!(f, "[{}] {} {path}", self.compatibility, self.event)?,
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+/// The changes from `snapshot` to `schema`, both validated.
+pub fn compare(snapshot: &Schema, schema: &Schema) -> Vec<Change> {
+    let old_types = types(snapshot);
+    let new_types = types(schema);
+    let mut changes = Vec::new();
+    for old in &snapshot.events {
+        let Some(new) = schema.events.iter().find(|event| event.name == old.name) else {
+            changes.push(Change {
+                event: old.name.clone(),
+                path: String::new(),
+                description: "event removed".to_string(),
+                compatibility: Compatibility::Breaking,
+            });
+            continue;
+        };
+        let mut diff = Diff {
+            event: &old.name,
+            changes: Vec::new(),
+        };
+        diff.shapes(
+            "",
+            &shape_of_type(&old_types, &old.payload, &mut Vec::new()),
+            &shape_of_type(&new_types, &new.payload, &mut Vec::new()),
+        );
+        let versioned = new.version.unwrap_or(1) > old.version.unwrap_or(1);
+        for mut change in diff.changes {
+            if versioned && change.compatibility == Compatibility::Breaking {
+                change.compatibility = Compatibility::Versioned;
+            }
+            changes.push(change);
+        }
+    }
+    for new in &schema.events {
+        if !snapshot.events.iter().any(|event| event.name == new.name) {
+            changes.push(Change {
+                event: new.name.clone(),
+                path: String::new(),
+                description: "event added".to_string(),
+                compatibility: Compatibility::Compatible,
+            });
+        }
+    }
+    changes
+}
+
+/// What a payload looks like on the wire, the names of the types are not part of it.
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Primitive(String),
+    Array(Box<Shape>),
+    Map(Box<Shape>),
+    Struct(BTreeMap<String, Field>),
+    Enum(BTreeSet<String>),
+}
+
+#[derive(Debug, PartialEq)]
+struct Field {
+    /// Name in the schema, to tell a renamed field from a removed one.
+    name: String,
+    shape: Shape,
+    optional: bool,
+    /// Whether the JSON must have it, otherwise the consumers take a default.
+    required: bool,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Primitive(ty) => f.write_str(ty),
+            Shape::Array(item) => write!(f, "{item}[]"),
+            Shape::Map(value) => write!(f, "map<{value}>"),
+            Shape::Struct(_) => f.write_str("object"),
+            Shape::Enum(_) => f.write_str("enum"),
+        }
+    }
+}
+
+fn types(schema: &Schema) -> HashMap<&str, &TypeDef> {
+    schema
+        .events
+        .iter()
+        .map(|event| &event.payload)
+        .chain(&schema.types)
+        .map(|t| (t.name.as_str(), t))
+        .collect()
+}
+
+/// `stack` holds the types being expanded, a recursive type is compared by its name.
+fn shape_of_type<'a>(
+    types: &HashMap<&str, &'a TypeDef>,
+    t: &'a TypeDef,
+    stack: &mut Vec<&'a str>,
+) -> Shape {
+    if stack.contains(&t.name.as_str()) {
+        return Shape::Primitive(t.name.clone());
+    }
+    stack.push(&t.name);
+    let rule = t.rename_all.as_deref().unwrap_or("camelCase");
+    let shape = match (&t.fields, &t.variants) {
+        (Some(fields), _) => Shape::Struct(
+            fields
+                .iter()
+                .map(|field| {
+                    let wire = match &field.rename {
+                        Some(rename) => rename.clone(),
+                        None => rename_field(rule, &field.name),
+                    };
+                    let field = Field {
+                        name: field.name.clone(),
+                        shape: shape_of(types, &field.ty, stack),
+                        optional: field.optional,
+                        required: !field.optional && !field.default,
+                    };
+                    (wire, field)
+                })
+                .collect(),
+        ),
+        (None, variants) => Shape::Enum(
+            variants
+                .iter()
+                .flatten()
+                .map(|variant| rename_variant(rule, variant))
+                .collect(),
+        ),
+    };
+    stack.pop();
+    shape
+}
+
+fn shape_of<'a>(types: &HashMap<&str, &'a TypeDef>, ty: &str, stack: &mut Vec<&'a str>) -> Shape {
+    if let Some(item) = ty.strip_suffix("[]") {
+        return Shape::Array(Box::new(shape_of(types, item, stack)));
+    }
+    if let Some(value) = ty.strip_prefix("map<").and_then(|t| t.strip_suffix('>')) {
+        return Shape::Map(Box::new(shape_of(types, value, stack)));
+    }
+    match types.get(base_type(ty)) {
+        Some(t) => shape_of_type(types, t, stack),
+        None => Shape::Primitive(ty.to_string()),
+    }
+}
+
+/// The wire name of a snake_case field under a serde `rename_all` rule.
+fn rename_field(rule: &str, field: &str) -> String {
+    let pascal = || -> String {
+        field
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
+
+/// The wire name of a PascalCase variant under a serde `rename_all` rule.
+fn rename_variant(rule: &str, variant: &str) -> String {
+    let snake = || -> String {
+        let mut snake = String::new();
+        for (i, c) in variant.char_indices() {
+            if i > 0 && c.is_ascii_uppercase() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+        snake
+    };
+    match rule {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "camelCase" => {
+            let mut chars = variant.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "snake_case" => snake(),
+        "SCREAMING_SNAKE_CASE" => snake().to_ascii_uppercase(),
+        "kebab-case" => snake().replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake().to_ascii_uppercase().replace('_', "-"),
+        _ => variant.to_string(),
+    }
+}
+
+struct Diff<'a> {
+    event: &'a str,
+    changes: Vec<Change>,
+}
+
+impl Diff<'_> {
+    fn push(&mut self, path: &str, description: String, compatibility: Compatibility) {
+        self.changes.push(Change {
+            event: self.event.to_string(),
+            path: path.to_string(),
+            description,
+            compatibility,
+        });
+    }
+
+    fn shapes(&mut self, path: &str, old: &Shape, new: &Shape) {
+        match (old, new) {
+            (Shape::Struct(old), Shape::Struct(new)) => self.structs(path, old, new),
+            (Shape::Enum(old), Shape::Enum(new)) => {
+                for value in old.difference(new) {
+                    self.push(
+                        path,
+                        format!("value {value:?} removed"),
+                        Compatibility::Breaking,
+                    );
+                }
+                // Consumers that parse the values into an enum reject the new ones
+                for value in new.difference(old) {
+                    self.push(
+                        path,
+                        format!("value {value:?} added"),
+                        Compatibility::Breaking,
+                    );
+                }
+            }
+            (Shape::Array(old), Shape::Array(new)) => self.shapes(&format!("{path}[]"), old, new),
+            (Shape::Map(old), Shape::Map(new)) => self.shapes(&format!("{path}{{}}"), old, new),
+            (Shape::Primitive(old), Shape::Primitive(new)) if old == new => {}
+            (old, new) => self.push(
+                path,
+                format!("type changed from {old} to {new}"),
+                Compatibility::Breaking,
+            ),
+        }
+    }
+
+    fn structs(
+        &mut self,
+        path: &str,
+        old: &BTreeMap<String, Field>,
+        new: &BTreeMap<String, Field>,
+    ) {
+        let join = |wire: &str| match path {
+            "" => wire.to_string(),
+            path => format!("{path}.{wire}"),
+        };
+        let mut renamed = BTreeSet::new();
+        for (wire, field) in old {
+            let Some(current) = new.get(wire) else {
+                match new
+                    .iter()
+                    .find(|(wire, f)| f.name == field.name && !old.contains_key(*wire))
+                {
+                    Some((new_wire, _)) => {
+                        renamed.insert(new_wire);
+                        self.push(
+                            &join(wire),
+                            format!("renamed to {new_wire}"),
+                            Compatibility::Breaking,
+                        );
+                    }
+                    None => self.push(
+                        &join(wire),
+                        "field removed".to_string(),
+                        Compatibility::Breaking,
+                    ),
+                }
+                continue;
+            };
+            match (field.optional, current.optional) {
+                (false, true) => self.push(
+                    &join(wire),
+                    "field made optional".to_string(),
+                    Compatibility::Breaking,
+                ),
+                (true, false) => self.push(
+                    &join(wire),
+                    "field made required".to_string(),
+                    Compatibility::Breaking,
+                ),
+                _ => {}
+            }
+            self.shapes(&join(wire), &field.shape, &current.shape);
+        }
+        for (wire, field) in new {
+            if old.contains_key(wire) || renamed.contains(wire) {
+                continue;
+            }
+            match field.required {
+                true => self.push(
+                    &join(wire),
+                    "required field added".to_string(),
+                    Compatibility::Breaking,
+                ),
+                false => self.push(
+                    &join(wire),
+                    "optional field added".to_string(),
+                    Compatibility::Compatible,
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_compat {
+    use super::*;
+    use crate::{parse, workspace_root, SCHEMA_PATH};
+    use std::fs;
+
+    #[test]
+    fn no_unversioned_breaking_changes_since_the_snapshot() {
+        let root = workspace_root();
+        let read = |path: &str| parse(&fs::read_to_string(root.join(path)).unwrap()).unwrap();
+        let breaking: Vec<String> = compare(&read(SNAPSHOT_PATH), &read(SCHEMA_PATH))
+            .into_iter()
+            .filter(|change| change.compatibility == Compatibility::Breaking)
+            .map(|change| change.to_string())
+            .collect();
+        assert!(
+            breaking.is_empty(),
+            "breaking changes since {SNAPSHOT_PATH}, increase the version of the events and \
+             register upcasters, or revert them:\n{}",
+            breaking.join("\n")
+        );
+    }
+
+    fn schema(fields: &str, version: u32) -> Schema {
+        parse(&format!(
+            r#"{{
+                "events": [{{
+                    "name": "missions.finished",
+                    "version": {version},
+                    "payload": {{"name": "P", "fields": [{fields}]}}
+                }}],
+                "types": [
+                    {{"name": "Winner", "fields": [{{"name": "user_id", "type": "string"}}]}},
+                    {{"name": "Medal", "renameAll": "UPPERCASE", "variants": ["Gold", "Silver"]}}
+                ]
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        compare(&schema(old, 1), &schema(new, 1))
+            .into_iter()
+            .map(|change| change.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_the_changes_of_the_fields() {
+        let old = r#"{"name": "mission_id", "type": "string"},
+                     {"name": "winners", "type": "Winner[]"}"#;
+        assert!(changes(old, old).is_empty());
+        assert_eq!(
+            changes(
+                old,
+                r#"{"name": "mission_id", "type": "string"},
+                   {"name": "winners", "type": "Winner[]"},
+                   {"name": "medal", "type": "Medal", "optional": true}"#
+            ),
+            ["[compatible] missions.finished medal: optional field added"]
+        );
+        assert_eq!(
+            changes(
+                old,
+                r#"{"name": "mission_id", "type": "i64"},
+                   {"name": "winners", "type": "Winner[]", "rename": "users"},
+                   {"name": "ended_at", "type": "datetime"}"#
+            ),
+            [
+                "[breaking] missions.finished missionId: type changed from string to i64",
+                "[breaking] missions.finished winners: renamed to users",
+                "[breaking] missions.finished endedAt: required field added",
+            ]
+        );
+        assert_eq!(
+            changes(old, r#"{"name": "winners", "type": "Winner"}"#),
+            [
+                "[breaking] missions.finished missionId: field removed",
+                "[breaking] missions.finished winners: type changed from object[] to object",
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_nested_types_and_versions() {
+        let fields = r#"{"name": "winners", "type": "Winner[]"},
+                        {"name": "medal", "type": "Medal"}"#;
+        let old = schema(fields, 1);
+        let mut new = schema(fields, 2);
+        new.types[0].fields.as_mut().unwrap()[0].rename = Some("uid".to_string());
+        new.types[1].variants.as_mut().unwrap().pop();
+        let mut changes: Vec<String> = compare(&old, &new)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            [
+                "[versioned] missions.finished medal: value \"SILVER\" removed",
+                "[versioned] missions.finished winners[].userId: renamed to uid",
+            ]
+        );
+
+        new.events[0].name = "missions.closed".to_string();
+        let events: Vec<String> = compare(&old, &new)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "[breaking] missions.finished: event removed",
+                "[compatible] missions.closed: event added",
+            ]
+        );
+    }
+}
@@ -0,0 +1,580 @@
+//! Generator of legend-saga's `events` module. `schema/events.json` is the single source
+//! of truth of the events shared with the other legend-transactional libraries: the
+//! `MicroserviceEvent` enum, the payload structs and their `PayloadEvent` impls are
+//! generated from it.
+
+pub mod compat;
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// The schema, relative to the workspace root.
+pub const SCHEMA_PATH: &str = "schema/events.json";
+/// The generated module, relative to the workspace root.
+pub const OUTPUT_PATH: &str = "legend-saga/src/events/generated.rs";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    pub events: Vec<EventDef>,
+    /// Types used by the payloads that are not payloads themselves.
+    #[serde(default)]
+    pub types: Vec<TypeDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventDef {
+    /// Wire name, `<domain>.<event>`. The variant is its PascalCase form.
+    pub name: String,
+    pub doc: Option<String>,
+    /// Comment heading a group of variants.
+    pub section: Option<String>,
+    /// Version of the payload, increased on each change of its schema. 1 unless given.
+    pub version: Option<u32>,
+    pub payload: TypeDef,
+}
+
+/// A struct, when it has `fields`, or a unit-only enum, when it has `variants`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct TypeDef {
+    pub name: String,
+    pub doc: Option<String>,
+    /// serde `rename_all` rule, `camelCase` unless given. `none` keeps the Rust names.
+    pub rename_all: Option<String>,
+    pub fields: Option<Vec<FieldDef>>,
+    pub variants: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct FieldDef {
+    pub name: String,
+    /// `string`, `bool`, `i32`, `i64`, `u32`, `u64`, `f32`, `f64`, `json`, `datetime`, a
+    /// type of the schema, `T[]` or `map<T>`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub optional: bool,
+    /// Leaves the field out of the JSON when it is `None`.
+    #[serde(default)]
+    pub skip_if_none: bool,
+    /// Takes the default value when the field is missing.
+    #[serde(default)]
+    pub default: bool,
+    pub rename: Option<String>,
+    pub doc: Option<String>,
+    /// Rejects empty or blank strings when validating the payload.
+    #[serde(default)]
+    pub non_empty: bool,
+    /// The only values a string accepts when validating the payload.
+    pub one_of: Option<Vec<String>>,
+}
+
+/// Root of the workspace this crate belongs to.
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("the codegen crate is in the workspace")
+        .to_path_buf()
+}
+
+pub fn parse(schema: &str) -> Result<Schema, String> {
+    serde_json::from_str(schema).map_err(|e| format!("invalid schema: {e}"))
+}
+
+/// The variant of `MicroserviceEvent` for a wire name, e.g. `AuthDeletedUser` for
+/// `auth.deleted_user`.
+pub fn variant_name(event: &str) -> String {
+    event
+        .split(['.', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Source of the generated module.
+pub fn generate(schema: &Schema) -> Result<String, String> {
+    validate(schema)?;
+    let uses_type = |name: &str| {
+        schema
+            .events
+            .iter()
+            .map(|event| &event.payload)
+            .chain(&schema.types)
+            .flat_map(|t| t.fields.iter().flatten())
+            .any(|field| rust_type(&field.ty).contains(name))
+    };
+
+    let structs: HashSet<&str> = schema
+        .events
+        .iter()
+        .map(|event| &event.payload)
+        .chain(&schema.types)
+        .filter(|t| t.fields.is_some())
+        .map(|t| t.name.as_str())
+        .collect();
+    let constrained = schema
+        .events
+        .iter()
+        .map(|event| &event.payload)
+        .chain(&schema.types)
+        .flat_map(|t| t.fields.iter().flatten())
+        .any(|field| field.non_empty || field.one_of.is_some());
+
+    let mut out = String::new();
+    out.push_str("// @generated by legend-saga-codegen from schema/events.json, do not edit.\n");
+    out.push_str("// Change the schema and run `make generate-events` instead.\n\n");
+    let checks = if constrained {
+        "non_empty, one_of, "
+    } else {
+        ""
+    };
+    writeln!(
+        out,
+        "use super::{{{checks}PayloadEvent, Validate, ValidationError}};"
+    )
+    .unwrap();
+    if uses_type("DateTime<") {
+        out.push_str("use chrono::{DateTime, Utc};\n");
+    }
+    out.push_str("use serde::{Deserialize, Serialize};\n");
+    if uses_type("HashMap<") {
+        out.push_str("use std::collections::HashMap;\n");
+    }
+    out.push_str("use strum_macros::{AsRefStr, EnumIter, EnumString};\n\n");
+
+    out.push_str("/// Represents the available events in the system.\n");
+    out.push_str(
+        "#[derive(Debug, Clone, Copy, AsRefStr, EnumString, PartialEq, EnumIter, Hash, Eq)]\n",
+    );
+    out.push_str("#[strum(serialize_all = \"snake_case\")]\npub enum MicroserviceEvent {\n");
+    for event in &schema.events {
+        if let Some(section) = &event.section {
+            writeln!(out, "    // {section}").unwrap();
+        }
+        write_doc(&mut out, "    ", event.doc.as_deref());
+        writeln!(out, "    #[strum(serialize = \"{}\")]", event.name).unwrap();
+        writeln!(out, "    {},", variant_name(&event.name)).unwrap();
+    }
+    out.push_str("}\n\n");
+    write_schema_versions(&mut out, schema);
+    out.push('\n');
+    write_payload_schemas(&mut out, schema);
+
+    for event in &schema.events {
+        out.push('\n');
+        write_type(&mut out, &event.payload, Some(&variant_name(&event.name)));
+        write_validate(&mut out, &event.payload, &structs);
+    }
+    for t in &schema.types {
+        out.push('\n');
+        write_type(&mut out, t, None);
+        write_validate(&mut out, t, &structs);
+    }
+
+    out.push_str("\nany_event! {\n");
+    for event in &schema.events {
+        writeln!(
+            out,
+            "    {}({}),",
+            variant_name(&event.name),
+            event.payload.name
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+fn validate(schema: &Schema) -> Result<(), String> {
+    let mut events = HashSet::new();
+    for event in &schema.events {
+        let valid = event.name.split_once('.').is_some_and(|(domain, name)| {
+            [domain, name].iter().all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            })
+        });
+        if !valid {
+            return Err(format!(
+                "invalid event name {:?}, expected <domain>.<event> in snake_case",
+                event.name
+            ));
+        }
+        if !events.insert(variant_name(&event.name)) {
+            return Err(format!("event {:?} is defined twice", event.name));
+        }
+        if event.version == Some(0) {
+            return Err(format!("the versions of {:?} start at 1", event.name));
+        }
+        if event.payload.fields.is_none() {
+            return Err(format!("the payload of {:?} must be a struct", event.name));
+        }
+    }
+
+    let types: Vec<&TypeDef> = schema
+        .events
+        .iter()
+        .map(|event| &event.payload)
+        .chain(&schema.types)
+        .collect();
+    let mut names = HashSet::new();
+    for t in &types {
+        if !names.insert(t.name.as_str()) {
+            return Err(format!("type {} is defined twice", t.name));
+        }
+        if let Some(rule) = t.rename_all.as_deref() {
+            if !RENAME_RULES.contains(&rule) {
+                return Err(format!(
+                    "type {} has the unknown renameAll {rule:?}",
+                    t.name
+                ));
+            }
+        }
+        if t.fields.is_some() == t.variants.is_some() {
+            return Err(format!(
+                "type {} must have either fields or variants",
+                t.name
+            ));
+        }
+    }
+    for t in &types {
+        for field in t.fields.iter().flatten() {
+            let base = base_type(&field.ty);
+            if !PRIMITIVES.contains(&base) && !names.contains(base) {
+                return Err(format!(
+                    "field {}.{} has the unknown type {base:?}",
+                    t.name, field.name
+                ));
+            }
+            if (field.non_empty || field.one_of.is_some()) && field.ty != "string" {
+                return Err(format!(
+                    "field {}.{} has constraints but is not a string",
+                    t.name, field.name
+                ));
+            }
+            if field.one_of.as_ref().is_some_and(Vec::is_empty) {
+                return Err(format!("field {}.{} accepts no value", t.name, field.name));
+            }
+            if field.skip_if_none && !field.optional {
+                return Err(format!(
+                    "field {}.{} skips None but is not optional",
+                    t.name, field.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Maximum width of the lines of rustfmt.
+const MAX_WIDTH: usize = 100;
+
+/// The serde `rename_all` rules, plus `none`.
+const RENAME_RULES: [&str; 9] = [
+    "none",
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+const PRIMITIVES: [&str; 10] = [
+    "string", "json", "datetime", "bool", "i32", "i64", "u32", "u64", "f32", "f64",
+];
+
+/// The type of the schema a field type is made of, e.g. `string` for `map<string>[]`.
+fn base_type(ty: &str) -> &str {
+    if let Some(item) = ty.strip_suffix("[]") {
+        return base_type(item);
+    }
+    match ty.strip_prefix("map<").and_then(|t| t.strip_suffix('>')) {
+        Some(value) => base_type(value),
+        None => ty,
+    }
+}
+
+fn rust_type(ty: &str) -> String {
+    if let Some(item) = ty.strip_suffix("[]") {
+        return format!("Vec<{}>", rust_type(item));
+    }
+    if let Some(value) = ty.strip_prefix("map<").and_then(|t| t.strip_suffix('>')) {
+        return format!("HashMap<String, {}>", rust_type(value));
+    }
+    match ty {
+        "string" => "String".to_string(),
+        "json" => "serde_json::Value".to_string(),
+        "datetime" => "DateTime<Utc>".to_string(),
+        _ => ty.to_string(),
+    }
+}
+
+fn write_schema_versions(out: &mut String, schema: &Schema) {
+    let mut versions: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for event in &schema.events {
+        if let Some(version) = event.version.filter(|version| *version > 1) {
+            versions
+                .entry(version)
+                .or_default()
+                .push(format!("MicroserviceEvent::{}", variant_name(&event.name)));
+        }
+    }
+
+    out.push_str("impl MicroserviceEvent {\n");
+    out.push_str(
+        "    /// Version of the payload of the event, sent in the `x-schema-version` header.\n",
+    );
+    out.push_str("    pub fn schema_version(&self) -> u32 {\n");
+    if versions.is_empty() {
+        out.push_str("        1\n");
+    } else {
+        out.push_str("        match self {\n");
+        for (version, variants) in versions {
+            writeln!(
+                out,
+                "            {} => {version},",
+                variants.join("\n            | ")
+            )
+            .unwrap();
+        }
+        out.push_str("            _ => 1,\n        }\n");
+    }
+    out.push_str("    }\n}\n");
+}
+
+fn write_payload_schemas(out: &mut String, schema: &Schema) {
+    out.push_str("#[cfg(feature = \"json-schema\")]\nimpl MicroserviceEvent {\n");
+    out.push_str("    /// JSON Schema of the payload of the event.\n");
+    out.push_str("    pub fn payload_schema(&self) -> schemars::Schema {\n");
+    out.push_str("        match self {\n");
+    for event in &schema.events {
+        write_match_arm(
+            out,
+            "            ",
+            &format!("MicroserviceEvent::{}", variant_name(&event.name)),
+            &format!("schemars::schema_for!({})", event.payload.name),
+        );
+    }
+    out.push_str("        }\n    }\n}\n");
+}
+
+/// Writes a match arm the way rustfmt does, in a block when it does not fit in a line.
+fn write_match_arm(out: &mut String, indent: &str, pattern: &str, expression: &str) {
+    let line = format!("{indent}{pattern} => {expression},");
+    if line.len() <= MAX_WIDTH {
+        writeln!(out, "{line}").unwrap();
+    } else {
+        writeln!(
+            out,
+            "{indent}{pattern} => {{\n{indent}    {expression}\n{indent}}}"
+        )
+        .unwrap();
+    }
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    for line in doc.into_iter().flat_map(str::lines) {
+        match line {
+            "" => writeln!(out, "{indent}///").unwrap(),
+            line => writeln!(out, "{indent}/// {line}").unwrap(),
+        }
+    }
+}
+
+/// Writes a type of a validated schema, `event` is the variant of the payloads.
+fn write_type(out: &mut String, t: &TypeDef, event: Option<&str>) {
+    write_doc(out, "", t.doc.as_deref());
+    let derives = match (event, &t.variants) {
+        (Some(_), _) => "Debug, Serialize, Deserialize, Clone, PayloadEvent",
+        (None, Some(_)) => "Debug, Serialize, Deserialize, Clone, PartialEq, Eq",
+        (None, None) => "Debug, Serialize, Deserialize, Clone",
+    };
+    writeln!(out, "#[derive({derives})]").unwrap();
+    out.push_str("#[cfg_attr(feature = \"json-schema\", derive(schemars::JsonSchema))]\n");
+    if let Some(event) = event {
+        // The Validate impl comes from the constraints of the schema
+        writeln!(out, "#[event({event}, custom_validate, payload_of)]").unwrap();
+    }
+    write_rename_all(out, t);
+    match (&t.fields, &t.variants) {
+        (Some(fields), _) => {
+            writeln!(out, "pub struct {} {{", t.name).unwrap();
+            for field in fields {
+                let values = field.one_of.as_ref().map(|values| {
+                    let values: Vec<String> = values.iter().map(|v| format!("`{v}`")).collect();
+                    format!("One of {}.", values.join(", "))
+                });
+                let doc: Vec<&str> = [field.doc.as_deref(), values.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                write_doc(out, "    ", Some(&doc.join("\n\n")));
+                let mut attrs = Vec::new();
+                if field.default {
+                    attrs.push("default".to_string());
+                }
+                if let Some(rename) = &field.rename {
+                    attrs.push(format!("rename = \"{rename}\""));
+                }
+                if field.skip_if_none {
+                    attrs.push("skip_serializing_if = \"Option::is_none\"".to_string());
+                }
+                if !attrs.is_empty() {
+                    writeln!(out, "    #[serde({})]", attrs.join(", ")).unwrap();
+                }
+                let ty = rust_type(&field.ty);
+                match field.optional {
+                    true => writeln!(out, "    pub {}: Option<{ty}>,", field.name).unwrap(),
+                    false => writeln!(out, "    pub {}: {ty},", field.name).unwrap(),
+                }
+            }
+            out.push_str("}\n");
+        }
+        (None, Some(variants)) => {
+            writeln!(out, "pub enum {} {{", t.name).unwrap();
+            for variant in variants {
+                writeln!(out, "    {variant},").unwrap();
+            }
+            out.push_str("}\n");
+        }
+        (None, None) => unreachable!("validated types have fields or variants"),
+    }
+}
+
+/// Writes the `Validate` impl of a struct from the constraints of its fields, nested
+/// structs are validated too. Enums are valid once parsed.
+fn write_validate(out: &mut String, t: &TypeDef, structs: &HashSet<&str>) {
+    let Some(fields) = &t.fields else {
+        return;
+    };
+    let mut checks = Vec::new();
+    for field in fields {
+        let name = &field.name;
+        if field.non_empty {
+            checks.push(call(
+                "non_empty",
+                &[format!("\"{name}\""), format!("&self.{name}")],
+            ));
+        }
+        if let Some(values) = &field.one_of {
+            let values: Vec<String> = values.iter().map(|v| format!("\"{v}\"")).collect();
+            checks.push(call(
+                "one_of",
+                &[
+                    format!("\"{name}\""),
+                    format!("&self.{name}"),
+                    format!("&[{}]", values.join(", ")),
+                ],
+            ));
+        }
+        if structs.contains(base_type(&field.ty)) {
+            checks.push(format!("        self.{name}.validate()?;\n"));
+        }
+    }
+    out.push('\n');
+    if checks.is_empty() {
+        writeln!(out, "impl Validate for {} {{}}", t.name).unwrap();
+        return;
+    }
+    writeln!(out, "impl Validate for {} {{", t.name).unwrap();
+    out.push_str("    fn validate(&self) -> Result<(), ValidationError> {\n");
+    for check in checks {
+        out.push_str(&check);
+    }
+    out.push_str("        Ok(())\n    }\n}\n");
+}
+
+/// A `function(args)?;` statement of `validate`, formatted the way rustfmt does.
+fn call(function: &str, args: &[String]) -> String {
+    let indent = "        ";
+    let args_line = args.join(", ");
+    let line = format!("{indent}{function}({args_line})?;");
+    // rustfmt breaks the arguments vertically past `fn_call_width`
+    if args_line.len() <= 60 && line.len() <= MAX_WIDTH {
+        return line + "\n";
+    }
+    let mut call = format!("{indent}{function}(\n");
+    for arg in args {
+        writeln!(call, "{indent}    {arg},").unwrap();
+    }
+    call + indent + ")?;\n"
+}
+
+fn write_rename_all(out: &mut String, t: &TypeDef) {
+    match t.rename_all.as_deref() {
+        Some("none") => {}
+        rule => writeln!(
+            out,
+            "#[serde(rename_all = \"{}\")]",
+            rule.unwrap_or("camelCase")
+        )
+        .unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod test_codegen {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn generated_events_are_up_to_date() {
+        let root = workspace_root();
+        let schema = parse(&fs::read_to_string(root.join(SCHEMA_PATH)).unwrap()).unwrap();
+        let generated = fs::read_to_string(root.join(OUTPUT_PATH)).unwrap();
+        assert!(
+            generate(&schema).unwrap() == generated,
+            "{OUTPUT_PATH} is out of date, run `make generate-events`"
+        );
+    }
+
+    #[test]
+    fn variants_are_the_pascal_case_of_the_wire_name() {
+        assert_eq!(variant_name("auth.deleted_user"), "AuthDeletedUser");
+        assert_eq!(
+            variant_name("legend_missions.mission_finished"),
+            "LegendMissionsMissionFinished"
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_schemas() {
+        let error = |schema: &str| generate(&parse(schema).unwrap()).unwrap_err();
+        let event = |name: &str, ty: &str| {
+            format!(
+                r#"{{"name": "{name}", "payload": {{"name": "P{}", "fields": [{{"name": "a", "type": "{ty}"}}]}}}}"#,
+                variant_name(name)
+            )
+        };
+
+        let twice = format!(
+            r#"{{"events": [{}, {}]}}"#,
+            event("auth.new_user", "string"),
+            event("auth.new_user", "string")
+        );
+        assert!(error(&twice).contains("defined twice"));
+        let unknown = format!(r#"{{"events": [{}]}}"#, event("auth.new_user", "User[]"));
+        assert!(error(&unknown).contains("unknown type \"User\""));
+        let invalid = format!(r#"{{"events": [{}]}}"#, event("AuthNewUser", "string"));
+        assert!(error(&invalid).contains("invalid event name"));
+        let constrained = format!(r#"{{"events": [{}]}}"#, event("auth.new_user", "i64"))
+            .replace(r#""type": "i64""#, r#""type": "i64", "nonEmpty": true"#);
+        assert!(error(&constrained).contains("has constraints but is not a string"));
+        assert!(parse(r#"{"events": [], "typo": 1}"#).is_err());
+    }
+}
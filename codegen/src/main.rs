@@ -0,0 +1,43 @@
+//! Writes `legend-saga/src/events/generated.rs` from `schema/events.json`. With `--check`
+//! it only fails if the file is out of date.
+
+use legend_saga_codegen::{generate, parse, workspace_root, OUTPUT_PATH, SCHEMA_PATH};
+use std::fs;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let check = match std::env::args().nth(1).as_deref() {
+        None => false,
+        Some("--check") => true,
+        Some(arg) => {
+            eprintln!("unknown argument {arg:?}, usage: generate-events [--check]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let root = workspace_root();
+    let generated = fs::read_to_string(root.join(SCHEMA_PATH))
+        .map_err(|e| format!("cannot read {SCHEMA_PATH}: {e}"))
+        .and_then(|schema| generate(&parse(&schema)?));
+    let generated = match generated {
+        Ok(generated) => generated,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = root.join(OUTPUT_PATH);
+    if check {
+        if fs::read_to_string(&output).ok().as_deref() != Some(generated.as_str()) {
+            eprintln!("{OUTPUT_PATH} is out of date, run `make generate-events`");
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+    if let Err(e) = fs::write(&output, generated) {
+        eprintln!("cannot write {OUTPUT_PATH}: {e}");
+        return ExitCode::FAILURE;
+    }
+    println!("{OUTPUT_PATH} generated from {SCHEMA_PATH}");
+    ExitCode::SUCCESS
+}
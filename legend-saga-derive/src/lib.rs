@@ -0,0 +1,291 @@
+//! Derive macros for the payloads of legend-saga, re-exported by it next to the traits
+//! they implement: `legend_saga::events::PayloadEvent` and
+//! `legend_saga::commence_saga::PayloadCommenceSaga`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, DeriveInput, Ident, Token};
+
+/// Option of `#[event(...)]` that leaves the `Validate` impl to the payload.
+const CUSTOM_VALIDATE: &str = "custom_validate";
+/// Option of `#[event(...)]` that maps the event to the payload for `payload_of!`.
+const PAYLOAD_OF: &str = "payload_of";
+
+/// Implements `PayloadEvent` and `TypedPayloadEvent` for the event named by the
+/// `#[event(...)]` attribute. The payload also gets a `Validate` impl that accepts every
+/// value, unless the attribute lists `custom_validate` to implement it by hand.
+///
+/// The `payload_of` option maps the event to the payload for `payload_of!`. It is meant for
+/// the schema payloads of legend-saga only: the mapping is implemented on
+/// `MicroserviceEvent`, which the orphan rule forbids outside of legend-saga.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, PayloadEvent)]
+/// #[event(AuthDeletedUser)]
+/// pub struct AuthDeletedUserPayload {
+///     pub user_id: String,
+/// }
+///
+/// #[derive(Serialize, Deserialize, PayloadEvent)]
+/// #[event(AuthLogoutUser, custom_validate)]
+/// pub struct AuthLogoutUserPayload { /* ... */ }
+///
+/// impl Validate for AuthLogoutUserPayload { /* ... */ }
+/// ```
+#[proc_macro_derive(PayloadEvent, attributes(event))]
+pub fn derive_payload_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    payload_event(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `PayloadCommenceSaga` and `TypedPayloadCommenceSaga` for the saga named by
+/// the `#[saga(...)]` attribute.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, PayloadCommenceSaga)]
+/// #[saga(TransferCryptoRewardToMissionWinner)]
+/// pub struct TransferCryptoRewardToMissionWinnerPayload { /* ... */ }
+/// ```
+#[proc_macro_derive(PayloadCommenceSaga, attributes(saga))]
+pub fn derive_payload_commence_saga(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    payload_commence_saga(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn payload_event(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let (event, options) = variant_with_options(input, "event", "MicroserviceEvent")?;
+    let mut custom_validate = false;
+    let mut payload_of = false;
+    for option in options {
+        if option == CUSTOM_VALIDATE {
+            custom_validate = true;
+        } else if option == PAYLOAD_OF {
+            payload_of = true;
+        } else {
+            return Err(syn::Error::new_spanned(
+                &option,
+                format!("unknown option {option}, expected {CUSTOM_VALIDATE} or {PAYLOAD_OF}"),
+            ));
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let validate = if custom_validate {
+        quote! {}
+    } else {
+        quote! {
+            impl #impl_generics ::legend_saga::events::Validate for #name #ty_generics #where_clause {}
+        }
+    };
+    if payload_of && !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "a generic payload cannot be the payload_of its event",
+        ));
+    }
+    let mapping = if payload_of {
+        quote! {
+            impl ::legend_saga::events::EventPayloadMap<
+                { ::legend_saga::events::MicroserviceEvent::#event as u16 },
+            > for ::legend_saga::events::MicroserviceEvent {
+                type Payload = #name;
+            }
+        }
+    } else {
+        quote! {}
+    };
+    Ok(quote! {
+        impl #impl_generics ::legend_saga::events::PayloadEvent for #name #ty_generics #where_clause {
+            fn event_type(&self) -> ::legend_saga::events::MicroserviceEvent {
+                ::legend_saga::events::MicroserviceEvent::#event
+            }
+        }
+
+        impl #impl_generics ::legend_saga::events::TypedPayloadEvent for #name #ty_generics #where_clause {
+            const EVENT: ::legend_saga::events::MicroserviceEvent =
+                ::legend_saga::events::MicroserviceEvent::#event;
+        }
+
+        #validate
+
+        #mapping
+    })
+}
+
+fn payload_commence_saga(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let title = variant(input, "saga", "SagaTitle")?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::legend_saga::commence_saga::PayloadCommenceSaga for #name #ty_generics #where_clause {
+            fn saga_title(&self) -> ::legend_saga::commence_saga::SagaTitle {
+                ::legend_saga::commence_saga::SagaTitle::#title
+            }
+        }
+
+        impl #impl_generics ::legend_saga::commence_saga::TypedPayloadCommenceSaga for #name #ty_generics #where_clause {
+            const TITLE: ::legend_saga::commence_saga::SagaTitle =
+                ::legend_saga::commence_saga::SagaTitle::#title;
+        }
+    })
+}
+
+/// The variant of `enum_name` given by the single `#[attribute(Variant)]` of the type.
+fn variant(input: &DeriveInput, attribute: &str, enum_name: &str) -> syn::Result<Ident> {
+    let (variant, options) = variant_with_options(input, attribute, enum_name)?;
+    match options.first() {
+        Some(option) => Err(syn::Error::new_spanned(
+            option,
+            format!("#[{attribute}(...)] takes no option"),
+        )),
+        None => Ok(variant),
+    }
+}
+
+/// The variant of `enum_name` and the options that follow it in the single
+/// `#[attribute(Variant, option, ...)]` of the type.
+fn variant_with_options(
+    input: &DeriveInput,
+    attribute: &str,
+    enum_name: &str,
+) -> syn::Result<(Ident, Vec<Ident>)> {
+    let mut attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident(attribute));
+    let Some(attr) = attrs.next() else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("missing #[{attribute}(...)] with the {enum_name} variant of the payload"),
+        ));
+    };
+    if let Some(duplicate) = attrs.next() {
+        return Err(syn::Error::new_spanned(
+            duplicate,
+            format!("#[{attribute}(...)] is given more than once"),
+        ));
+    }
+    let expected = |span| {
+        syn::Error::new(
+            span,
+            format!("expected #[{attribute}(Variant)] with a variant of {enum_name}"),
+        )
+    };
+    let mut args = attr
+        .parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)
+        .map_err(|e| expected(e.span()))?
+        .into_iter();
+    let variant = args
+        .next()
+        .ok_or_else(|| expected(attr.bracket_token.span.join()))?;
+    Ok((variant, args.collect()))
+}
+
+#[cfg(test)]
+mod test_derive {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn payload_event_names_the_event() {
+        let input: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser)]
+            struct AuthDeletedUserPayload {
+                user_id: String,
+            }
+        };
+        let expanded = payload_event(&input).unwrap().to_string();
+        assert!(expanded.contains("MicroserviceEvent :: AuthDeletedUser"));
+        assert!(expanded.contains("Validate for AuthDeletedUserPayload { }"));
+        assert!(!expanded.contains("EventPayloadMap"));
+    }
+
+    #[test]
+    fn payload_of_maps_the_event_to_the_payload() {
+        let input: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser, custom_validate, payload_of)]
+            struct AuthDeletedUserPayload {
+                user_id: String,
+            }
+        };
+        let expanded = payload_event(&input).unwrap().to_string();
+        assert!(expanded.contains("type Payload = AuthDeletedUserPayload"));
+        assert!(!expanded.contains("Validate for"));
+
+        let generic: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser, payload_of)]
+            struct Payload<T> {
+                value: T,
+            }
+        };
+        assert!(payload_event(&generic).is_err());
+    }
+
+    #[test]
+    fn custom_validate_leaves_validate_to_the_payload() {
+        let input: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser, custom_validate)]
+            struct AuthDeletedUserPayload {
+                user_id: String,
+            }
+        };
+        let expanded = payload_event(&input).unwrap().to_string();
+        assert!(expanded.contains("TypedPayloadEvent for AuthDeletedUserPayload"));
+        assert!(!expanded.contains("Validate"));
+
+        let unknown: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser, skip_validate)]
+            struct Payload;
+        };
+        assert!(payload_event(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown option skip_validate"));
+    }
+
+    #[test]
+    fn generic_payloads_are_not_mapped() {
+        let input: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser)]
+            struct Payload<T> {
+                value: T,
+            }
+        };
+        let expanded = payload_event(&input).unwrap().to_string();
+        assert!(expanded.contains("TypedPayloadEvent for Payload < T >"));
+        assert!(!expanded.contains("EventPayloadMap"));
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_attributes() {
+        let missing: DeriveInput = parse_quote! {
+            struct Payload;
+        };
+        assert!(payload_commence_saga(&missing)
+            .unwrap_err()
+            .to_string()
+            .contains("missing #[saga(...)]"));
+
+        let invalid: DeriveInput = parse_quote! {
+            #[event("auth.deleted_user")]
+            struct Payload;
+        };
+        assert!(payload_event(&invalid)
+            .unwrap_err()
+            .to_string()
+            .contains("expected #[event(Variant)]"));
+
+        let twice: DeriveInput = parse_quote! {
+            #[event(AuthDeletedUser)]
+            #[event(AuthLogoutUser)]
+            struct Payload;
+        };
+        assert!(payload_event(&twice).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Parsing of large event payloads and saga step payloads by the handlers: the map round
+//! trip they used to do, against parsing the received JSON straight into the payload with
+//! `RawPayload`.
+//!
+//! `make bench`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use legend_saga::events::LegendMissionsMissionFinishedEventPayload;
+use legend_saga::raw_payload::RawPayload;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::hint::black_box;
+
+fn mission_finished(participants: usize) -> Vec<u8> {
+    let participants: Vec<Value> = (0..participants)
+        .map(|i| json!({"userId": format!("user{i}"), "email": format!("user{i}@legend.com"), "position": i}))
+        .collect();
+    serde_json::to_vec(&json!({
+        "missionTitle": "Weekly mission",
+        "participants": participants,
+        "rewardKind": "coins",
+    }))
+    .unwrap()
+}
+
+/// The fields of a saga step the participants read, the previous payload as `P`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SagaStep<P> {
+    saga_id: i32,
+    previous_payload: P,
+}
+
+fn saga_step(participants: usize) -> Vec<u8> {
+    let previous_payload: Value = serde_json::from_slice(&mission_finished(participants)).unwrap();
+    serde_json::to_vec(&json!({
+        "microservice": "legend-missions",
+        "command": "mint_image",
+        "status": "sent",
+        "sagaId": 1,
+        "payload": {},
+        "previousPayload": previous_payload,
+        "isCurrentStep": true,
+    }))
+    .unwrap()
+}
+
+fn parse_payload(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_payload");
+    for participants in [10, 1_000, 10_000] {
+        let body = mission_finished(participants);
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("map_round_trip", participants),
+            &body,
+            |b, body| {
+                b.iter(|| {
+                    let map: HashMap<String, Value> = serde_json::from_slice(body).unwrap();
+                    let value = serde_json::to_value(map.clone()).unwrap();
+                    let payload: LegendMissionsMissionFinishedEventPayload =
+                        serde_json::from_value(value).unwrap();
+                    black_box(payload)
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("raw_payload", participants),
+            &body,
+            |b, body| {
+                b.iter(|| {
+                    let payload: LegendMissionsMissionFinishedEventPayload =
+                        RawPayload::from_json(body).unwrap().parse().unwrap();
+                    black_box(payload)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn parse_saga_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_saga_step");
+    for participants in [10, 1_000, 10_000] {
+        let body = saga_step(participants);
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("map_round_trip", participants),
+            &body,
+            |b, body| {
+                b.iter(|| {
+                    let step: SagaStep<HashMap<String, Value>> =
+                        serde_json::from_slice(body).unwrap();
+                    let value = serde_json::to_value(step.previous_payload.clone()).unwrap();
+                    let payload: LegendMissionsMissionFinishedEventPayload =
+                        serde_json::from_value(value).unwrap();
+                    black_box((step.saga_id, payload))
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("raw_payload", participants),
+            &body,
+            |b, body| {
+                b.iter(|| {
+                    let step: SagaStep<Box<RawValue>> = serde_json::from_slice(body).unwrap();
+                    let payload: LegendMissionsMissionFinishedEventPayload =
+                        RawPayload::from_raw(step.previous_payload)
+                            .unwrap()
+                            .parse()
+                            .unwrap();
+                    black_box((step.saga_id, payload))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_payload, parse_saga_step);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+//! A service crate deriving `PayloadEvent` on its own payload. Examples build as crates of
+//! their own, so this keeps the derive usable outside of legend-saga.
+
+use legend_saga::events::{MicroserviceEvent, PayloadEvent, TypedPayloadEvent, Validate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PayloadEvent)]
+#[event(AuthDeletedUser)]
+#[serde(rename_all = "camelCase")]
+struct DeletedUser {
+    user_id: String,
+}
+
+fn main() {
+    let payload = DeletedUser {
+        user_id: "user1".to_string(),
+    };
+    assert_eq!(payload.event_type(), MicroserviceEvent::AuthDeletedUser);
+    assert_eq!(DeletedUser::EVENT, MicroserviceEvent::AuthDeletedUser);
+    payload.validate().unwrap();
+    println!("{payload:?} is the payload of {}", payload.event_type().as_ref());
+}
@@ -0,0 +1,23 @@
+//! Writes the JSON Schema catalog of the event and saga payloads to the path given as
+//! argument, `schema/json-schema.json` by default.
+
+use std::fs;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "schema/json-schema.json".to_string());
+    let catalog = legend_saga::json_schema::catalog();
+    let json = serde_json::to_string_pretty(&catalog).expect("schemas serialize to JSON");
+    if let Err(e) = fs::write(&path, json + "\n") {
+        eprintln!("cannot write {path}: {e}");
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "JSON Schema of {} events and {} sagas written to {path}",
+        catalog.events.len(),
+        catalog.sagas.len()
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,336 @@
+use crate::connection::RabbitMQClient;
+use crate::events::MicroserviceEvent;
+use crate::saga::StepCommand;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Configuration of the circuit breaker of one event or saga command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures (nacks) that open the breaker.
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before letting a single probe through.
+    pub cooldown: Duration,
+    /// Delay applied to the deliveries requeued while the breaker does not dispatch.
+    pub requeue_delay: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+            requeue_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Deliveries are dispatched to the handler.
+    Closed,
+    /// Deliveries are requeued with delay without reaching the handler.
+    Open,
+    /// The cooldown elapsed, a single probe is dispatched to decide whether to close the breaker.
+    HalfOpen,
+}
+
+/// Snapshot of a circuit breaker, exposed in the health output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CircuitBreakerStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+/// What to do with a delivery of an event guarded by a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Dispatch,
+    /// Requeue the delivery with this delay, without counting it as a retry.
+    Postpone(Duration),
+}
+
+#[derive(Debug)]
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open probe was dispatched, a probe that never acks nor nacks
+    /// is given up after another cooldown.
+    probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started_at: None,
+        }
+    }
+
+    fn admit(&mut self, now: Instant) -> Admission {
+        match self.state {
+            CircuitState::Closed => Admission::Dispatch,
+            CircuitState::Open => {
+                let cooling = self
+                    .opened_at
+                    .is_some_and(|opened_at| now.duration_since(opened_at) < self.config.cooldown);
+                if cooling {
+                    return Admission::Postpone(self.config.requeue_delay);
+                }
+                self.state = CircuitState::HalfOpen;
+                self.probe_started_at = Some(now);
+                Admission::Dispatch
+            }
+            CircuitState::HalfOpen => {
+                let probing = self
+                    .probe_started_at
+                    .is_some_and(|started| now.duration_since(started) < self.config.cooldown);
+                if probing {
+                    return Admission::Postpone(self.config.requeue_delay);
+                }
+                self.probe_started_at = Some(now);
+                Admission::Dispatch
+            }
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.probe_started_at = None;
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let failed_probe = self.state == CircuitState::HalfOpen;
+        if failed_probe || self.consecutive_failures >= self.config.failure_threshold {
+            self.state = CircuitState::Open;
+            self.opened_at = Some(now);
+            self.probe_started_at = None;
+        }
+    }
+
+    fn status(&self) -> CircuitBreakerStatus {
+        CircuitBreakerStatus {
+            state: self.state,
+            consecutive_failures: self.consecutive_failures,
+        }
+    }
+}
+
+/// The circuit breakers of a client, keyed by the wire name of the event or saga command.
+/// Events and commands without a registered breaker are always dispatched.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CircuitBreakers {
+    breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
+}
+
+impl CircuitBreakers {
+    fn register(&self, key: &str, config: CircuitBreakerConfig) {
+        self.breakers
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), CircuitBreaker::new(config));
+    }
+
+    pub(crate) fn admit(&self, key: &str) -> Admission {
+        match self.breakers.lock().unwrap().get_mut(key) {
+            Some(breaker) => breaker.admit(Instant::now()),
+            None => Admission::Dispatch,
+        }
+    }
+
+    pub(crate) fn record_success(&self, key: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(key) {
+            if breaker.state != CircuitState::Closed {
+                info!("Circuit breaker of {} closed", key);
+            }
+            breaker.record_success();
+        }
+    }
+
+    pub(crate) fn record_failure(&self, key: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(key) {
+            let was_open = breaker.state == CircuitState::Open;
+            breaker.record_failure(Instant::now());
+            if !was_open && breaker.state == CircuitState::Open {
+                warn!(
+                    "Circuit breaker of {} opened after {} consecutive failures",
+                    key, breaker.consecutive_failures
+                );
+            }
+        }
+    }
+
+    pub(crate) fn statuses(&self) -> BTreeMap<String, CircuitBreakerStatus> {
+        self.breakers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, breaker)| (key.clone(), breaker.status()))
+            .collect()
+    }
+}
+
+impl RabbitMQClient {
+    /// Guards the handler of `event` with a circuit breaker. After `failure_threshold`
+    /// consecutive nacks the deliveries of the event are requeued with delay instead of
+    /// being dispatched, until a probe succeeds after the cooldown.
+    ///
+    /// An open breaker does not pause the consumer: the queue of a microservice carries every
+    /// event it listens to, pausing it would also stop the events whose handlers are healthy.
+    /// Each delivery of the event is still received and republished to the requeue exchange
+    /// with [`CircuitBreakerConfig::requeue_delay`], which bounds how often it comes back.
+    pub fn enable_event_circuit_breaker(
+        &self,
+        event: MicroserviceEvent,
+        config: CircuitBreakerConfig,
+    ) {
+        self.circuit_breakers.register(event.as_ref(), config);
+    }
+
+    /// Guards the handler of the saga `command` with a circuit breaker, see
+    /// [`RabbitMQClient::enable_event_circuit_breaker`].
+    pub fn enable_saga_circuit_breaker(&self, command: StepCommand, config: CircuitBreakerConfig) {
+        self.circuit_breakers.register(command.as_ref(), config);
+    }
+
+    /// State of every registered circuit breaker, keyed by event or command.
+    pub fn circuit_breakers(&self) -> BTreeMap<String, CircuitBreakerStatus> {
+        self.circuit_breakers.statuses()
+    }
+}
+
+#[cfg(test)]
+mod test_circuit_breaker {
+    use super::*;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(10),
+            requeue_delay: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(config());
+
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert_eq!(breaker.admit(now), Admission::Dispatch);
+
+        breaker.record_failure(now);
+        assert_eq!(breaker.status().state, CircuitState::Open);
+        assert_eq!(
+            breaker.admit(now + Duration::from_secs(5)),
+            Admission::Postpone(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn success_resets_the_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(config());
+
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        breaker.record_success();
+        breaker.record_failure(now);
+
+        assert_eq!(
+            breaker.status(),
+            CircuitBreakerStatus {
+                state: CircuitState::Closed,
+                consecutive_failures: 1
+            }
+        );
+    }
+
+    #[test]
+    fn half_open_probe_closes_on_success() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(config());
+        for _ in 0..3 {
+            breaker.record_failure(now);
+        }
+
+        let after_cooldown = now + Duration::from_secs(10);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Dispatch);
+        assert_eq!(breaker.status().state, CircuitState::HalfOpen);
+        // only one probe at a time
+        assert_eq!(
+            breaker.admit(after_cooldown),
+            Admission::Postpone(Duration::from_secs(1))
+        );
+
+        breaker.record_success();
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Dispatch);
+    }
+
+    #[test]
+    fn half_open_probe_reopens_on_failure() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(config());
+        for _ in 0..3 {
+            breaker.record_failure(now);
+        }
+
+        let after_cooldown = now + Duration::from_secs(10);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Dispatch);
+        breaker.record_failure(after_cooldown);
+
+        assert_eq!(breaker.status().state, CircuitState::Open);
+        assert_eq!(
+            breaker.admit(after_cooldown + Duration::from_secs(9)),
+            Admission::Postpone(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn lost_probe_is_retried_after_cooldown() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(config());
+        for _ in 0..3 {
+            breaker.record_failure(now);
+        }
+
+        let after_cooldown = now + Duration::from_secs(10);
+        assert_eq!(breaker.admit(after_cooldown), Admission::Dispatch);
+        assert_eq!(
+            breaker.admit(after_cooldown + Duration::from_secs(20)),
+            Admission::Dispatch
+        );
+    }
+
+    #[test]
+    fn unregistered_keys_are_always_dispatched() {
+        let breakers = CircuitBreakers::default();
+        breakers.record_failure("social.new_user");
+        assert_eq!(breakers.admit("social.new_user"), Admission::Dispatch);
+        assert!(breakers.statuses().is_empty());
+
+        breakers.register("social.new_user", config());
+        for _ in 0..3 {
+            breakers.record_failure("social.new_user");
+        }
+        assert_eq!(breakers.statuses()["social.new_user"].state, CircuitState::Open);
+        assert!(matches!(
+            breakers.admit("social.new_user"),
+            Admission::Postpone(_)
+        ));
+    }
+}
@@ -1,27 +1,69 @@
 use crate::queue_consumer_props::Queue;
 use crate::operation::operation_headers;
 use lapin::{options::BasicPublishOptions, BasicProperties};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use strum_macros::{AsRefStr, EnumIter, EnumString};
+use strum::EnumMessage;
+use strum_macros::{EnumIter, EnumMessage, EnumString};
+use crate::registry::RegisteredName;
 use crate::connection::{get_or_init_publish_channel, RabbitMQClient, RabbitMQError};
+use crate::saga_handle::SagaHandle;
+use uuid::Uuid;
+
+pub use legend_saga_derive::PayloadCommenceSaga;
 
 #[derive(
-    Debug, Clone, Copy, AsRefStr, EnumString, PartialEq, EnumIter, Hash, Eq, Deserialize, Serialize,
+    Debug, Clone, Copy, EnumMessage, EnumString, PartialEq, EnumIter, Hash, Eq, Deserialize, Serialize,
 )]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum SagaTitle {
     TransferCryptoRewardToMissionWinner,
-    TransferCryptoRewardToRankingWinners
+    TransferCryptoRewardToRankingWinners,
+    /// A saga declared by a service, see [`crate::registry::register_saga_title`].
+    #[strum(disabled)]
+    #[serde(untagged, deserialize_with = "crate::registry::deserialize_saga_title")]
+    Custom(RegisteredName),
+}
+
+impl AsRef<str> for SagaTitle {
+    fn as_ref(&self) -> &str {
+        match self {
+            SagaTitle::Custom(name) => name.as_str(),
+            builtin => builtin.get_serializations()[0],
+        }
+    }
+}
+
+impl SagaTitle {
+    /// JSON Schema of the payload the saga is commenced with, `None` for registered sagas.
+    #[cfg(feature = "json-schema")]
+    pub fn payload_schema(&self) -> Option<schemars::Schema> {
+        match self {
+            SagaTitle::TransferCryptoRewardToMissionWinner => {
+                Some(schemars::schema_for!(TransferCryptoRewardToMissionWinnerPayload))
+            }
+            SagaTitle::TransferCryptoRewardToRankingWinners => {
+                Some(schemars::schema_for!(TransferCryptoRewardToRankingWinnersPayload))
+            }
+            SagaTitle::Custom(_) => None,
+        }
+    }
 }
 
 pub trait PayloadCommenceSaga {
     fn saga_title(&self) -> SagaTitle;
 }
 
+/// A payload whose saga is known at compile time, implemented by
+/// `#[derive(PayloadCommenceSaga)]`.
+pub trait TypedPayloadCommenceSaga: PayloadCommenceSaga + Serialize + DeserializeOwned {
+    const TITLE: SagaTitle;
+}
 
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadCommenceSaga)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[saga(TransferCryptoRewardToMissionWinner)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferCryptoRewardToMissionWinnerPayload {
     /// Wallet address from which rewards will be transferred
@@ -32,13 +74,8 @@ pub struct TransferCryptoRewardToMissionWinnerPayload {
     pub reward: String,
 }
 
-impl PayloadCommenceSaga for TransferCryptoRewardToMissionWinnerPayload {
-    fn saga_title(&self) -> SagaTitle {
-        SagaTitle::TransferCryptoRewardToMissionWinner
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CryptoRankingWinners {
     pub user_id: String,
@@ -46,28 +83,31 @@ pub struct CryptoRankingWinners {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CompletedCryptoRanking {
     pub wallet_address: String,
     pub winners: Vec<CryptoRankingWinners>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadCommenceSaga)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[saga(TransferCryptoRewardToRankingWinners)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferCryptoRewardToRankingWinnersPayload {
     pub completed_crypto_rankings: Vec<CompletedCryptoRanking>,
 }
 
-impl PayloadCommenceSaga for TransferCryptoRewardToRankingWinnersPayload {
-    fn saga_title(&self) -> SagaTitle {
-        SagaTitle::TransferCryptoRewardToRankingWinners
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize)]
-struct CommenceSaga<T> {
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommenceSaga<T> {
     pub title: SagaTitle,
     pub payload: T, // The payload is a JSON object, Value
+    /// Identifies the saga in the updates sent to `reply_to`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
 }
 
 impl RabbitMQClient {
@@ -92,6 +132,8 @@ impl RabbitMQClient {
 
         Ok(())
     }
+    /// Asks the orchestrator to run the saga of `payload`, see
+    /// [`RabbitMQClient::commence_tracked_saga`] to follow it.
     pub async fn commence_saga<T: PayloadCommenceSaga + Serialize>(
         payload: T,
     ) -> Result<(), RabbitMQError> {
@@ -99,11 +141,35 @@ impl RabbitMQClient {
             Queue::COMMENCE_SAGA,
             &CommenceSaga {
                 title: payload.saga_title(),
-                payload: serde_json::to_value(&payload)?,
+                payload,
+                correlation_id: None,
+                reply_to: None,
+            },
+        )
+        .await
+    }
+
+    /// Like [`RabbitMQClient::commence_saga`], the returned handle follows the saga until it
+    /// finishes. The first call declares the exclusive queue the orchestrator reports to.
+    pub async fn commence_tracked_saga<T: PayloadCommenceSaga + Serialize>(
+        payload: T,
+    ) -> Result<SagaHandle, RabbitMQError> {
+        let title = payload.saga_title();
+        let correlation_id = Uuid::now_v7().to_string();
+        // Dropped with the handle if the saga cannot be commenced
+        let (reply_to, handle) = Self::track_saga(title, correlation_id.clone()).await?;
+
+        Self::send(
+            Queue::COMMENCE_SAGA,
+            &CommenceSaga {
+                title,
+                payload,
+                correlation_id: Some(correlation_id),
+                reply_to: Some(reply_to),
             },
         )
         .await?;
-        Ok(())
+        Ok(handle)
     }
 }
 
@@ -0,0 +1,253 @@
+//! Compression of large payloads. Once enabled with [`RabbitMQClient::set_compression`],
+//! the events and saga steps published above a size threshold are compressed and sent with
+//! their `content_encoding`, and every consumer decompresses the deliveries by it.
+
+use crate::connection::{RabbitMQClient, RabbitMQError};
+use lapin::BasicProperties;
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+    /// Requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl ContentEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => "zstd",
+        }
+    }
+
+    /// The encoding of a delivery, `None` when it is not compressed.
+    pub fn from_content_encoding(
+        content_encoding: Option<&str>,
+    ) -> Result<Option<Self>, RabbitMQError> {
+        let Some(content_encoding) = content_encoding else {
+            return Ok(None);
+        };
+        match content_encoding.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(None),
+            #[cfg(feature = "gzip")]
+            "gzip" => Ok(Some(ContentEncoding::Gzip)),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Some(ContentEncoding::Zstd)),
+            _ => Err(RabbitMQError::UnsupportedContentEncoding(
+                content_encoding.to_string(),
+            )),
+        }
+    }
+
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, RabbitMQError> {
+        match *self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => {
+                use std::io::Write;
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(data)
+                    .and_then(|()| encoder.finish())
+                    .map_err(|e| self.error(e))
+            }
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => zstd::encode_all(data, 0).map_err(|e| self.error(e)),
+        }
+    }
+
+    /// Fails rather than decompress more than `limit` bytes, a small delivery can expand to
+    /// gigabytes.
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, RabbitMQError> {
+        match *self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => self.read_limited(flate2::read::GzDecoder::new(data), limit),
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => {
+                let decoder = zstd::stream::read::Decoder::new(data).map_err(|e| self.error(e))?;
+                self.read_limited(decoder, limit)
+            }
+        }
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn read_limited(&self, decoder: impl std::io::Read, limit: usize) -> Result<Vec<u8>, RabbitMQError> {
+        use std::io::Read;
+        let mut decompressed = Vec::new();
+        decoder
+            .take(limit as u64 + 1)
+            .read_to_end(&mut decompressed)
+            .map_err(|e| self.error(e))?;
+        if decompressed.len() > limit {
+            return Err(RabbitMQError::CompressionError {
+                content_encoding: self.as_str(),
+                reason: format!("the payload exceeds {limit} bytes once decompressed"),
+            });
+        }
+        Ok(decompressed)
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn error(&self, error: std::io::Error) -> RabbitMQError {
+        RabbitMQError::CompressionError {
+            content_encoding: self.as_str(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// Deliveries larger than this once decompressed are rejected, see
+/// [`Compression::max_decompressed_size`].
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compression of the payloads published, see [`RabbitMQClient::set_compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub encoding: ContentEncoding,
+    /// Payloads smaller than this, in bytes, are sent as they are.
+    pub threshold: usize,
+    /// Deliveries that decompress to more bytes than this are quarantined. Consumers without
+    /// compression enabled use [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub max_decompressed_size: usize,
+}
+
+impl Compression {
+    /// Compresses with `encoding` from `threshold` bytes on, with the default limit of
+    /// decompression.
+    pub fn new(encoding: ContentEncoding, threshold: usize) -> Self {
+        Self {
+            encoding,
+            threshold,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+        }
+    }
+}
+
+// Shared by the clients of the process, like the publish channel
+static COMPRESSION: RwLock<Option<Compression>> = RwLock::new(None);
+
+/// Compresses `body` if compression is enabled and it reaches the threshold, setting the
+/// content encoding of the message.
+pub(crate) fn compress(
+    body: Vec<u8>,
+    properties: BasicProperties,
+) -> Result<(Vec<u8>, BasicProperties), RabbitMQError> {
+    match *COMPRESSION.read().unwrap() {
+        Some(compression) if body.len() >= compression.threshold => {
+            let encoding = compression.encoding;
+            Ok((
+                encoding.compress(&body)?,
+                properties.with_content_encoding(encoding.as_str().into()),
+            ))
+        }
+        _ => Ok((body, properties)),
+    }
+}
+
+/// The body of a delivery, decompressed by its content encoding.
+pub(crate) fn decompress<'a>(
+    content_encoding: Option<&str>,
+    data: &'a [u8],
+) -> Result<Cow<'a, [u8]>, RabbitMQError> {
+    let Some(encoding) = ContentEncoding::from_content_encoding(content_encoding)? else {
+        return Ok(Cow::Borrowed(data));
+    };
+    let limit = COMPRESSION
+        .read()
+        .unwrap()
+        .map_or(DEFAULT_MAX_DECOMPRESSED_SIZE, |c| c.max_decompressed_size);
+    encoding.decompress(data, limit).map(Cow::Owned)
+}
+
+impl RabbitMQClient {
+    /// Compresses the events published, and the saga steps dispatched by the orchestrator,
+    /// from `compression.threshold` bytes on. `None`, the default, disables it. Consumers
+    /// need the feature of the encoding before their publishers enable it. Like
+    /// `publish_event`, it applies to the whole process rather than to one client.
+    pub fn set_compression(compression: Option<Compression>) {
+        *COMPRESSION.write().unwrap() = compression;
+    }
+}
+
+#[cfg(test)]
+mod test_compression {
+    use super::*;
+
+    #[test]
+    fn uncompressed_deliveries_are_read_as_they_are() {
+        let data = br#"{"userId":"user1"}"#;
+        assert!(matches!(decompress(None, data), Ok(Cow::Borrowed(d)) if d == data));
+        assert!(matches!(
+            decompress(Some("identity"), data),
+            Ok(Cow::Borrowed(_))
+        ));
+        let error = decompress(Some("br"), data).unwrap_err();
+        assert!(matches!(&error, RabbitMQError::UnsupportedContentEncoding(e) if e == "br"));
+        assert!(error.is_decode_error());
+
+        // Below any threshold, or with compression disabled, the body is left as is
+        let (body, properties) = compress(data.to_vec(), BasicProperties::default()).unwrap();
+        assert_eq!(body, data);
+        assert_eq!(properties.content_encoding(), &None);
+    }
+
+    #[test]
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn payloads_round_trip_compressed() {
+        let encodings = [
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip,
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd,
+        ];
+        let participants = format!("[{}]", vec![r#"{"userId":"user1"}"#; 500].join(","));
+        for encoding in encodings {
+            let compressed = encoding.compress(participants.as_bytes()).unwrap();
+            assert!(compressed.len() < participants.len() / 10, "{encoding:?}");
+            let decompressed = decompress(Some(encoding.as_str()), &compressed).unwrap();
+            assert_eq!(decompressed, participants.as_bytes());
+
+            let error = decompress(Some(encoding.as_str()), b"not compressed").unwrap_err();
+            assert!(matches!(error, RabbitMQError::CompressionError { .. }));
+            assert!(error.is_decode_error());
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn decompression_is_limited() {
+        let encodings = [
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip,
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd,
+        ];
+        // A few KB that expand to 64 MB
+        let bomb = vec![0u8; 64 * 1024 * 1024];
+        for encoding in encodings {
+            let compressed = encoding.compress(&bomb).unwrap();
+            assert!(compressed.len() < 128 * 1024, "{encoding:?}");
+            let error = decompress(Some(encoding.as_str()), &compressed).unwrap_err();
+            assert!(
+                matches!(&error, RabbitMQError::CompressionError { reason, .. }
+                    if reason.contains("exceeds")),
+                "{encoding:?}"
+            );
+            assert!(error.is_decode_error());
+
+            // Exactly the limit is fine
+            let compressed = encoding.compress(&bomb[..1024]).unwrap();
+            assert_eq!(encoding.decompress(&compressed, 1024).unwrap().len(), 1024);
+            assert!(encoding.decompress(&compressed, 1023).is_err());
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
 use lapin::{Channel, Connection};
 use serde::{Deserialize, Serialize};
-use strum_macros::{AsRefStr, EnumIter, EnumString};
+use strum::EnumMessage;
+use strum_macros::{EnumIter, EnumMessage, EnumString};
 use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, warn};
@@ -10,9 +13,19 @@ use crate::events::MicroserviceEvent;
 use backoff::{Error as BackoffError, ExponentialBackoff};
 use once_cell::sync::OnceCell;
 use crate::start::{AuditEmitter, EventEmitter, SagaEmitter};
+use crate::circuit_breaker::{CircuitBreakerStatus, CircuitBreakers};
+use crate::orchestrator::SagaOrchestrator;
+use crate::saga_definition::SagaDefinitionError;
+use crate::saga_store::SagaStoreError;
+use crate::saga_handle::SagaUpdate;
+use crate::saga_metadata::SagaMetadataError;
+use crate::emitter::Emitter;
+use crate::rpc::Rpc;
+use crate::versioning::Versioning;
+use crate::registry::RegisteredName;
 use std::sync::RwLock as StdRwLock;
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumString, AsRefStr, EnumIter, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumString, EnumMessage, EnumIter, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab-case")]
 #[serde(rename_all = "kebab-case")]
 pub enum AvailableMicroservices {
@@ -30,6 +43,19 @@ pub enum AvailableMicroservices {
     Social,
     Storage,
     LegendGameAnalytics,
+    /// A microservice declared by a service, see [`crate::registry::register_microservice`].
+    #[strum(disabled)]
+    #[serde(untagged, deserialize_with = "crate::registry::deserialize_microservice")]
+    Custom(RegisteredName),
+}
+
+impl AsRef<str> for AvailableMicroservices {
+    fn as_ref(&self) -> &str {
+        match self {
+            AvailableMicroservices::Custom(name) => name.as_str(),
+            builtin => builtin.get_serializations()[0],
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -52,6 +78,49 @@ pub enum RabbitMQError {
     InvalidPayload(String),
     #[error("{0} is not set, you need to call RabbitMQClient::new() first")]
     ValueIsNotSet(String),
+    #[error("Invalid saga definition: {0}")]
+    InvalidSagaDefinition(#[from] SagaDefinitionError),
+    #[error("Saga store error: {0}")]
+    SagaStore(#[from] SagaStoreError),
+    #[error("Request failed: {0}")]
+    RpcError(String),
+    #[error(
+        "Saga {} finished {:?}: {}",
+        .0.saga_id,
+        .0.status,
+        .0.failure_reason.as_deref().unwrap_or("no reason")
+    )]
+    SagaNotCompleted(Box<SagaUpdate>),
+    #[error("Saga metadata error: {0}")]
+    SagaMetadata(#[from] SagaMetadataError),
+    #[error("{event} payload of schema version {version} is newer than the current version {current}")]
+    UnsupportedSchemaVersion {
+        event: String,
+        version: u32,
+        current: u32,
+    },
+    #[error("Unsupported content type {0:?}")]
+    UnsupportedContentType(String),
+    #[error("Invalid {content_type} payload: {reason}")]
+    PayloadFormatError {
+        content_type: &'static str,
+        reason: String,
+    },
+    #[error("Unsupported content encoding {0:?}")]
+    UnsupportedContentEncoding(String),
+    #[error("Invalid {content_encoding} payload: {reason}")]
+    CompressionError {
+        content_encoding: &'static str,
+        reason: String,
+    },
+    #[error("Cannot upcast {event} payload from schema version {version}: {reason}")]
+    UpcastError {
+        event: String,
+        version: u32,
+        reason: String,
+    },
+    #[error("Quarantine error: {0}")]
+    QuarantineError(String),
 }
 
 #[derive(Debug, Error)]
@@ -62,6 +131,16 @@ pub enum HealthCheckError {
     Timeout(u128),
 }
 
+/// Health of the client together with the state of its circuit breakers, an open
+/// breaker degrades a single event without making the client unhealthy.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub circuit_breakers: BTreeMap<String, CircuitBreakerStatus>,
+}
+
 impl From<RabbitMQError> for HealthCheckError {
     fn from(err: RabbitMQError) -> Self {
         HealthCheckError::Unhealthy(err.to_string())
@@ -78,7 +157,13 @@ pub struct RabbitMQClient {
     pub(crate) saga_queue_name: String,
     pub(crate) event_emitter:  Arc<Mutex<Option<EventEmitter>>>,
     pub(crate) saga_emitter: Arc<Mutex<Option<SagaEmitter>>>,
+    pub(crate) compensation_emitter: SagaEmitter,
     pub(crate) audit_emitter: Arc<Mutex<Option<AuditEmitter>>>,
+    pub(crate) circuit_breakers: CircuitBreakers,
+    pub(crate) orchestrator: Arc<Mutex<Option<SagaOrchestrator>>>,
+    pub(crate) rpc: Rpc,
+    pub(crate) versioning: Versioning,
+    pub(crate) validate_consumed: Arc<AtomicBool>,
     reconnecting: Arc<Mutex<bool>>,
 }
 
@@ -90,7 +175,13 @@ impl Clone for RabbitMQClient {
             saga_queue_name: self.saga_queue_name.clone(),
             event_emitter: self.event_emitter.clone(),
             saga_emitter: self.saga_emitter.clone(),
+            compensation_emitter: self.compensation_emitter.clone(),
             audit_emitter: self.audit_emitter.clone(),
+            circuit_breakers: self.circuit_breakers.clone(),
+            orchestrator: self.orchestrator.clone(),
+            rpc: self.rpc.clone(),
+            versioning: self.versioning.clone(),
+            validate_consumed: Arc::clone(&self.validate_consumed),
             microservice: self.microservice.clone(),
             events_channel: Arc::clone(&self.events_channel),
             saga_channel: Arc::clone(&self.saga_channel),
@@ -176,7 +267,13 @@ impl RabbitMQClient {
             // the emitters are set later
             event_emitter:  Arc::new(Mutex::new(None)),
             saga_emitter:  Arc::new(Mutex::new(None)),
+            compensation_emitter: Emitter::new(),
             audit_emitter: Arc::new(Mutex::new(None)),
+            circuit_breakers: CircuitBreakers::default(),
+            orchestrator: Arc::new(Mutex::new(None)),
+            rpc: Rpc::default(),
+            versioning: Versioning::default(),
+            validate_consumed: Arc::new(AtomicBool::new(false)),
             events: events.unwrap_or(&[]),
             events_channel: Arc::new(Mutex::new(events_channel)),
             saga_channel: Arc::new(Mutex::new(saga_channel)),
@@ -243,6 +340,16 @@ impl RabbitMQClient {
             .map_err(|_| HealthCheckError::Timeout(timeout.as_millis()))?
     }
 
+    /// health_report runs [`RabbitMQClient::health_check`] and adds the state of the circuit breakers.
+    pub async fn health_report(&self, timeout: Duration) -> HealthReport {
+        let error = self.health_check(timeout).await.err().map(|e| e.to_string());
+        HealthReport {
+            healthy: error.is_none(),
+            error,
+            circuit_breakers: self.circuit_breakers(),
+        }
+    }
+
     /// Provides a thread-safe connection to RabbitMQ, creating or refreshing the connection as needed.
     ///
     /// # Returns
@@ -347,6 +454,14 @@ impl RabbitMQClient {
             let _ = self.start_consuming_saga_commands().await;
             info!("Successfully reconnected to saga_emitter");
         }
+        let orchestrator = self.orchestrator.lock().await.clone();
+        if let Some(orchestrator) = orchestrator {
+            self.start_orchestrating(orchestrator);
+            info!("Successfully reconnected to the saga orchestrator");
+        }
+        if let Err(e) = self.reconnect_rpc().await {
+            error!("Error reconnecting the request consumer: {:?}", e);
+        }
 
 
 
@@ -308,6 +308,24 @@ impl RabbitMQClient {
         Ok(())
     }
 
+    /// Creates the durable queue undecodable deliveries are moved to
+    pub(crate) async fn create_quarantine_resources(&self) -> Result<(), lapin::Error> {
+        let channel = self.events_channel.lock().await;
+
+        channel
+            .queue_declare(
+                Queue::QUARANTINE,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
     /// Creates audit logging infrastructure with direct exchange and separate queues
     /// Uses direct exchange for efficient single-consumer delivery to audit microservice
     pub(crate) async fn create_audit_logging_resources(&self) -> Result<(), lapin::Error> {
@@ -374,6 +392,18 @@ impl RabbitMQClient {
             )
             .await?;
 
+        // Create a single queue for the audit.saga_step_* events, told apart by routing key
+        channel
+            .queue_declare(
+                Queue::AUDIT_SAGA_STEP_COMMANDS,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
         // Bind each queue to its specific routing key
         channel
             .queue_bind(
@@ -415,6 +445,23 @@ impl RabbitMQClient {
             )
             .await?;
 
+        for saga_step_event in [
+            MicroserviceEvent::AuditSagaStepReceived,
+            MicroserviceEvent::AuditSagaStepSucceeded,
+            MicroserviceEvent::AuditSagaStepRetried,
+            MicroserviceEvent::AuditSagaStepExhausted,
+        ] {
+            channel
+                .queue_bind(
+                    Queue::AUDIT_SAGA_STEP_COMMANDS,
+                    Exchange::AUDIT,
+                    saga_step_event.as_ref(),
+                    QueueBindOptions::default(),
+                    FieldTable::default(),
+                )
+                .await?;
+        }
+
         Ok(())
     }
 }
@@ -464,6 +511,46 @@ mod test_consumers {
             }
         });
     }
+    #[test]
+    fn audit_saga_step_events_are_bound() {
+        let setup = TestSetup::new(None);
+
+        setup.rt.block_on(async {
+            setup
+                .client
+                .create_audit_logging_resources()
+                .await
+                .expect("Failed to create the audit resources");
+            let conn = setup.client.current_connection().await.expect("Cannot get the connection").read().await;
+            let t = conn.topology();
+
+            let queue = t
+                .queues
+                .iter()
+                .find(|q| q.name.as_str() == Queue::AUDIT_SAGA_STEP_COMMANDS)
+                .expect("Queue audit_saga_step_commands not found");
+            let routing_keys: Vec<&str> = queue
+                .bindings
+                .iter()
+                .filter(|binding| binding.source.as_str() == Exchange::AUDIT)
+                .map(|binding| binding.routing_key.as_str())
+                .collect();
+            for event in [
+                MicroserviceEvent::AuditSagaStepReceived,
+                MicroserviceEvent::AuditSagaStepSucceeded,
+                MicroserviceEvent::AuditSagaStepRetried,
+                MicroserviceEvent::AuditSagaStepExhausted,
+            ] {
+                assert!(
+                    routing_keys.contains(&event.as_ref()),
+                    "{} is not bound to {}",
+                    event.as_ref(),
+                    Queue::AUDIT_SAGA_STEP_COMMANDS
+                );
+            }
+        });
+    }
+
     #[test]
     fn create_header_consumers() {
         let setup = TestSetup::new(None);
@@ -65,6 +65,11 @@ where
         });
     }
 
+    /// Whether a handler is registered for `event`, [`Emitter::emit`] drops the data otherwise.
+    pub(crate) async fn has_handler(&self, event: &U) -> bool {
+        self.events.lock().await.contains_key(event)
+    }
+
     pub(crate) async fn emit(&self, event: U, data: T) {
         let events = self.events.lock().await;
         if let Some(sender) = events.get(&event) {
@@ -303,4 +308,16 @@ mod test_emitter {
 
         // If we reached this point without panicking, the test passes
     }
+
+    #[tokio::test]
+    async fn test_has_handler() {
+        let emitter = Emitter::<EventPayload, TestEvent>::new();
+        assert!(!emitter.has_handler(&TestEvent::Event1).await);
+
+        emitter
+            .on_with_async_handler(TestEvent::Event1, |_| async {})
+            .await;
+        assert!(emitter.has_handler(&TestEvent::Event1).await);
+        assert!(!emitter.has_handler(&TestEvent::Event2).await);
+    }
 }
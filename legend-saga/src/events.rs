@@ -1,1245 +1,187 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+//! Events shared with the other legend-transactional libraries. The `MicroserviceEvent`
+//! enum and the payloads are generated from `schema/events.json`: add or change an event
+//! there and run `make generate-events`.
+
+/// Defines [`AnyEvent`] from every event and its payload, invoked by the generated module.
+macro_rules! any_event {
+    ($($event:ident($payload:ident)),* $(,)?) => {
+        /// The payload of any event, in the variant of its event. It serializes as the
+        /// payload itself.
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Debug, Clone, serde::Serialize)]
+        #[serde(untagged)]
+        pub enum AnyEvent {
+            $($event($payload),)*
+        }
+
+        impl AnyEvent {
+            /// Parses `payload` as the payload of `event`.
+            pub fn from_value(
+                event: MicroserviceEvent,
+                payload: serde_json::Value,
+            ) -> Result<Self, serde_json::Error> {
+                Self::deserialize(event, payload)
+            }
+
+            /// Deserializes the payload of `event` with `deserializer`.
+            pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+                event: MicroserviceEvent,
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                match event {
+                    $(MicroserviceEvent::$event => {
+                        <$payload as serde::Deserialize>::deserialize(deserializer).map(Self::$event)
+                    })*
+                }
+            }
+        }
+
+        impl PayloadEvent for AnyEvent {
+            fn event_type(&self) -> MicroserviceEvent {
+                match self {
+                    $(Self::$event(_) => MicroserviceEvent::$event,)*
+                }
+            }
+        }
+
+        impl Validate for AnyEvent {
+            fn validate(&self) -> Result<(), ValidationError> {
+                match self {
+                    $(Self::$event(payload) => payload.validate(),)*
+                }
+            }
+        }
+    };
+}
+
+mod generated;
+
+pub use generated::*;
+pub use legend_saga_derive::PayloadEvent;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::collections::HashMap;
-use strum_macros::{AsRefStr, EnumIter, EnumString};
-
-/// Represents the available events in the system.
-#[derive(Debug, Clone, Copy, AsRefStr, EnumString, PartialEq, EnumIter, Hash, Eq)]
-#[strum(serialize_all = "snake_case")]
-pub enum MicroserviceEvent {
-    #[strum(serialize = "test.image")]
-    TestImage,
-    #[strum(serialize = "test.mint")]
-    TestMint,
-    /// Emitted when an event is received by a microservice before processing starts (audit tracking)
-    #[strum(serialize = "audit.received")]
-    AuditReceived,
-    /// Emitted when an event is successfully processed by a microservice for audit tracking
-    #[strum(serialize = "audit.processed")]
-    AuditProcessed,
-    /// Emitted when a message is rejected/nacked and sent to dead letter queue
-    #[strum(serialize = "audit.dead_letter")]
-    AuditDeadLetter,
-    /// Emitted when an event is published by a microservice (audit tracking)
-    #[strum(serialize = "audit.published")]
-    AuditPublished,
-    #[strum(serialize = "auth.deleted_user")]
-    AuthDeletedUser,
-    #[strum(serialize = "auth.logout_user")]
-    AuthLogoutUser,
-    #[strum(serialize = "auth.new_user")]
-    AuthNewUser,
-    #[strum(serialize = "auth.blocked_user")]
-    AuthBlockedUser,
-    #[strum(serialize = "auth.operation_created")]
-    AuthOperationCreated,
-    #[strum(serialize = "legend_missions.new_mission_created")]
-    LegendMissionsNewMissionCreated,
-    #[strum(serialize = "legend_missions.ongoing_mission")]
-    LegendMissionsOngoingMission,
-    #[strum(serialize = "legend_missions.mission_finished")]
-    LegendMissionsMissionFinished,
-    #[strum(serialize = "legend_missions.mission_approved")]
-    LegendMissionsMissionApproved,
-    #[strum(serialize = "legend_missions.mission_rejected")]
-    LegendMissionsMissionRejected,
-    #[strum(serialize = "legend_missions.mission_activated")]
-    LegendMissionsMissionActivated,
-    #[strum(serialize = "legend_missions.send_email_code_exchange_mission_completed")]
-    LegendMissionsSendEmailCodeExchangeMissionCompleted,
-    #[strum(serialize = "legend_missions.send_email_gift_card_mission_completed")]
-    LegendMissionsSendEmailGiftCardMissionCompleted,
-    #[strum(serialize = "legend_rankings.rankings_finished")]
-    LegendRankingsRankingsFinished,
-    /// A distinct player's first participation in a ranking was recorded
-    /// (the billable unit — the same player playing 50 times counts once).
-    /// Invalidation signal, not a snapshot: consumers re-fetch the count
-    /// they need rather than trust a total baked into the event.
-    #[strum(serialize = "legend_rankings.billable_participant_recorded")]
-    LegendRankingsBillableParticipantRecorded,
-    #[strum(serialize = "legend_showcase.product_virtual_deleted")]
-    LegendShowcaseProductVirtualDeleted,
-    #[strum(serialize = "legend_showcase.update_allowed_mission_subscription_ids")]
-    LegendShowcaseUpdateAllowedMissionSubscriptionIds,
-    #[strum(serialize = "legend_showcase.update_allowed_ranking_subscription_ids")]
-    LegendShowcaseUpdateAllowedRankingSubscriptionIds,
-    #[strum(serialize = "social.block_chat")]
-    SocialBlockChat,
-    #[strum(serialize = "social.new_user")]
-    SocialNewUser,
-    #[strum(serialize = "social.unblock_chat")]
-    SocialUnblockChat,
-    #[strum(serialize = "social.updated_user")]
-    SocialUpdatedUser,
-    #[strum(serialize = "social.country_created")]
-    SocialCountryCreated,
-    #[strum(serialize = "social.country_updated")]
-    SocialCountryUpdated,
-    #[strum(serialize = "social.country_deleted")]
-    SocialCountryDeleted,
-    #[strum(serialize = "legend_rankings.new_ranking_created")]
-    LegendRankingsNewRankingCreated,
-    #[strum(serialize = "legend_rankings.ranking_submitted_for_review")]
-    LegendRankingsRankingSubmittedForReview,
-    #[strum(serialize = "legend_rankings.ranking_approved")]
-    LegendRankingsRankingApproved,
-    #[strum(serialize = "legend_rankings.ranking_rejected")]
-    LegendRankingsRankingRejected,
-    #[strum(serialize = "legend_rankings.ranking_activated")]
-    LegendRankingsRankingActivated,
-    #[strum(serialize = "legend_rankings.intermediate_reward")]
-    LegendRankingsIntermediateReward,
-    #[strum(serialize = "legend_rankings.participation_reward")]
-    LegendRankingsParticipationReward,
-    // Billing events - Payment and subscription domain events (No Stripe leakage)
-    #[strum(serialize = "billing.payment_created")]
-    BillingPaymentCreated,
-    #[strum(serialize = "billing.payment_succeeded")]
-    BillingPaymentSucceeded,
-    #[strum(serialize = "billing.payment_failed")]
-    BillingPaymentFailed,
-    #[strum(serialize = "billing.payment_refunded")]
-    BillingPaymentRefunded,
-    #[strum(serialize = "billing.subscription_created")]
-    BillingSubscriptionCreated,
-    #[strum(serialize = "billing.subscription_updated")]
-    BillingSubscriptionUpdated,
-    #[strum(serialize = "billing.subscription_renewed")]
-    BillingSubscriptionRenewed,
-    #[strum(serialize = "billing.subscription_canceled")]
-    BillingSubscriptionCanceled,
-    #[strum(serialize = "billing.subscription_expired")]
-    BillingSubscriptionExpired,
-    // Platform events - Level 1 entitlements (an operation pays SIPLEI)
-    #[strum(serialize = "platform.operation_features_changed")]
-    PlatformOperationFeaturesChanged,
-    // Legend Events - Event and registration domain events
-    #[strum(serialize = "legend_events.new_event_created")]
-    LegendEventsNewEventCreated,
-    #[strum(serialize = "legend_events.event_started")]
-    LegendEventsEventStarted,
-    #[strum(serialize = "legend_events.event_ended")]
-    LegendEventsEventEnded,
-    #[strum(serialize = "legend_events.player_registered")]
-    LegendEventsPlayerRegistered,
-    #[strum(serialize = "legend_events.player_joined_waitlist")]
-    LegendEventsPlayerJoinedWaitlist,
-    #[strum(serialize = "legend_events.score_submitted")]
-    LegendEventsScoreSubmitted,
-    #[strum(serialize = "legend_events.events_finished")]
-    LegendEventsEventsFinished,
-    #[strum(serialize = "legend_events.intermediate_reward")]
-    LegendEventsIntermediateReward,
-    #[strum(serialize = "legend_events.participation_reward")]
-    LegendEventsParticipationReward,
-}
+use std::fmt;
 
 pub trait PayloadEvent {
     fn event_type(&self) -> MicroserviceEvent;
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct TestImagePayload {
-    pub image: String,
-}
-
-impl PayloadEvent for TestImagePayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::TestImage
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct TestMintPayload {
-    pub mint: String,
-}
-
-impl PayloadEvent for TestMintPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::TestMint
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct AuthDeletedUserPayload {
-    pub user_id: String,
-}
-
-impl PayloadEvent for AuthDeletedUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuthDeletedUser
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct AuthLogoutUserPayload {
-    pub user_id: String,
-}
-
-impl PayloadEvent for AuthLogoutUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuthLogoutUser
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct AuthNewUserPayload {
-    pub id: String,
-    pub email: String,
-    pub username: String,
-    pub userlastname: String,
-}
-
-impl PayloadEvent for AuthNewUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuthNewUser
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct AuthBlockedUserPayload {
-    pub user_id: String,
-    pub block_type: String,
-    pub block_reason: Option<String>,
-    pub block_expiration_hours: Option<i32>,
-}
-
-impl PayloadEvent for AuthBlockedUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuthBlockedUser
-    }
-}
-
-/// identity_mode is immutable once an operation exists, so this creation-time
-/// event is the only one a consumer needs to build a local
-/// {operation_id -> identity_mode} projection (IDR-01,
-/// MODULO-IDENTITY-RESOLVER.md).
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct AuthOperationCreatedPayload {
-    pub operation_id: String,
-    pub identity_mode: String,
-}
-
-impl PayloadEvent for AuthOperationCreatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuthOperationCreated
-    }
-}
-
-/// An operation's effective feature set changed (plan assignment or feature
-/// override) — an invalidation signal, not a snapshot. A consumer refetches
-/// the effective set from legend-billing rather than trust a payload that
-/// could drift from the feature schema that lives there.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct PlatformOperationFeaturesChangedPayload {
-    pub operation_id: String,
-}
-
-impl PayloadEvent for PlatformOperationFeaturesChangedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::PlatformOperationFeaturesChanged
+/// Checks of a payload beyond its types, e.g. the values a status accepts. The generated
+/// payloads implement it from the `nonEmpty` and `oneOf` constraints of the schema,
+/// `publish_event` rejects the payloads that fail it. `#[derive(PayloadEvent)]` implements
+/// it accepting every value, unless `#[event(Variant, custom_validate)]`.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationError> {
+        Ok(())
     }
 }
 
-/// Represents the fields that will be sent by email when a mission is created.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsNewMissionCreatedEventPayload {
-    pub mission_id: i32,
-    pub title: String,
-    pub author: String,
-    pub author_email: String,
-    pub reward_kind: String,
-    pub start_date: String,
-    pub end_date: String,
-}
-
-impl PayloadEvent for LegendMissionsNewMissionCreatedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsNewMissionCreated
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.iter().try_for_each(Validate::validate)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsMissionApprovedEventPayload {
-    pub mission_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub start_date: String,
-}
-
-impl PayloadEvent for LegendMissionsMissionApprovedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsMissionApproved
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.iter().try_for_each(Validate::validate)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsMissionRejectedEventPayload {
-    pub mission_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub admin_notes: String,
-}
-
-impl PayloadEvent for LegendMissionsMissionRejectedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsMissionRejected
+impl<T: Validate> Validate for HashMap<String, T> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.values().try_for_each(Validate::validate)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsOngoingMissionEventPayload {
-    pub redis_key: String,
+/// A field of a payload with a value its schema does not accept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub reason: String,
 }
 
-impl PayloadEvent for LegendMissionsOngoingMissionEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsOngoingMission
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.reason)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct MissionFinishedParticipant {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user_id: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub email: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub position: Option<i32>,
-}
+impl std::error::Error for ValidationError {}
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsMissionFinishedEventPayload {
-    pub mission_title: String,
-    pub participants: Vec<MissionFinishedParticipant>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub author: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub author_email: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub total_seats: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub registered_participants: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub reward_kind: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub start_date: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub end_date: Option<String>,
+/// A string field of a payload, `None` when an optional one is missing.
+trait StringField {
+    fn value(&self) -> Option<&str>;
 }
 
-impl PayloadEvent for LegendMissionsMissionFinishedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsMissionFinished
+impl StringField for String {
+    fn value(&self) -> Option<&str> {
+        Some(self)
     }
 }
 
-/// Emitted by the finalize ticker when an approved mission reaches its start_date
-/// and becomes active. Mirrors the Go and TS library payloads.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsMissionActivatedEventPayload {
-    pub title: String,
-    pub author: String,
-    pub author_email: String,
-    pub start_date: String,
-    pub end_date: String,
-}
-
-impl PayloadEvent for LegendMissionsMissionActivatedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsMissionActivated
+impl StringField for Option<String> {
+    fn value(&self) -> Option<&str> {
+        self.as_deref()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct RankingWinners {
-    pub user_id: String,
-    pub reward: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct CompletedRanking {
-    pub title: String,
-    pub description: String,
-    pub author_email: String,
-    pub ends_at: String,
-    pub reward: String,
-    pub reward_type: String,
-    pub winners: Vec<RankingWinners>,
-    // Present only if reward_type is "Nft"
-    pub nft_blockchain_network: Option<String>,
-    pub nft_contract_address: Option<String>,
-    // Present only if reward_type is "Crypto"
-    pub wallet_crypto_asset: Option<String>,
-    /// Optional notification config (JSON) to enrich email templates
-    pub notification_config: Option<serde_json::Value>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsSendEmailCodeExchangeMissionCompletedPayload {
-    pub user_id: String,
-    pub mission_title: String,
-    pub code_value: String,
-    pub code_description: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub ecommerce_redeem_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub maps_redeem_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub template_name: Option<String>,
-}
-
-impl PayloadEvent for LegendMissionsSendEmailCodeExchangeMissionCompletedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsSendEmailCodeExchangeMissionCompleted
+fn non_empty(field: &'static str, value: &impl StringField) -> Result<(), ValidationError> {
+    match value.value() {
+        Some(value) if value.trim().is_empty() => Err(ValidationError {
+            field,
+            reason: "must not be empty".to_string(),
+        }),
+        _ => Ok(()),
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendMissionsSendEmailGiftCardMissionCompletedPayload {
-    pub user_id: String,
-    pub mission_title: String,
-    pub description: String,
-    pub file_key: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub ecommerce_redeem_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub maps_redeem_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub template_name: Option<String>,
-}
-
-impl PayloadEvent for LegendMissionsSendEmailGiftCardMissionCompletedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendMissionsSendEmailGiftCardMissionCompleted
+fn one_of(
+    field: &'static str,
+    value: &impl StringField,
+    values: &[&str],
+) -> Result<(), ValidationError> {
+    match value.value() {
+        Some(value) if !values.contains(&value) => Err(ValidationError {
+            field,
+            reason: format!("is {value:?}, expected one of {values:?}"),
+        }),
+        _ => Ok(()),
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsRankingsFinishedEventPayload {
-    pub completed_rankings: Vec<CompletedRanking>,
+/// A payload whose event is known at compile time, implemented by
+/// `#[derive(PayloadEvent)]`.
+pub trait TypedPayloadEvent: PayloadEvent + Serialize + DeserializeOwned {
+    const EVENT: MicroserviceEvent;
 }
 
-impl PayloadEvent for LegendRankingsRankingsFinishedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsRankingsFinished
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsBillableParticipantRecordedEventPayload {
-    pub operation_id: String,
-    pub source_type: String,
-    pub source_id: String,
-    // Needed so consumers can dedupe on (operation_id, source_type,
-    // source_id, user_ref) — the same composite key billable_participants
-    // uses — and stay correct under RabbitMQ redelivery instead of
-    // double-counting.
-    pub user_ref: String,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for LegendRankingsBillableParticipantRecordedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsBillableParticipantRecorded
-    }
+/// Compile-time mapping from an event, as `MicroserviceEvent::X as u16`, to its payload.
+/// `#[derive(PayloadEvent)]` implements it for the schema payloads through
+/// `#[event(Variant, payload_of)]`, use it through [`payload_of!`].
+pub trait EventPayloadMap<const EVENT: u16> {
+    type Payload: TypedPayloadEvent;
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendShowcaseProductVirtualDeletedEventPayload {
-    /// Unique identifier of the deleted virtual product
-    pub product_virtual_id: String,
-    /// Slug of the deleted virtual product
-    pub product_virtual_slug: String,
-}
-
-impl PayloadEvent for LegendShowcaseProductVirtualDeletedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendShowcaseProductVirtualDeleted
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload {
-    pub product_virtual_slug: String,
-    pub allowed_subscription_ids: Vec<String>,
-}
-
-impl PayloadEvent for LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendShowcaseUpdateAllowedMissionSubscriptionIds
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload {
-    pub product_virtual_id: String,
-    pub allowed_subscription_ids: Vec<String>,
-}
-
-impl PayloadEvent for LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendShowcaseUpdateAllowedRankingSubscriptionIds
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialBlockChatPayload {
-    pub user_id: String,
-    pub user_to_block_id: String,
-}
-
-impl PayloadEvent for SocialBlockChatPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialBlockChat
-    }
-}
-
-/// Gender represents the possible genders a social user can have.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "UPPERCASE")]
-pub enum Gender {
-    Male,
-    Female,
-    Undefined,
-}
-
-/// Represents the geographical location of a user
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct UserLocation {
-    pub continent: String,
-    pub country: String,
-    pub region: String,
-    pub city: String,
-}
-
-/// SocialUser represents the social user model.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialUser {
-    #[serde(rename = "_id")]
-    pub id: String,
-    pub username: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub first_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub last_name: Option<String>,
-    pub gender: Gender,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub is_public_profile: Option<bool>,
-    pub followers: Vec<String>,
-    pub following: Vec<String>,
-    pub email: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub birthday: Option<DateTime<Utc>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub location: Option<UserLocation>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub avatar: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub avatar_screenshot: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user_image: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub glb_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub social_media: Option<HashMap<String, String>>,
-    pub preferences: Vec<String>,
-    pub blocked_users: Vec<String>,
-    #[serde(rename = "RPMAvatarId", skip_serializing_if = "Option::is_none")]
-    pub rpm_avatar_id: Option<String>,
-    #[serde(rename = "RPMUserId", skip_serializing_if = "Option::is_none")]
-    pub rpm_user_id: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub paid_price_id: Option<String>,
-    pub created_at: DateTime<Utc>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialNewUserPayload {
-    pub social_user: SocialUser,
-}
-
-impl PayloadEvent for SocialNewUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialNewUser
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialUpdatedUserPayload {
-    pub social_user: SocialUser,
-}
-
-impl PayloadEvent for SocialUpdatedUserPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialUpdatedUser
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialUnblockChatPayload {
-    pub user_id: String,
-    pub user_to_unblock_id: String,
-}
-
-impl PayloadEvent for SocialUnblockChatPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialUnblockChat
-    }
-}
-
-/// Payload for social.country_created event. Source of truth lives in social;
-/// consumers upsert their local projection keyed by `code`.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialCountryCreatedPayload {
-    pub code: String,
-    pub name: String,
-    pub is_enabled: bool,
-}
-
-impl PayloadEvent for SocialCountryCreatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialCountryCreated
-    }
-}
-
-/// Payload for social.country_updated event (name and/or isEnabled changed).
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialCountryUpdatedPayload {
-    pub code: String,
-    pub name: String,
-    pub is_enabled: bool,
-}
-
-impl PayloadEvent for SocialCountryUpdatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialCountryUpdated
-    }
-}
-
-/// Payload for social.country_deleted event. Consumers delete the matching
-/// entry from their local projection.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SocialCountryDeletedPayload {
-    pub code: String,
-}
-
-impl PayloadEvent for SocialCountryDeletedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::SocialCountryDeleted
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct NotificationConfig {
-    pub custom_emails: Option<Vec<String>>,
-    pub template_name: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsNewRankingCreatedEventPayload {
-    pub title: String,
-    pub description: String,
-    pub author_email: String,
-    pub reward_type: String,
-    pub start_at: String,
-    pub ends_at: String,
-    pub nft_blockchain_network: Option<String>,
-    pub nft_contract_address: Option<String>,
-    pub wallet_crypto_asset: Option<String>,
-    pub notification_config: Option<NotificationConfig>,
-}
-
-impl PayloadEvent for LegendRankingsNewRankingCreatedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsNewRankingCreated
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsRankingSubmittedForReviewEventPayload {
-    pub ranking_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub created_at: String,
-}
-
-impl PayloadEvent for LegendRankingsRankingSubmittedForReviewEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsRankingSubmittedForReview
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsRankingApprovedEventPayload {
-    pub ranking_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub start_at: String,
-}
-
-impl PayloadEvent for LegendRankingsRankingApprovedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsRankingApproved
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsRankingRejectedEventPayload {
-    pub ranking_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub reasons: String,
-}
-
-impl PayloadEvent for LegendRankingsRankingRejectedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsRankingRejected
-    }
-}
+/// The payload of an event, see [`payload_of!`].
+pub type PayloadOf<const EVENT: u16> = <MicroserviceEvent as EventPayloadMap<EVENT>>::Payload;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsRankingActivatedEventPayload {
-    pub ranking_id: i32,
-    pub title: String,
-    pub author_email: String,
-    pub start_at: String,
-}
-
-impl PayloadEvent for LegendRankingsRankingActivatedEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsRankingActivated
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsIntermediateRewardEventPayload {
-    pub user_id: String,
-    pub ranking_id: i32,
-    pub intermediate_reward_type: String,
-    pub reward_config: serde_json::Value,
-    pub template_name: String,
-    pub template_data: serde_json::Value,
-}
-
-impl PayloadEvent for LegendRankingsIntermediateRewardEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsIntermediateReward
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendRankingsParticipationRewardEventPayload {
-    pub user_id: String,
-    pub ranking_id: i32,
-    pub participation_reward_type: String,
-    pub reward_config: serde_json::Value,
-    pub template_name: String,
-    pub template_data: serde_json::Value,
-}
-
-impl PayloadEvent for LegendRankingsParticipationRewardEventPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendRankingsParticipationReward
-    }
-}
-
-// ********** BILLING ************** //
-
-/// Payload for billing.payment.created event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingPaymentCreatedPayload {
-    pub payment_id: String,
-    pub user_id: String,
-    pub amount: i64,
-    pub currency: String,
-    pub status: String, // "pending" | "processing"
-    pub metadata: HashMap<String, String>,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingPaymentCreatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingPaymentCreated
-    }
-}
-
-/// Payload for billing.payment.succeeded event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingPaymentSucceededPayload {
-    pub payment_id: String,
-    pub user_id: String,
-    pub amount: i64,
-    pub currency: String,
-    pub metadata: HashMap<String, String>,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingPaymentSucceededPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingPaymentSucceeded
-    }
-}
-
-/// Payload for billing.payment.failed event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingPaymentFailedPayload {
-    pub payment_id: String,
-    pub user_id: String,
-    pub amount: i64,
-    pub currency: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub failure_reason: Option<String>,
-    pub metadata: HashMap<String, String>,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingPaymentFailedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingPaymentFailed
-    }
-}
-
-/// Payload for billing.payment.refunded event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingPaymentRefundedPayload {
-    pub payment_id: String,
-    pub user_id: String,
-    pub amount: i64,
-    pub refunded_amount: i64,
-    pub currency: String,
-    pub metadata: HashMap<String, String>,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingPaymentRefundedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingPaymentRefunded
-    }
-}
-
-/// Payload for billing.subscription.created event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingSubscriptionCreatedPayload {
-    pub subscription_id: String,
-    pub user_id: String,
-    pub plan_id: String,
-    pub plan_slug: String,
-    pub status: String, // "pending" | "active" | "trialing"
-    pub period_start: String,
-    pub period_end: String,
-    pub occurred_at: String,
-    #[serde(default)]
-    pub features: Vec<String>,
-}
-
-impl PayloadEvent for BillingSubscriptionCreatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingSubscriptionCreated
-    }
-}
-
-/// Payload for billing.subscription.updated event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingSubscriptionUpdatedPayload {
-    pub subscription_id: String,
-    pub user_id: String,
-    pub plan_id: String,
-    pub plan_slug: String,
-    pub status: String, // "active" | "past_due" | "unpaid" | "paused" | "trialing"
-    pub cancel_at_period_end: bool,
-    pub period_start: String,
-    pub period_end: String,
-    pub occurred_at: String,
-    #[serde(default)]
-    pub features: Vec<String>,
-}
-
-impl PayloadEvent for BillingSubscriptionUpdatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingSubscriptionUpdated
-    }
-}
-
-/// Payload for billing.subscription.renewed event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingSubscriptionRenewedPayload {
-    pub subscription_id: String,
-    pub user_id: String,
-    pub plan_id: String,
-    pub plan_slug: String,
-    pub period_start: String,
-    pub period_end: String,
-    pub occurred_at: String,
-    #[serde(default)]
-    pub features: Vec<String>,
-}
-
-impl PayloadEvent for BillingSubscriptionRenewedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingSubscriptionRenewed
-    }
-}
-
-/// Payload for billing.subscription.canceled event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingSubscriptionCanceledPayload {
-    pub subscription_id: String,
-    pub user_id: String,
-    pub plan_id: String,
-    pub plan_slug: String,
-    pub canceled_at: String,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingSubscriptionCanceledPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingSubscriptionCanceled
-    }
-}
-
-/// Payload for billing.subscription.expired event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct BillingSubscriptionExpiredPayload {
-    pub subscription_id: String,
-    pub user_id: String,
-    pub plan_id: String,
-    pub plan_slug: String,
-    pub expired_at: String,
-    pub occurred_at: String,
-}
-
-impl PayloadEvent for BillingSubscriptionExpiredPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::BillingSubscriptionExpired
-    }
-}
-
-// ********** LEGEND EVENTS ************** //
-
-/// Payload for legend_events.new_event_created event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsNewEventCreatedPayload {
-    pub event_id: i32,
-    pub title: String,
-    pub description: String,
-    pub author_email: String,
-    pub reward_type: Option<String>,
-    pub start_date: String,
-    pub end_date: String,
-    pub max_players: Option<i32>,
-    pub ticket_price_usd: Option<f32>,
-    pub is_free_tournament: bool,
-    /// Optional notification config (JSON) to enrich email templates
-    pub notification_config: Option<NotificationConfig>,
-}
-
-impl PayloadEvent for LegendEventsNewEventCreatedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsNewEventCreated
-    }
-}
-
-/// Payload for legend_events.event_started event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsEventStartedPayload {
-    pub event_id: i32,
-    pub title: String,
-    pub started_at: String,
-}
-
-impl PayloadEvent for LegendEventsEventStartedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsEventStarted
-    }
-}
-
-/// Payload for legend_events.event_ended event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsEventEndedPayload {
-    pub event_id: i32,
-    pub title: String,
-    pub ended_at: String,
-    pub total_participants: i32,
-}
-
-impl PayloadEvent for LegendEventsEventEndedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsEventEnded
-    }
-}
-
-/// Payload for legend_events.player_registered event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsPlayerRegisteredPayload {
-    pub event_id: i32,
-    pub user_id: String,
-    pub payment_id: Option<String>,
-    pub amount_paid: Option<f32>,
-    pub is_free: bool,
-    pub registered_at: String,
-}
-
-impl PayloadEvent for LegendEventsPlayerRegisteredPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsPlayerRegistered
-    }
-}
-
-/// Payload for legend_events.player_joined_waitlist event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsPlayerJoinedWaitlistPayload {
-    pub event_id: i32,
-    pub user_id: String,
-    pub position: i32,
-    pub joined_at: String,
-}
-
-impl PayloadEvent for LegendEventsPlayerJoinedWaitlistPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsPlayerJoinedWaitlist
-    }
-}
-
-/// Payload for legend_events.score_submitted event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsScoreSubmittedPayload {
-    pub event_id: i32,
-    pub user_id: String,
-    pub score: f64,
-    pub total_score: f64,
-    pub match_id: Option<String>,
-    pub submitted_at: String,
-}
-
-impl PayloadEvent for LegendEventsScoreSubmittedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsScoreSubmitted
-    }
-}
-
-/// Represents a completed event with its winners
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct CompletedEvent {
-    pub event_id: i32,
-    pub title: String,
-    pub description: String,
-    pub author_email: String,
-    pub ends_at: String,
-    pub reward: Option<String>,
-    pub reward_type: Option<String>,
-    pub winners: Vec<EventWinner>,
-    /// Optional notification config forwarded from event
-    pub notification_config: Option<serde_json::Value>,
-}
-
-/// Represents a winner in an event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct EventWinner {
-    pub user_id: String,
-    pub position: i32,
-    pub score: f64,
-}
-
-/// Payload for legend_events.events_finished event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsEventsFinishedPayload {
-    pub completed_events: Vec<CompletedEvent>,
-}
-
-impl PayloadEvent for LegendEventsEventsFinishedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsEventsFinished
-    }
-}
-
-/// Payload for legend_events.intermediate_reward event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsIntermediateRewardPayload {
-    pub user_id: String,
-    pub event_id: i32,
-    pub intermediate_reward_type: String,
-    pub reward_config: serde_json::Value,
-    pub template_name: String,
-    pub template_data: serde_json::Value,
-}
-
-impl PayloadEvent for LegendEventsIntermediateRewardPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsIntermediateReward
-    }
-}
-
-/// Payload for legend_events.participation_reward event
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct LegendEventsParticipationRewardPayload {
-    pub user_id: String,
-    pub event_id: i32,
-    pub participation_reward_type: String,
-    pub reward_config: serde_json::Value,
-    pub template_name: String,
-    pub template_data: serde_json::Value,
-}
-
-impl PayloadEvent for LegendEventsParticipationRewardPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::LegendEventsParticipationReward
-    }
-}
-
-// ********** AUDIT ************** //
-/// Payload for audit.received event - tracks when event is received before processing
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AuditReceivedPayload {
-    /// The microservice that published the original event
-    pub publisher_microservice: String,
-    /// The microservice that received the event
-    pub receiver_microservice: String,
-    /// The event that was received
-    pub received_event: String,
-    /// Timestamp when the event was received (UNIX timestamp in milliseconds)
-    pub received_at: u64,
-    /// The queue name from which the event was consumed
-    pub queue_name: String,
-    /// Event identifier for cross-event correlation (UUID v7)
-    pub event_id: String,
-}
-
-impl PayloadEvent for AuditReceivedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuditReceived
-    }
-}
-
-/// Payload for audit.processed event - tracks successful event processing
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AuditProcessedPayload {
-    /// The microservice that published the original event
-    pub publisher_microservice: String,
-    /// The microservice that processed the event
-    pub processor_microservice: String,
-    /// The original event that was processed
-    pub processed_event: String,
-    /// Timestamp when the event was processed (UNIX timestamp in milliseconds)
-    pub processed_at: u64,
-    /// The queue name where the event was consumed
-    pub queue_name: String,
-    /// Event identifier for cross-event correlation (UUID v7)
-    pub event_id: String,
-}
-
-impl PayloadEvent for AuditProcessedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuditProcessed
-    }
-}
-
-/// Payload for audit.dead_letter event - tracks when message is rejected/nacked
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AuditDeadLetterPayload {
-    /// The microservice that published the original event
-    pub publisher_microservice: String,
-    /// The microservice that rejected the event
-    pub rejector_microservice: String,
-    /// The original event that was rejected
-    pub rejected_event: String,
-    /// Timestamp when the event was rejected (UNIX timestamp in milliseconds)
-    pub rejected_at: u64,
-    /// The queue name where the event was rejected from
-    pub queue_name: String,
-    /// Reason for rejection (delay, fibonacci_strategy, etc.)
-    pub rejection_reason: String,
-    /// Optional retry count
-    pub retry_count: Option<u32>,
-    /// Event identifier for cross-event correlation (UUID v7)
-    pub event_id: String,
-}
-
-impl PayloadEvent for AuditDeadLetterPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuditDeadLetter
-    }
-}
-
-/// Payload for audit.published event - tracks when event is published at the source microservice
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AuditPublishedPayload {
-    /// The microservice that published the event
-    pub publisher_microservice: String,
-    /// The event that was published
-    pub published_event: String,
-    /// Timestamp when the event was published (UNIX timestamp in milliseconds)
-    pub published_at: u64,
-    /// Event identifier for cross-event correlation (UUID v7)
-    pub event_id: String,
-}
-
-impl PayloadEvent for AuditPublishedPayload {
-    fn event_type(&self) -> MicroserviceEvent {
-        MicroserviceEvent::AuditPublished
-    }
+/// The payload type of a `MicroserviceEvent` variant, for typed dispatch:
+///
+/// ```
+/// use legend_saga::events::MicroserviceEvent;
+/// use legend_saga::payload_of;
+///
+/// let payload: payload_of!(AuthDeletedUser) = serde_json::from_str(r#"{"userId": "1"}"#).unwrap();
+/// assert_eq!(payload.user_id, "1");
+/// ```
+#[macro_export]
+macro_rules! payload_of {
+    ($event:ident) => {
+        $crate::events::PayloadOf<{ $crate::events::MicroserviceEvent::$event as u16 }>
+    };
 }
 
 #[cfg(test)]
@@ -1353,6 +295,7 @@ mod tests {
             queue_name: "test_queue".to_string(),
             rejection_reason: "test_reason".to_string(),
             retry_count: Some(3),
+            retry_strategy: Some("delay".to_string()),
             event_id: "test-uuid".to_string(),
         };
 
@@ -1395,4 +338,36 @@ mod tests {
             timestamp_s
         );
     }
+
+    #[test]
+    fn payloads_map_to_their_event_at_compile_time() {
+        assert_eq!(
+            AuthDeletedUserPayload::EVENT,
+            MicroserviceEvent::AuthDeletedUser
+        );
+        let payload: payload_of!(SocialBlockChat) = SocialBlockChatPayload {
+            user_id: "1".to_string(),
+            user_to_block_id: "2".to_string(),
+        };
+        assert_eq!(payload.event_type(), MicroserviceEvent::SocialBlockChat);
+        assert_eq!(
+            <payload_of!(AuditPublished)>::EVENT,
+            MicroserviceEvent::AuditPublished
+        );
+    }
+
+    #[test]
+    fn any_event_holds_the_payload_of_its_event() {
+        let payload = serde_json::json!({"userId": "1", "userToBlockId": "2"});
+        let event =
+            AnyEvent::from_value(MicroserviceEvent::SocialBlockChat, payload.clone()).unwrap();
+        match &event {
+            AnyEvent::SocialBlockChat(blocked) => assert_eq!(blocked.user_to_block_id, "2"),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(event.event_type(), MicroserviceEvent::SocialBlockChat);
+        assert_eq!(serde_json::to_value(&event).unwrap(), payload);
+
+        assert!(AnyEvent::from_value(MicroserviceEvent::AuthNewUser, payload).is_err());
+    }
 }
@@ -0,0 +1,1698 @@
+// @generated by legend-saga-codegen from schema/events.json, do not edit.
+// Change the schema and run `make generate-events` instead.
+
+use super::{non_empty, one_of, PayloadEvent, Validate, ValidationError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
+
+/// Represents the available events in the system.
+#[derive(Debug, Clone, Copy, AsRefStr, EnumString, PartialEq, EnumIter, Hash, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum MicroserviceEvent {
+    #[strum(serialize = "test.image")]
+    TestImage,
+    #[strum(serialize = "test.mint")]
+    TestMint,
+    /// Emitted when an event is received by a microservice before processing starts (audit tracking)
+    #[strum(serialize = "audit.received")]
+    AuditReceived,
+    /// Emitted when an event is successfully processed by a microservice for audit tracking
+    #[strum(serialize = "audit.processed")]
+    AuditProcessed,
+    /// Emitted when a message is rejected/nacked and sent to dead letter queue
+    #[strum(serialize = "audit.dead_letter")]
+    AuditDeadLetter,
+    /// Emitted when an event is published by a microservice (audit tracking)
+    #[strum(serialize = "audit.published")]
+    AuditPublished,
+    /// Emitted when a saga step is received by a microservice before processing starts
+    #[strum(serialize = "audit.saga_step_received")]
+    AuditSagaStepReceived,
+    /// Emitted when a saga step is acked and its reply sent to the orchestrator
+    #[strum(serialize = "audit.saga_step_succeeded")]
+    AuditSagaStepSucceeded,
+    /// Emitted when a saga step is nacked and requeued for another attempt
+    #[strum(serialize = "audit.saga_step_retried")]
+    AuditSagaStepRetried,
+    /// Emitted when a saga step is nacked with its retries exhausted and is not requeued, or failed by its handler
+    #[strum(serialize = "audit.saga_step_exhausted")]
+    AuditSagaStepExhausted,
+    #[strum(serialize = "auth.deleted_user")]
+    AuthDeletedUser,
+    #[strum(serialize = "auth.logout_user")]
+    AuthLogoutUser,
+    #[strum(serialize = "auth.new_user")]
+    AuthNewUser,
+    #[strum(serialize = "auth.blocked_user")]
+    AuthBlockedUser,
+    #[strum(serialize = "auth.operation_created")]
+    AuthOperationCreated,
+    #[strum(serialize = "legend_missions.new_mission_created")]
+    LegendMissionsNewMissionCreated,
+    #[strum(serialize = "legend_missions.ongoing_mission")]
+    LegendMissionsOngoingMission,
+    #[strum(serialize = "legend_missions.mission_finished")]
+    LegendMissionsMissionFinished,
+    #[strum(serialize = "legend_missions.mission_approved")]
+    LegendMissionsMissionApproved,
+    #[strum(serialize = "legend_missions.mission_rejected")]
+    LegendMissionsMissionRejected,
+    #[strum(serialize = "legend_missions.mission_activated")]
+    LegendMissionsMissionActivated,
+    #[strum(serialize = "legend_missions.send_email_code_exchange_mission_completed")]
+    LegendMissionsSendEmailCodeExchangeMissionCompleted,
+    #[strum(serialize = "legend_missions.send_email_gift_card_mission_completed")]
+    LegendMissionsSendEmailGiftCardMissionCompleted,
+    #[strum(serialize = "legend_rankings.rankings_finished")]
+    LegendRankingsRankingsFinished,
+    /// A distinct player's first participation in a ranking was recorded
+    /// (the billable unit — the same player playing 50 times counts once).
+    /// Invalidation signal, not a snapshot: consumers re-fetch the count
+    /// they need rather than trust a total baked into the event.
+    #[strum(serialize = "legend_rankings.billable_participant_recorded")]
+    LegendRankingsBillableParticipantRecorded,
+    #[strum(serialize = "legend_showcase.product_virtual_deleted")]
+    LegendShowcaseProductVirtualDeleted,
+    #[strum(serialize = "legend_showcase.update_allowed_mission_subscription_ids")]
+    LegendShowcaseUpdateAllowedMissionSubscriptionIds,
+    #[strum(serialize = "legend_showcase.update_allowed_ranking_subscription_ids")]
+    LegendShowcaseUpdateAllowedRankingSubscriptionIds,
+    #[strum(serialize = "social.block_chat")]
+    SocialBlockChat,
+    #[strum(serialize = "social.new_user")]
+    SocialNewUser,
+    #[strum(serialize = "social.unblock_chat")]
+    SocialUnblockChat,
+    #[strum(serialize = "social.updated_user")]
+    SocialUpdatedUser,
+    #[strum(serialize = "social.country_created")]
+    SocialCountryCreated,
+    #[strum(serialize = "social.country_updated")]
+    SocialCountryUpdated,
+    #[strum(serialize = "social.country_deleted")]
+    SocialCountryDeleted,
+    #[strum(serialize = "legend_rankings.new_ranking_created")]
+    LegendRankingsNewRankingCreated,
+    #[strum(serialize = "legend_rankings.ranking_submitted_for_review")]
+    LegendRankingsRankingSubmittedForReview,
+    #[strum(serialize = "legend_rankings.ranking_approved")]
+    LegendRankingsRankingApproved,
+    #[strum(serialize = "legend_rankings.ranking_rejected")]
+    LegendRankingsRankingRejected,
+    #[strum(serialize = "legend_rankings.ranking_activated")]
+    LegendRankingsRankingActivated,
+    #[strum(serialize = "legend_rankings.intermediate_reward")]
+    LegendRankingsIntermediateReward,
+    #[strum(serialize = "legend_rankings.participation_reward")]
+    LegendRankingsParticipationReward,
+    // Billing events - Payment and subscription domain events (No Stripe leakage)
+    #[strum(serialize = "billing.payment_created")]
+    BillingPaymentCreated,
+    #[strum(serialize = "billing.payment_succeeded")]
+    BillingPaymentSucceeded,
+    #[strum(serialize = "billing.payment_failed")]
+    BillingPaymentFailed,
+    #[strum(serialize = "billing.payment_refunded")]
+    BillingPaymentRefunded,
+    #[strum(serialize = "billing.subscription_created")]
+    BillingSubscriptionCreated,
+    #[strum(serialize = "billing.subscription_updated")]
+    BillingSubscriptionUpdated,
+    #[strum(serialize = "billing.subscription_renewed")]
+    BillingSubscriptionRenewed,
+    #[strum(serialize = "billing.subscription_canceled")]
+    BillingSubscriptionCanceled,
+    #[strum(serialize = "billing.subscription_expired")]
+    BillingSubscriptionExpired,
+    // Platform events - Level 1 entitlements (an operation pays SIPLEI)
+    #[strum(serialize = "platform.operation_features_changed")]
+    PlatformOperationFeaturesChanged,
+    // Legend Events - Event and registration domain events
+    #[strum(serialize = "legend_events.new_event_created")]
+    LegendEventsNewEventCreated,
+    #[strum(serialize = "legend_events.event_started")]
+    LegendEventsEventStarted,
+    #[strum(serialize = "legend_events.event_ended")]
+    LegendEventsEventEnded,
+    #[strum(serialize = "legend_events.player_registered")]
+    LegendEventsPlayerRegistered,
+    #[strum(serialize = "legend_events.player_joined_waitlist")]
+    LegendEventsPlayerJoinedWaitlist,
+    #[strum(serialize = "legend_events.score_submitted")]
+    LegendEventsScoreSubmitted,
+    #[strum(serialize = "legend_events.events_finished")]
+    LegendEventsEventsFinished,
+    #[strum(serialize = "legend_events.intermediate_reward")]
+    LegendEventsIntermediateReward,
+    #[strum(serialize = "legend_events.participation_reward")]
+    LegendEventsParticipationReward,
+}
+
+impl MicroserviceEvent {
+    /// Version of the payload of the event, sent in the `x-schema-version` header.
+    pub fn schema_version(&self) -> u32 {
+        match self {
+            MicroserviceEvent::BillingSubscriptionCreated
+            | MicroserviceEvent::BillingSubscriptionUpdated
+            | MicroserviceEvent::BillingSubscriptionRenewed => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl MicroserviceEvent {
+    /// JSON Schema of the payload of the event.
+    pub fn payload_schema(&self) -> schemars::Schema {
+        match self {
+            MicroserviceEvent::TestImage => schemars::schema_for!(TestImagePayload),
+            MicroserviceEvent::TestMint => schemars::schema_for!(TestMintPayload),
+            MicroserviceEvent::AuditReceived => schemars::schema_for!(AuditReceivedPayload),
+            MicroserviceEvent::AuditProcessed => schemars::schema_for!(AuditProcessedPayload),
+            MicroserviceEvent::AuditDeadLetter => schemars::schema_for!(AuditDeadLetterPayload),
+            MicroserviceEvent::AuditPublished => schemars::schema_for!(AuditPublishedPayload),
+            MicroserviceEvent::AuditSagaStepReceived => {
+                schemars::schema_for!(AuditSagaStepReceivedPayload)
+            }
+            MicroserviceEvent::AuditSagaStepSucceeded => {
+                schemars::schema_for!(AuditSagaStepSucceededPayload)
+            }
+            MicroserviceEvent::AuditSagaStepRetried => {
+                schemars::schema_for!(AuditSagaStepRetriedPayload)
+            }
+            MicroserviceEvent::AuditSagaStepExhausted => {
+                schemars::schema_for!(AuditSagaStepExhaustedPayload)
+            }
+            MicroserviceEvent::AuthDeletedUser => schemars::schema_for!(AuthDeletedUserPayload),
+            MicroserviceEvent::AuthLogoutUser => schemars::schema_for!(AuthLogoutUserPayload),
+            MicroserviceEvent::AuthNewUser => schemars::schema_for!(AuthNewUserPayload),
+            MicroserviceEvent::AuthBlockedUser => schemars::schema_for!(AuthBlockedUserPayload),
+            MicroserviceEvent::AuthOperationCreated => {
+                schemars::schema_for!(AuthOperationCreatedPayload)
+            }
+            MicroserviceEvent::LegendMissionsNewMissionCreated => {
+                schemars::schema_for!(LegendMissionsNewMissionCreatedEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsOngoingMission => {
+                schemars::schema_for!(LegendMissionsOngoingMissionEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsMissionFinished => {
+                schemars::schema_for!(LegendMissionsMissionFinishedEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsMissionApproved => {
+                schemars::schema_for!(LegendMissionsMissionApprovedEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsMissionRejected => {
+                schemars::schema_for!(LegendMissionsMissionRejectedEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsMissionActivated => {
+                schemars::schema_for!(LegendMissionsMissionActivatedEventPayload)
+            }
+            MicroserviceEvent::LegendMissionsSendEmailCodeExchangeMissionCompleted => {
+                schemars::schema_for!(LegendMissionsSendEmailCodeExchangeMissionCompletedPayload)
+            }
+            MicroserviceEvent::LegendMissionsSendEmailGiftCardMissionCompleted => {
+                schemars::schema_for!(LegendMissionsSendEmailGiftCardMissionCompletedPayload)
+            }
+            MicroserviceEvent::LegendRankingsRankingsFinished => {
+                schemars::schema_for!(LegendRankingsRankingsFinishedEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsBillableParticipantRecorded => {
+                schemars::schema_for!(LegendRankingsBillableParticipantRecordedEventPayload)
+            }
+            MicroserviceEvent::LegendShowcaseProductVirtualDeleted => {
+                schemars::schema_for!(LegendShowcaseProductVirtualDeletedEventPayload)
+            }
+            MicroserviceEvent::LegendShowcaseUpdateAllowedMissionSubscriptionIds => {
+                schemars::schema_for!(LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload)
+            }
+            MicroserviceEvent::LegendShowcaseUpdateAllowedRankingSubscriptionIds => {
+                schemars::schema_for!(LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload)
+            }
+            MicroserviceEvent::SocialBlockChat => schemars::schema_for!(SocialBlockChatPayload),
+            MicroserviceEvent::SocialNewUser => schemars::schema_for!(SocialNewUserPayload),
+            MicroserviceEvent::SocialUnblockChat => schemars::schema_for!(SocialUnblockChatPayload),
+            MicroserviceEvent::SocialUpdatedUser => schemars::schema_for!(SocialUpdatedUserPayload),
+            MicroserviceEvent::SocialCountryCreated => {
+                schemars::schema_for!(SocialCountryCreatedPayload)
+            }
+            MicroserviceEvent::SocialCountryUpdated => {
+                schemars::schema_for!(SocialCountryUpdatedPayload)
+            }
+            MicroserviceEvent::SocialCountryDeleted => {
+                schemars::schema_for!(SocialCountryDeletedPayload)
+            }
+            MicroserviceEvent::LegendRankingsNewRankingCreated => {
+                schemars::schema_for!(LegendRankingsNewRankingCreatedEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsRankingSubmittedForReview => {
+                schemars::schema_for!(LegendRankingsRankingSubmittedForReviewEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsRankingApproved => {
+                schemars::schema_for!(LegendRankingsRankingApprovedEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsRankingRejected => {
+                schemars::schema_for!(LegendRankingsRankingRejectedEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsRankingActivated => {
+                schemars::schema_for!(LegendRankingsRankingActivatedEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsIntermediateReward => {
+                schemars::schema_for!(LegendRankingsIntermediateRewardEventPayload)
+            }
+            MicroserviceEvent::LegendRankingsParticipationReward => {
+                schemars::schema_for!(LegendRankingsParticipationRewardEventPayload)
+            }
+            MicroserviceEvent::BillingPaymentCreated => {
+                schemars::schema_for!(BillingPaymentCreatedPayload)
+            }
+            MicroserviceEvent::BillingPaymentSucceeded => {
+                schemars::schema_for!(BillingPaymentSucceededPayload)
+            }
+            MicroserviceEvent::BillingPaymentFailed => {
+                schemars::schema_for!(BillingPaymentFailedPayload)
+            }
+            MicroserviceEvent::BillingPaymentRefunded => {
+                schemars::schema_for!(BillingPaymentRefundedPayload)
+            }
+            MicroserviceEvent::BillingSubscriptionCreated => {
+                schemars::schema_for!(BillingSubscriptionCreatedPayload)
+            }
+            MicroserviceEvent::BillingSubscriptionUpdated => {
+                schemars::schema_for!(BillingSubscriptionUpdatedPayload)
+            }
+            MicroserviceEvent::BillingSubscriptionRenewed => {
+                schemars::schema_for!(BillingSubscriptionRenewedPayload)
+            }
+            MicroserviceEvent::BillingSubscriptionCanceled => {
+                schemars::schema_for!(BillingSubscriptionCanceledPayload)
+            }
+            MicroserviceEvent::BillingSubscriptionExpired => {
+                schemars::schema_for!(BillingSubscriptionExpiredPayload)
+            }
+            MicroserviceEvent::PlatformOperationFeaturesChanged => {
+                schemars::schema_for!(PlatformOperationFeaturesChangedPayload)
+            }
+            MicroserviceEvent::LegendEventsNewEventCreated => {
+                schemars::schema_for!(LegendEventsNewEventCreatedPayload)
+            }
+            MicroserviceEvent::LegendEventsEventStarted => {
+                schemars::schema_for!(LegendEventsEventStartedPayload)
+            }
+            MicroserviceEvent::LegendEventsEventEnded => {
+                schemars::schema_for!(LegendEventsEventEndedPayload)
+            }
+            MicroserviceEvent::LegendEventsPlayerRegistered => {
+                schemars::schema_for!(LegendEventsPlayerRegisteredPayload)
+            }
+            MicroserviceEvent::LegendEventsPlayerJoinedWaitlist => {
+                schemars::schema_for!(LegendEventsPlayerJoinedWaitlistPayload)
+            }
+            MicroserviceEvent::LegendEventsScoreSubmitted => {
+                schemars::schema_for!(LegendEventsScoreSubmittedPayload)
+            }
+            MicroserviceEvent::LegendEventsEventsFinished => {
+                schemars::schema_for!(LegendEventsEventsFinishedPayload)
+            }
+            MicroserviceEvent::LegendEventsIntermediateReward => {
+                schemars::schema_for!(LegendEventsIntermediateRewardPayload)
+            }
+            MicroserviceEvent::LegendEventsParticipationReward => {
+                schemars::schema_for!(LegendEventsParticipationRewardPayload)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(TestImage, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct TestImagePayload {
+    pub image: String,
+}
+
+impl Validate for TestImagePayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(TestMint, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct TestMintPayload {
+    pub mint: String,
+}
+
+impl Validate for TestMintPayload {}
+
+/// Payload for audit.received event - tracks when event is received before processing
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditReceived, custom_validate, payload_of)]
+pub struct AuditReceivedPayload {
+    /// The microservice that published the original event
+    pub publisher_microservice: String,
+    /// The microservice that received the event
+    pub receiver_microservice: String,
+    /// The event that was received
+    pub received_event: String,
+    /// Timestamp when the event was received (UNIX timestamp in milliseconds)
+    pub received_at: u64,
+    /// The queue name from which the event was consumed
+    pub queue_name: String,
+    /// Event identifier for cross-event correlation (UUID v7)
+    pub event_id: String,
+}
+
+impl Validate for AuditReceivedPayload {}
+
+/// Payload for audit.processed event - tracks successful event processing
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditProcessed, custom_validate, payload_of)]
+pub struct AuditProcessedPayload {
+    /// The microservice that published the original event
+    pub publisher_microservice: String,
+    /// The microservice that processed the event
+    pub processor_microservice: String,
+    /// The original event that was processed
+    pub processed_event: String,
+    /// Timestamp when the event was processed (UNIX timestamp in milliseconds)
+    pub processed_at: u64,
+    /// The queue name where the event was consumed
+    pub queue_name: String,
+    /// Event identifier for cross-event correlation (UUID v7)
+    pub event_id: String,
+}
+
+impl Validate for AuditProcessedPayload {}
+
+/// Payload for audit.dead_letter event - tracks when message is rejected/nacked
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditDeadLetter, custom_validate, payload_of)]
+pub struct AuditDeadLetterPayload {
+    /// The microservice that published the original event
+    pub publisher_microservice: String,
+    /// The microservice that rejected the event
+    pub rejector_microservice: String,
+    /// The original event that was rejected
+    pub rejected_event: String,
+    /// Timestamp when the event was rejected (UNIX timestamp in milliseconds)
+    pub rejected_at: u64,
+    /// The queue name where the event was rejected from
+    pub queue_name: String,
+    /// Reason for rejection: the error given by the handler, or the retry strategy
+    /// (delay, fibonacci_strategy, etc.) when none was given
+    pub rejection_reason: String,
+    /// Optional retry count
+    pub retry_count: Option<u32>,
+    /// Retry strategy used to requeue the event (delay, fibonacci_strategy)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_strategy: Option<String>,
+    /// Event identifier for cross-event correlation (UUID v7)
+    pub event_id: String,
+}
+
+impl Validate for AuditDeadLetterPayload {}
+
+/// Payload for audit.published event - tracks when event is published at the source microservice
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditPublished, custom_validate, payload_of)]
+pub struct AuditPublishedPayload {
+    /// The microservice that published the event
+    pub publisher_microservice: String,
+    /// The event that was published
+    pub published_event: String,
+    /// Timestamp when the event was published (UNIX timestamp in milliseconds)
+    pub published_at: u64,
+    /// Event identifier for cross-event correlation (UUID v7)
+    pub event_id: String,
+}
+
+impl Validate for AuditPublishedPayload {}
+
+/// Payload for audit.saga_step_received event - tracks when a saga step is received before processing
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditSagaStepReceived, custom_validate, payload_of)]
+pub struct AuditSagaStepReceivedPayload {
+    /// The saga the step belongs to
+    pub saga_id: i32,
+    /// The step command, e.g. "crypto_reward:transfer_reward_to_winners"
+    pub command: String,
+    /// The microservice that owns the step
+    pub microservice: String,
+    /// The operation (tenant) the saga belongs to
+    pub operation_id: Option<String>,
+    /// Timestamp when the step was received (UNIX timestamp in milliseconds)
+    pub received_at: u64,
+    /// The queue name from which the step was consumed
+    pub queue_name: String,
+}
+
+impl Validate for AuditSagaStepReceivedPayload {}
+
+/// Payload for audit.saga_step_succeeded event - tracks a saga step acked by its microservice
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditSagaStepSucceeded, custom_validate, payload_of)]
+pub struct AuditSagaStepSucceededPayload {
+    /// The saga the step belongs to
+    pub saga_id: i32,
+    /// The step command, e.g. "crypto_reward:transfer_reward_to_winners"
+    pub command: String,
+    /// The microservice that owns the step
+    pub microservice: String,
+    /// The operation (tenant) the saga belongs to
+    pub operation_id: Option<String>,
+    /// Timestamp when the step succeeded (UNIX timestamp in milliseconds)
+    pub succeeded_at: u64,
+    /// The queue name from which the step was consumed
+    pub queue_name: String,
+}
+
+impl Validate for AuditSagaStepSucceededPayload {}
+
+/// Payload for audit.saga_step_retried event - tracks a saga step nacked and requeued
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditSagaStepRetried, custom_validate, payload_of)]
+pub struct AuditSagaStepRetriedPayload {
+    /// The saga the step belongs to
+    pub saga_id: i32,
+    /// The step command, e.g. "crypto_reward:transfer_reward_to_winners"
+    pub command: String,
+    /// The microservice that owns the step
+    pub microservice: String,
+    /// The operation (tenant) the saga belongs to
+    pub operation_id: Option<String>,
+    /// Timestamp when the step was nacked (UNIX timestamp in milliseconds)
+    pub retried_at: u64,
+    /// The queue name from which the step was consumed
+    pub queue_name: String,
+    /// The number of the retry
+    pub retry_count: u32,
+    /// Retry strategy used to requeue the step (delay, fibonacci_strategy)
+    pub retry_strategy: String,
+    /// Delay before the next attempt, in milliseconds
+    pub delay_ms: u64,
+    /// The error given by the handler
+    pub error: Option<String>,
+}
+
+impl Validate for AuditSagaStepRetriedPayload {}
+
+/// Payload for audit.saga_step_exhausted event - tracks a saga step that ran out of retries
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuditSagaStepExhausted, custom_validate, payload_of)]
+pub struct AuditSagaStepExhaustedPayload {
+    /// The saga the step belongs to
+    pub saga_id: i32,
+    /// The step command, e.g. "crypto_reward:transfer_reward_to_winners"
+    pub command: String,
+    /// The microservice that owns the step
+    pub microservice: String,
+    /// The operation (tenant) the saga belongs to
+    pub operation_id: Option<String>,
+    /// Timestamp when the step was given up (UNIX timestamp in milliseconds)
+    pub exhausted_at: u64,
+    /// The queue name from which the step was consumed
+    pub queue_name: String,
+    /// The number of attempts made
+    pub retry_count: u32,
+    /// Retry strategy used to requeue the step (delay, fibonacci_strategy), fail when the handler failed the step
+    pub retry_strategy: String,
+    /// The error given by the handler in the last attempt, the failure reason for fail
+    pub error: Option<String>,
+}
+
+impl Validate for AuditSagaStepExhaustedPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuthDeletedUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthDeletedUserPayload {
+    pub user_id: String,
+}
+
+impl Validate for AuthDeletedUserPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuthLogoutUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthLogoutUserPayload {
+    pub user_id: String,
+}
+
+impl Validate for AuthLogoutUserPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuthNewUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthNewUserPayload {
+    pub id: String,
+    pub email: String,
+    pub username: String,
+    pub userlastname: String,
+}
+
+impl Validate for AuthNewUserPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuthBlockedUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthBlockedUserPayload {
+    pub user_id: String,
+    pub block_type: String,
+    pub block_reason: Option<String>,
+    pub block_expiration_hours: Option<i32>,
+}
+
+impl Validate for AuthBlockedUserPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// identity_mode is immutable once an operation exists, so this creation-time
+/// event is the only one a consumer needs to build a local
+/// {operation_id -> identity_mode} projection (IDR-01,
+/// MODULO-IDENTITY-RESOLVER.md).
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(AuthOperationCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthOperationCreatedPayload {
+    pub operation_id: String,
+    pub identity_mode: String,
+}
+
+impl Validate for AuthOperationCreatedPayload {}
+
+/// Represents the fields that will be sent by email when a mission is created.
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsNewMissionCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsNewMissionCreatedEventPayload {
+    pub mission_id: i32,
+    pub title: String,
+    pub author: String,
+    pub author_email: String,
+    pub reward_kind: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+impl Validate for LegendMissionsNewMissionCreatedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsOngoingMission, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsOngoingMissionEventPayload {
+    pub redis_key: String,
+}
+
+impl Validate for LegendMissionsOngoingMissionEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsMissionFinished, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsMissionFinishedEventPayload {
+    pub mission_title: String,
+    pub participants: Vec<MissionFinishedParticipant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_seats: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registered_participants: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+}
+
+impl Validate for LegendMissionsMissionFinishedEventPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.participants.validate()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsMissionApproved, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsMissionApprovedEventPayload {
+    pub mission_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub start_date: String,
+}
+
+impl Validate for LegendMissionsMissionApprovedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsMissionRejected, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsMissionRejectedEventPayload {
+    pub mission_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub admin_notes: String,
+}
+
+impl Validate for LegendMissionsMissionRejectedEventPayload {}
+
+/// Emitted by the finalize ticker when an approved mission reaches its start_date
+/// and becomes active. Mirrors the Go and TS library payloads.
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsMissionActivated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsMissionActivatedEventPayload {
+    pub title: String,
+    pub author: String,
+    pub author_email: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+impl Validate for LegendMissionsMissionActivatedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsSendEmailCodeExchangeMissionCompleted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsSendEmailCodeExchangeMissionCompletedPayload {
+    pub user_id: String,
+    pub mission_title: String,
+    pub code_value: String,
+    pub code_description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ecommerce_redeem_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maps_redeem_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_name: Option<String>,
+}
+
+impl Validate for LegendMissionsSendEmailCodeExchangeMissionCompletedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendMissionsSendEmailGiftCardMissionCompleted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendMissionsSendEmailGiftCardMissionCompletedPayload {
+    pub user_id: String,
+    pub mission_title: String,
+    pub description: String,
+    pub file_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ecommerce_redeem_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maps_redeem_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_name: Option<String>,
+}
+
+impl Validate for LegendMissionsSendEmailGiftCardMissionCompletedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsRankingsFinished, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsRankingsFinishedEventPayload {
+    pub completed_rankings: Vec<CompletedRanking>,
+}
+
+impl Validate for LegendRankingsRankingsFinishedEventPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.completed_rankings.validate()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsBillableParticipantRecorded, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsBillableParticipantRecordedEventPayload {
+    pub operation_id: String,
+    pub source_type: String,
+    pub source_id: String,
+    /// Needed so consumers can dedupe on (operation_id, source_type,
+    /// source_id, user_ref) — the same composite key billable_participants
+    /// uses — and stay correct under RabbitMQ redelivery instead of
+    /// double-counting.
+    pub user_ref: String,
+    pub occurred_at: String,
+}
+
+impl Validate for LegendRankingsBillableParticipantRecordedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendShowcaseProductVirtualDeleted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendShowcaseProductVirtualDeletedEventPayload {
+    /// Unique identifier of the deleted virtual product
+    pub product_virtual_id: String,
+    /// Slug of the deleted virtual product
+    pub product_virtual_slug: String,
+}
+
+impl Validate for LegendShowcaseProductVirtualDeletedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendShowcaseUpdateAllowedMissionSubscriptionIds, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload {
+    pub product_virtual_slug: String,
+    pub allowed_subscription_ids: Vec<String>,
+}
+
+impl Validate for LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendShowcaseUpdateAllowedRankingSubscriptionIds, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload {
+    pub product_virtual_id: String,
+    pub allowed_subscription_ids: Vec<String>,
+}
+
+impl Validate for LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialBlockChat, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialBlockChatPayload {
+    pub user_id: String,
+    pub user_to_block_id: String,
+}
+
+impl Validate for SocialBlockChatPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialNewUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialNewUserPayload {
+    pub social_user: SocialUser,
+}
+
+impl Validate for SocialNewUserPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.social_user.validate()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialUnblockChat, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialUnblockChatPayload {
+    pub user_id: String,
+    pub user_to_unblock_id: String,
+}
+
+impl Validate for SocialUnblockChatPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialUpdatedUser, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialUpdatedUserPayload {
+    pub social_user: SocialUser,
+}
+
+impl Validate for SocialUpdatedUserPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.social_user.validate()?;
+        Ok(())
+    }
+}
+
+/// Payload for social.country_created event. Source of truth lives in social;
+/// consumers upsert their local projection keyed by `code`.
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialCountryCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialCountryCreatedPayload {
+    pub code: String,
+    pub name: String,
+    pub is_enabled: bool,
+}
+
+impl Validate for SocialCountryCreatedPayload {}
+
+/// Payload for social.country_updated event (name and/or isEnabled changed).
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialCountryUpdated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialCountryUpdatedPayload {
+    pub code: String,
+    pub name: String,
+    pub is_enabled: bool,
+}
+
+impl Validate for SocialCountryUpdatedPayload {}
+
+/// Payload for social.country_deleted event. Consumers delete the matching
+/// entry from their local projection.
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(SocialCountryDeleted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct SocialCountryDeletedPayload {
+    pub code: String,
+}
+
+impl Validate for SocialCountryDeletedPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsNewRankingCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsNewRankingCreatedEventPayload {
+    pub title: String,
+    pub description: String,
+    pub author_email: String,
+    pub reward_type: String,
+    pub start_at: String,
+    pub ends_at: String,
+    pub nft_blockchain_network: Option<String>,
+    pub nft_contract_address: Option<String>,
+    pub wallet_crypto_asset: Option<String>,
+    pub notification_config: Option<NotificationConfig>,
+}
+
+impl Validate for LegendRankingsNewRankingCreatedEventPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.notification_config.validate()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsRankingSubmittedForReview, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsRankingSubmittedForReviewEventPayload {
+    pub ranking_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub created_at: String,
+}
+
+impl Validate for LegendRankingsRankingSubmittedForReviewEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsRankingApproved, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsRankingApprovedEventPayload {
+    pub ranking_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub start_at: String,
+}
+
+impl Validate for LegendRankingsRankingApprovedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsRankingRejected, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsRankingRejectedEventPayload {
+    pub ranking_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub reasons: String,
+}
+
+impl Validate for LegendRankingsRankingRejectedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsRankingActivated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsRankingActivatedEventPayload {
+    pub ranking_id: i32,
+    pub title: String,
+    pub author_email: String,
+    pub start_at: String,
+}
+
+impl Validate for LegendRankingsRankingActivatedEventPayload {}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsIntermediateReward, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsIntermediateRewardEventPayload {
+    pub user_id: String,
+    pub ranking_id: i32,
+    pub intermediate_reward_type: String,
+    pub reward_config: serde_json::Value,
+    pub template_name: String,
+    pub template_data: serde_json::Value,
+}
+
+impl Validate for LegendRankingsIntermediateRewardEventPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendRankingsParticipationReward, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendRankingsParticipationRewardEventPayload {
+    pub user_id: String,
+    pub ranking_id: i32,
+    pub participation_reward_type: String,
+    pub reward_config: serde_json::Value,
+    pub template_name: String,
+    pub template_data: serde_json::Value,
+}
+
+impl Validate for LegendRankingsParticipationRewardEventPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.payment.created event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingPaymentCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingPaymentCreatedPayload {
+    pub payment_id: String,
+    pub user_id: String,
+    pub amount: i64,
+    pub currency: String,
+    /// One of `pending`, `processing`.
+    pub status: String,
+    pub metadata: HashMap<String, String>,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingPaymentCreatedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("payment_id", &self.payment_id)?;
+        non_empty("user_id", &self.user_id)?;
+        one_of("status", &self.status, &["pending", "processing"])?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.payment.succeeded event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingPaymentSucceeded, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingPaymentSucceededPayload {
+    pub payment_id: String,
+    pub user_id: String,
+    pub amount: i64,
+    pub currency: String,
+    pub metadata: HashMap<String, String>,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingPaymentSucceededPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("payment_id", &self.payment_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.payment.failed event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingPaymentFailed, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingPaymentFailedPayload {
+    pub payment_id: String,
+    pub user_id: String,
+    pub amount: i64,
+    pub currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingPaymentFailedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("payment_id", &self.payment_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.payment.refunded event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingPaymentRefunded, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingPaymentRefundedPayload {
+    pub payment_id: String,
+    pub user_id: String,
+    pub amount: i64,
+    pub refunded_amount: i64,
+    pub currency: String,
+    pub metadata: HashMap<String, String>,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingPaymentRefundedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("payment_id", &self.payment_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.subscription.created event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingSubscriptionCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingSubscriptionCreatedPayload {
+    pub subscription_id: String,
+    pub user_id: String,
+    pub plan_id: String,
+    pub plan_slug: String,
+    /// One of `pending`, `active`, `trialing`.
+    pub status: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub occurred_at: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Validate for BillingSubscriptionCreatedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("subscription_id", &self.subscription_id)?;
+        non_empty("user_id", &self.user_id)?;
+        one_of("status", &self.status, &["pending", "active", "trialing"])?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.subscription.updated event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingSubscriptionUpdated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingSubscriptionUpdatedPayload {
+    pub subscription_id: String,
+    pub user_id: String,
+    pub plan_id: String,
+    pub plan_slug: String,
+    /// One of `active`, `past_due`, `unpaid`, `paused`, `trialing`.
+    pub status: String,
+    pub cancel_at_period_end: bool,
+    pub period_start: String,
+    pub period_end: String,
+    pub occurred_at: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Validate for BillingSubscriptionUpdatedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("subscription_id", &self.subscription_id)?;
+        non_empty("user_id", &self.user_id)?;
+        one_of(
+            "status",
+            &self.status,
+            &["active", "past_due", "unpaid", "paused", "trialing"],
+        )?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.subscription.renewed event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingSubscriptionRenewed, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingSubscriptionRenewedPayload {
+    pub subscription_id: String,
+    pub user_id: String,
+    pub plan_id: String,
+    pub plan_slug: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub occurred_at: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl Validate for BillingSubscriptionRenewedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("subscription_id", &self.subscription_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.subscription.canceled event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingSubscriptionCanceled, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingSubscriptionCanceledPayload {
+    pub subscription_id: String,
+    pub user_id: String,
+    pub plan_id: String,
+    pub plan_slug: String,
+    pub canceled_at: String,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingSubscriptionCanceledPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("subscription_id", &self.subscription_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for billing.subscription.expired event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(BillingSubscriptionExpired, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingSubscriptionExpiredPayload {
+    pub subscription_id: String,
+    pub user_id: String,
+    pub plan_id: String,
+    pub plan_slug: String,
+    pub expired_at: String,
+    pub occurred_at: String,
+}
+
+impl Validate for BillingSubscriptionExpiredPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("subscription_id", &self.subscription_id)?;
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// An operation's effective feature set changed (plan assignment or feature
+/// override) — an invalidation signal, not a snapshot. A consumer refetches
+/// the effective set from legend-billing rather than trust a payload that
+/// could drift from the feature schema that lives there.
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(PlatformOperationFeaturesChanged, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformOperationFeaturesChangedPayload {
+    pub operation_id: String,
+}
+
+impl Validate for PlatformOperationFeaturesChangedPayload {}
+
+/// Payload for legend_events.new_event_created event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsNewEventCreated, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsNewEventCreatedPayload {
+    pub event_id: i32,
+    pub title: String,
+    pub description: String,
+    pub author_email: String,
+    pub reward_type: Option<String>,
+    pub start_date: String,
+    pub end_date: String,
+    pub max_players: Option<i32>,
+    pub ticket_price_usd: Option<f32>,
+    pub is_free_tournament: bool,
+    /// Optional notification config (JSON) to enrich email templates
+    pub notification_config: Option<NotificationConfig>,
+}
+
+impl Validate for LegendEventsNewEventCreatedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.notification_config.validate()?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.event_started event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsEventStarted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsEventStartedPayload {
+    pub event_id: i32,
+    pub title: String,
+    pub started_at: String,
+}
+
+impl Validate for LegendEventsEventStartedPayload {}
+
+/// Payload for legend_events.event_ended event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsEventEnded, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsEventEndedPayload {
+    pub event_id: i32,
+    pub title: String,
+    pub ended_at: String,
+    pub total_participants: i32,
+}
+
+impl Validate for LegendEventsEventEndedPayload {}
+
+/// Payload for legend_events.player_registered event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsPlayerRegistered, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsPlayerRegisteredPayload {
+    pub event_id: i32,
+    pub user_id: String,
+    pub payment_id: Option<String>,
+    pub amount_paid: Option<f32>,
+    pub is_free: bool,
+    pub registered_at: String,
+}
+
+impl Validate for LegendEventsPlayerRegisteredPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.player_joined_waitlist event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsPlayerJoinedWaitlist, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsPlayerJoinedWaitlistPayload {
+    pub event_id: i32,
+    pub user_id: String,
+    pub position: i32,
+    pub joined_at: String,
+}
+
+impl Validate for LegendEventsPlayerJoinedWaitlistPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.score_submitted event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsScoreSubmitted, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsScoreSubmittedPayload {
+    pub event_id: i32,
+    pub user_id: String,
+    pub score: f64,
+    pub total_score: f64,
+    pub match_id: Option<String>,
+    pub submitted_at: String,
+}
+
+impl Validate for LegendEventsScoreSubmittedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.events_finished event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsEventsFinished, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsEventsFinishedPayload {
+    pub completed_events: Vec<CompletedEvent>,
+}
+
+impl Validate for LegendEventsEventsFinishedPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.completed_events.validate()?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.intermediate_reward event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsIntermediateReward, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsIntermediateRewardPayload {
+    pub user_id: String,
+    pub event_id: i32,
+    pub intermediate_reward_type: String,
+    pub reward_config: serde_json::Value,
+    pub template_name: String,
+    pub template_data: serde_json::Value,
+}
+
+impl Validate for LegendEventsIntermediateRewardPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+/// Payload for legend_events.participation_reward event
+#[derive(Debug, Serialize, Deserialize, Clone, PayloadEvent)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[event(LegendEventsParticipationReward, custom_validate, payload_of)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEventsParticipationRewardPayload {
+    pub user_id: String,
+    pub event_id: i32,
+    pub participation_reward_type: String,
+    pub reward_config: serde_json::Value,
+    pub template_name: String,
+    pub template_data: serde_json::Value,
+}
+
+impl Validate for LegendEventsParticipationRewardPayload {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MissionFinishedParticipant {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+}
+
+impl Validate for MissionFinishedParticipant {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct RankingWinners {
+    pub user_id: String,
+    pub reward: String,
+}
+
+impl Validate for RankingWinners {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedRanking {
+    pub title: String,
+    pub description: String,
+    pub author_email: String,
+    pub ends_at: String,
+    pub reward: String,
+    pub reward_type: String,
+    pub winners: Vec<RankingWinners>,
+    /// Present only if reward_type is "Nft"
+    pub nft_blockchain_network: Option<String>,
+    /// Present only if reward_type is "Nft"
+    pub nft_contract_address: Option<String>,
+    /// Present only if reward_type is "Crypto"
+    pub wallet_crypto_asset: Option<String>,
+    /// Optional notification config (JSON) to enrich email templates
+    pub notification_config: Option<serde_json::Value>,
+}
+
+impl Validate for CompletedRanking {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.winners.validate()?;
+        Ok(())
+    }
+}
+
+/// Gender represents the possible genders a social user can have.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Gender {
+    Male,
+    Female,
+    Undefined,
+}
+
+/// Represents the geographical location of a user
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct UserLocation {
+    pub continent: String,
+    pub country: String,
+    pub region: String,
+    pub city: String,
+}
+
+impl Validate for UserLocation {}
+
+/// SocialUser represents the social user model.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SocialUser {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    pub gender: Gender,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_public_profile: Option<bool>,
+    pub followers: Vec<String>,
+    pub following: Vec<String>,
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub birthday: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<UserLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_screenshot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glb_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub social_media: Option<HashMap<String, String>>,
+    pub preferences: Vec<String>,
+    pub blocked_users: Vec<String>,
+    #[serde(rename = "RPMAvatarId", skip_serializing_if = "Option::is_none")]
+    pub rpm_avatar_id: Option<String>,
+    #[serde(rename = "RPMUserId", skip_serializing_if = "Option::is_none")]
+    pub rpm_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paid_price_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Validate for SocialUser {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.location.validate()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationConfig {
+    pub custom_emails: Option<Vec<String>>,
+    pub template_name: String,
+}
+
+impl Validate for NotificationConfig {}
+
+/// Represents a completed event with its winners
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedEvent {
+    pub event_id: i32,
+    pub title: String,
+    pub description: String,
+    pub author_email: String,
+    pub ends_at: String,
+    pub reward: Option<String>,
+    pub reward_type: Option<String>,
+    pub winners: Vec<EventWinner>,
+    /// Optional notification config forwarded from event
+    pub notification_config: Option<serde_json::Value>,
+}
+
+impl Validate for CompletedEvent {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.winners.validate()?;
+        Ok(())
+    }
+}
+
+/// Represents a winner in an event
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EventWinner {
+    pub user_id: String,
+    pub position: i32,
+    pub score: f64,
+}
+
+impl Validate for EventWinner {
+    fn validate(&self) -> Result<(), ValidationError> {
+        non_empty("user_id", &self.user_id)?;
+        Ok(())
+    }
+}
+
+any_event! {
+    TestImage(TestImagePayload),
+    TestMint(TestMintPayload),
+    AuditReceived(AuditReceivedPayload),
+    AuditProcessed(AuditProcessedPayload),
+    AuditDeadLetter(AuditDeadLetterPayload),
+    AuditPublished(AuditPublishedPayload),
+    AuditSagaStepReceived(AuditSagaStepReceivedPayload),
+    AuditSagaStepSucceeded(AuditSagaStepSucceededPayload),
+    AuditSagaStepRetried(AuditSagaStepRetriedPayload),
+    AuditSagaStepExhausted(AuditSagaStepExhaustedPayload),
+    AuthDeletedUser(AuthDeletedUserPayload),
+    AuthLogoutUser(AuthLogoutUserPayload),
+    AuthNewUser(AuthNewUserPayload),
+    AuthBlockedUser(AuthBlockedUserPayload),
+    AuthOperationCreated(AuthOperationCreatedPayload),
+    LegendMissionsNewMissionCreated(LegendMissionsNewMissionCreatedEventPayload),
+    LegendMissionsOngoingMission(LegendMissionsOngoingMissionEventPayload),
+    LegendMissionsMissionFinished(LegendMissionsMissionFinishedEventPayload),
+    LegendMissionsMissionApproved(LegendMissionsMissionApprovedEventPayload),
+    LegendMissionsMissionRejected(LegendMissionsMissionRejectedEventPayload),
+    LegendMissionsMissionActivated(LegendMissionsMissionActivatedEventPayload),
+    LegendMissionsSendEmailCodeExchangeMissionCompleted(LegendMissionsSendEmailCodeExchangeMissionCompletedPayload),
+    LegendMissionsSendEmailGiftCardMissionCompleted(LegendMissionsSendEmailGiftCardMissionCompletedPayload),
+    LegendRankingsRankingsFinished(LegendRankingsRankingsFinishedEventPayload),
+    LegendRankingsBillableParticipantRecorded(LegendRankingsBillableParticipantRecordedEventPayload),
+    LegendShowcaseProductVirtualDeleted(LegendShowcaseProductVirtualDeletedEventPayload),
+    LegendShowcaseUpdateAllowedMissionSubscriptionIds(LegendShowcaseUpdateAllowedMissionSubscriptionIdsEventPayload),
+    LegendShowcaseUpdateAllowedRankingSubscriptionIds(LegendShowcaseUpdateAllowedRankingSubscriptionIdsEventPayload),
+    SocialBlockChat(SocialBlockChatPayload),
+    SocialNewUser(SocialNewUserPayload),
+    SocialUnblockChat(SocialUnblockChatPayload),
+    SocialUpdatedUser(SocialUpdatedUserPayload),
+    SocialCountryCreated(SocialCountryCreatedPayload),
+    SocialCountryUpdated(SocialCountryUpdatedPayload),
+    SocialCountryDeleted(SocialCountryDeletedPayload),
+    LegendRankingsNewRankingCreated(LegendRankingsNewRankingCreatedEventPayload),
+    LegendRankingsRankingSubmittedForReview(LegendRankingsRankingSubmittedForReviewEventPayload),
+    LegendRankingsRankingApproved(LegendRankingsRankingApprovedEventPayload),
+    LegendRankingsRankingRejected(LegendRankingsRankingRejectedEventPayload),
+    LegendRankingsRankingActivated(LegendRankingsRankingActivatedEventPayload),
+    LegendRankingsIntermediateReward(LegendRankingsIntermediateRewardEventPayload),
+    LegendRankingsParticipationReward(LegendRankingsParticipationRewardEventPayload),
+    BillingPaymentCreated(BillingPaymentCreatedPayload),
+    BillingPaymentSucceeded(BillingPaymentSucceededPayload),
+    BillingPaymentFailed(BillingPaymentFailedPayload),
+    BillingPaymentRefunded(BillingPaymentRefundedPayload),
+    BillingSubscriptionCreated(BillingSubscriptionCreatedPayload),
+    BillingSubscriptionUpdated(BillingSubscriptionUpdatedPayload),
+    BillingSubscriptionRenewed(BillingSubscriptionRenewedPayload),
+    BillingSubscriptionCanceled(BillingSubscriptionCanceledPayload),
+    BillingSubscriptionExpired(BillingSubscriptionExpiredPayload),
+    PlatformOperationFeaturesChanged(PlatformOperationFeaturesChangedPayload),
+    LegendEventsNewEventCreated(LegendEventsNewEventCreatedPayload),
+    LegendEventsEventStarted(LegendEventsEventStartedPayload),
+    LegendEventsEventEnded(LegendEventsEventEndedPayload),
+    LegendEventsPlayerRegistered(LegendEventsPlayerRegisteredPayload),
+    LegendEventsPlayerJoinedWaitlist(LegendEventsPlayerJoinedWaitlistPayload),
+    LegendEventsScoreSubmitted(LegendEventsScoreSubmittedPayload),
+    LegendEventsEventsFinished(LegendEventsEventsFinishedPayload),
+    LegendEventsIntermediateReward(LegendEventsIntermediateRewardPayload),
+    LegendEventsParticipationReward(LegendEventsParticipationRewardPayload),
+}
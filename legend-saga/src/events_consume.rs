@@ -1,9 +1,12 @@
+use crate::circuit_breaker::{Admission, CircuitBreakers};
 use crate::emitter::Emitter;
 use crate::events::{
-    AuditDeadLetterPayload, AuditProcessedPayload, AuditReceivedPayload, MicroserviceEvent,
+    AnyEvent, AuditDeadLetterPayload, AuditProcessedPayload, AuditReceivedPayload,
+    MicroserviceEvent,
 };
 use crate::my_delivery::MyDelivery;
-use crate::nack::Nack;
+use crate::nack::{now_millis, Nack};
+pub use crate::nack::{NackOptions, NackOutcome, RetryHistory, RetryStrategy};
 use crate::queue_consumer_props::Queue;
 use futures_lite::StreamExt;
 use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions};
@@ -13,22 +16,29 @@ use serde::Deserialize;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use strum::IntoEnumIterator;
 use tracing::{error, info, warn};
 use crate::connection::{RabbitMQClient, RabbitMQError};
 use crate::operation::{operation_from_headers, report_missing_operation, with_operation};
+use crate::payload_format::decode_payload;
+use crate::publish_event::spawn_audit_event;
+use crate::raw_payload::RawPayload;
+use crate::validation::validate_consumed;
+use crate::versioning::schema_version_from_headers;
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct EventHandler {
-    payload: HashMap<String, Value>,
+    payload: RawPayload,
     channel: EventsConsumeChannel,
     microservice: String,
-    processed_event: String,
+    event: MicroserviceEvent,
     publisher_microservice: String,
     event_id: String,
     operation_id: Option<String>,
+    circuit_breakers: CircuitBreakers,
 }
 impl EventHandler {
 
@@ -45,130 +55,107 @@ impl EventHandler {
     pub fn event_id(&self) -> &String {
         &self.event_id
     }
+
+    /// Retry count, first failure and errors recorded by the previous attempts.
+    pub fn retry_history(&self) -> RetryHistory {
+        RetryHistory::from_headers(&self.channel.delivery.headers)
+    }
     
+    /// The event delivered.
+    pub fn event(&self) -> MicroserviceEvent {
+        self.event
+    }
+
+    /// The payload parsed as the payload of [`EventHandler::event`], for handlers that
+    /// match on every event.
+    pub fn typed(&self) -> Result<AnyEvent, serde_json::Error> {
+        self.payload.typed(self.event)
+    }
+
+    /// Deserializes the payload, as received, into `T`.
     pub fn parse_payload<T>(&self) -> Result<T, serde_json::Error>
     where
         T: for<'de> Deserialize<'de>,
     {
-        let json_value = serde_json::to_value(self.payload.clone())?;
-        serde_json::from_value(json_value)
+        self.payload.parse()
     }
 
+    /// The payload as a map, parsed the first time it is asked for.
     pub fn get_payload(&self) -> &HashMap<String, Value> {
-        &self.payload
+        self.payload.as_map()
     }
 
     pub async fn ack(&self) -> Result<(), RabbitMQError> {
         // First, ack the original message
         self.channel.ack().await?;
+        self.circuit_breakers.record_success(self.event.as_ref());
 
         // Then emit audit.processed event automatically
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let audit_payload = AuditProcessedPayload {
-            publisher_microservice: self.publisher_microservice.clone(),
-            processor_microservice: self.microservice.clone(),
-            processed_event: self.processed_event.clone(),
-            processed_at: timestamp,
-            queue_name: self.channel.queue_name.clone(),
-            event_id: self.event_id.clone(),
-        };
-
-        let operation_id = self.operation_id.clone();
-        // Emit the audit event using the new direct exchange method
-        tokio::spawn(async move {
-            with_operation(operation_id, async {
-                if let Err(e) = RabbitMQClient::publish_audit_event(audit_payload).await {
-                    error!("Failed to emit audit.processed event: {:?}", e);
-                }
-            })
-            .await;
-        });
+        spawn_audit_event(
+            AuditProcessedPayload {
+                publisher_microservice: self.publisher_microservice.clone(),
+                processor_microservice: self.microservice.clone(),
+                processed_event: self.event.as_ref().to_string(),
+                processed_at: now_millis(),
+                queue_name: self.channel.queue_name.clone(),
+                event_id: self.event_id.clone(),
+            },
+            self.operation_id.clone(),
+        );
 
         Ok(())
     }
 
+    /// Rejects the event and requeues it as `options` says until its retries run out. The
+    /// failure counts in the circuit breaker of the event and emits audit.dead_letter.
+    pub async fn nack(&self, options: NackOptions) -> Result<NackOutcome, RabbitMQError> {
+        let outcome = self.channel.nack.nack(&options).await?;
+        self.on_nack(options.strategy.name(), options.error, outcome.count);
+        Ok(outcome)
+    }
+
+    /// [`EventHandler::nack`] with [`NackOptions::delay`].
     pub async fn nack_with_delay(
         &self,
         delay: Duration,
         max_retries: i32,
     ) -> Result<(i32, Duration), RabbitMQError> {
-        let result = self.channel.nack.with_delay(delay, max_retries).await?;
-
-        // Emit audit.dead_letter event automatically
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let audit_payload = AuditDeadLetterPayload {
-            publisher_microservice: self.publisher_microservice.clone(),
-            rejector_microservice: self.microservice.clone(),
-            rejected_event: self.processed_event.clone(),
-            rejected_at: timestamp,
-            queue_name: self.channel.queue_name.clone(),
-            rejection_reason: "delay".to_string(),
-            retry_count: Some(result.0 as u32),
-            event_id: self.event_id.clone(),
-        };
-
-        // Emit the audit event (don't fail if audit fails)
-        let operation_id = self.operation_id.clone();
-        tokio::spawn(async move {
-            with_operation(operation_id, async {
-                if let Err(e) = RabbitMQClient::publish_audit_event(audit_payload).await {
-                    error!("Failed to emit audit.dead_letter event: {:?}", e);
-                }
-            })
-            .await;
-        });
-
-        Ok(result)
+        self.nack(NackOptions::delay(delay, max_retries))
+            .await
+            .map(NackOutcome::delay_result)
     }
 
+    /// [`EventHandler::nack`] with [`NackOptions::fibonacci`].
     pub async fn nack_with_fibonacci_strategy(
         &self,
         max_occurrence: i32,
         max_retries: i32,
     ) -> Result<(i32, Duration, i32), RabbitMQError> {
-        let result = self
-            .channel
-            .nack
-            .with_fibonacci_strategy(max_occurrence, max_retries)
-            .await?;
-
-        // Emit audit.dead_letter event automatically
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let audit_payload = AuditDeadLetterPayload {
-            publisher_microservice: self.publisher_microservice.clone(),
-            rejector_microservice: self.microservice.clone(),
-            rejected_event: self.processed_event.clone(),
-            rejected_at: timestamp,
-            queue_name: self.channel.queue_name.clone(),
-            rejection_reason: "fibonacci_strategy".to_string(),
-            retry_count: Some(result.0 as u32),
-            event_id: self.event_id.clone(),
-        };
-
-        // Emit the audit event (don't fail if audit fails)
-        let operation_id = self.operation_id.clone();
-        tokio::spawn(async move {
-            with_operation(operation_id, async {
-                if let Err(e) = RabbitMQClient::publish_audit_event(audit_payload).await {
-                    error!("Failed to emit audit.dead_letter event: {:?}", e);
-                }
-            })
-            .await;
-        });
+        self.nack(NackOptions::fibonacci(max_occurrence, max_retries))
+            .await
+            .map(NackOutcome::fibonacci_result)
+    }
 
-        Ok(result)
+    /// Counts the failure in the circuit breaker of the event and emits the audit.dead_letter
+    /// event in the background, a failing audit never fails the nack.
+    /// Without an error the strategy name is the rejection reason.
+    fn on_nack(&self, retry_strategy: &str, error: Option<String>, retry_count: i32) {
+        self.circuit_breakers.record_failure(self.event.as_ref());
+
+        spawn_audit_event(
+            AuditDeadLetterPayload {
+                publisher_microservice: self.publisher_microservice.clone(),
+                rejector_microservice: self.microservice.clone(),
+                rejected_event: self.event.as_ref().to_string(),
+                rejected_at: now_millis(),
+                queue_name: self.channel.queue_name.clone(),
+                rejection_reason: error.unwrap_or_else(|| retry_strategy.to_string()),
+                retry_count: Some(retry_count as u32),
+                retry_strategy: Some(retry_strategy.to_string()),
+                event_id: self.event_id.clone(),
+            },
+            self.operation_id.clone(),
+        );
     }
 }
 
@@ -197,7 +184,7 @@ impl RabbitMQClient {
                 Ok(delivery) => {
                     if let Err(e) = self.handle_event(&delivery, &emitter, queue_name).await {
                         error!("Error handling event: {:?}", e);
-                        let _ = delivery.nack(BasicNackOptions::default()).await;
+                        self.reject_delivery(&delivery, queue_name, &e).await;
                     }
                 }
                 Err(e) => {
@@ -214,16 +201,22 @@ impl RabbitMQClient {
         emitter: &Emitter<EventHandler, MicroserviceEvent>,
         queue_name: &str,
     ) -> Result<(), RabbitMQError> {
-        let payload: HashMap<String, Value> = serde_json::from_slice(&delivery.data)?;
+        let payload = decode_payload(delivery)?;
 
-        let event_key =
-            Self::find_event_values(&delivery.properties.headers().clone().unwrap_or_default())?;
+        let headers = delivery.properties.headers().clone().unwrap_or_default();
+        let event_key = Self::find_event_values(&headers)?;
 
         if event_key.len() > 1 {
             info!("More than one valid header, using the first one detected");
         }
 
         let event = &event_key[0];
+        let payload = self
+            .versioning
+            .upcast(*event, schema_version_from_headers(&headers, *event), payload)?;
+        if self.validate_consumed.load(Ordering::Relaxed) {
+            validate_consumed(*event, &payload)?;
+        }
 
         let publisher_microservice = delivery.properties.app_id()
             .as_ref()
@@ -252,39 +245,34 @@ impl RabbitMQClient {
         let response_channel =
             EventsConsumeChannel::new(channel.clone(), delivery, queue_name.to_string());
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        let audit_payload = AuditReceivedPayload {
-            publisher_microservice: publisher_microservice.clone(),
-            receiver_microservice: self.microservice.as_ref().to_string(),
-            received_event: event.as_ref().to_string(),
-            received_at: timestamp,
-            queue_name: queue_name.to_string(),
-            event_id: event_id.clone(),
-        };
+        if let Admission::Postpone(delay) = self.circuit_breakers.admit(event.as_ref()) {
+            // The breaker of the event is open, the delivery waits in the requeue queue
+            // without reaching the handler nor counting as a retry
+            return response_channel.nack.postpone(delay).await;
+        }
 
-        let audit_operation_id = operation_id.clone();
         // Emit the audit.received event (don't fail the main flow if audit fails)
-        tokio::spawn(async move {
-            with_operation(audit_operation_id, async {
-                if let Err(e) = RabbitMQClient::publish_audit_event(audit_payload).await {
-                    error!("Failed to emit audit.received event: {:?}", e);
-                }
-            })
-            .await;
-        });
+        spawn_audit_event(
+            AuditReceivedPayload {
+                publisher_microservice: publisher_microservice.clone(),
+                receiver_microservice: self.microservice.as_ref().to_string(),
+                received_event: event.as_ref().to_string(),
+                received_at: now_millis(),
+                queue_name: queue_name.to_string(),
+                event_id: event_id.clone(),
+            },
+            operation_id.clone(),
+        );
 
         let event_handler = EventHandler {
             payload,
             channel: response_channel,
             microservice: self.microservice.as_ref().to_string(),
-            processed_event: event.as_ref().to_string(),
+            event: *event,
             publisher_microservice,
             event_id,
             operation_id: operation_id.clone(),
+            circuit_breakers: self.circuit_breakers.clone(),
         };
 
         // Running the handler inside the scope makes the operation propagate to
@@ -454,6 +442,40 @@ impl RabbitMQClient {
         Ok(())
     }
 
+    /// Consumes the audit.saga_step_* events from their shared queue
+    pub(crate) async fn consume_audit_saga_step_events(
+        &self,
+        emitter: Emitter<AuditHandler, MicroserviceEvent>,
+    ) -> Result<(), RabbitMQError> {
+        let channel = self.events_channel.lock().await;
+
+        let mut consumer = channel
+            .basic_consume(
+                Queue::AUDIT_SAGA_STEP_COMMANDS,
+                "audit_saga_step_consumer",
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        drop(channel);
+
+        while let Some(delivery) = consumer.next().await {
+            match delivery {
+                Ok(delivery) => {
+                    if let Err(e) = self.handle_audit_event(&delivery, &emitter, Queue::AUDIT_SAGA_STEP_COMMANDS).await {
+                        error!("Error handling audit saga step event: {:?}", e);
+                        let _ = delivery.nack(BasicNackOptions::default()).await;
+                    }
+                }
+                Err(e) => {
+                    error!("Error receiving audit saga step message: {:?}", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Handles audit events for the audit microservice
     async fn handle_audit_event(
         &self,
@@ -461,7 +483,7 @@ impl RabbitMQClient {
         emitter: &Emitter<AuditHandler, MicroserviceEvent>,
         queue_name: &str,
     ) -> Result<(), RabbitMQError> {
-        let payload: HashMap<String, Value> = serde_json::from_slice(&delivery.data)?;
+        let payload = decode_payload(delivery)?;
 
         // For audit events, we determine the event type from the routing key or queue name
         let event = match queue_name {
@@ -469,6 +491,11 @@ impl RabbitMQClient {
             Queue::AUDIT_RECEIVED_COMMANDS => MicroserviceEvent::AuditReceived,
             Queue::AUDIT_PROCESSED_COMMANDS => MicroserviceEvent::AuditProcessed,
             Queue::AUDIT_DEAD_LETTER_COMMANDS => MicroserviceEvent::AuditDeadLetter,
+            // The saga step events share a queue, the routing key is the event
+            Queue::AUDIT_SAGA_STEP_COMMANDS => {
+                MicroserviceEvent::from_str(delivery.routing_key.as_str())
+                    .map_err(|_| RabbitMQError::InvalidEventKey(delivery.routing_key.to_string()))?
+            }
             _ => return Err(RabbitMQError::InvalidHeader),
         };
 
@@ -493,7 +520,7 @@ impl RabbitMQClient {
 /// This prevents the audit microservice from auditing its own audit processing
 #[derive(Clone)]
 pub struct AuditHandler {
-    payload: HashMap<String, Value>,
+    payload: RawPayload,
     channel: EventsConsumeChannel,
 }
 
@@ -502,12 +529,11 @@ impl AuditHandler {
     where
         T: for<'de> Deserialize<'de>,
     {
-        let json_value = serde_json::to_value(self.payload.clone())?;
-        serde_json::from_value(json_value)
+        self.payload.parse()
     }
 
     pub fn get_payload(&self) -> &HashMap<String, Value> {
-        &self.payload
+        self.payload.as_map()
     }
 
     /// Audit-specific ack that doesn't emit recursive audit events
@@ -520,26 +546,37 @@ impl AuditHandler {
         self.channel.ack().await
     }
 
-    /// Nack with delay - no audit emission for audit handler
-    /// Note: In future versions, we might want to consider auditing nacks from audit service
+    /// Like [`EventHandler::nack`] without audit events, an audit of the audit service would
+    /// loop back to it.
+    pub async fn nack(&self, options: NackOptions) -> Result<NackOutcome, RabbitMQError> {
+        self.channel.nack.nack(&options).await
+    }
+
+    /// [`AuditHandler::nack`] with [`NackOptions::delay`].
     pub async fn nack_with_delay(
         &self,
         delay: Duration,
         max_retries: i32,
     ) -> Result<(i32, Duration), RabbitMQError> {
-        self.channel.nack.with_delay(delay, max_retries).await
+        self.nack(NackOptions::delay(delay, max_retries))
+            .await
+            .map(NackOutcome::delay_result)
     }
 
-    /// Nack with fibonacci strategy - no audit emission for audit handler
+    /// [`AuditHandler::nack`] with [`NackOptions::fibonacci`].
     pub async fn nack_with_fibonacci_strategy(
         &self,
         max_occurrence: i32,
         max_retries: i32,
     ) -> Result<(i32, Duration, i32), RabbitMQError> {
-        self.channel
-            .nack
-            .with_fibonacci_strategy(max_occurrence, max_retries)
+        self.nack(NackOptions::fibonacci(max_occurrence, max_retries))
             .await
+            .map(NackOutcome::fibonacci_result)
+    }
+
+    /// Retry count, first failure and errors recorded by the previous attempts.
+    pub fn retry_history(&self) -> RetryHistory {
+        RetryHistory::from_headers(&self.channel.delivery.headers)
     }
 }
 
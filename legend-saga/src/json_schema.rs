@@ -0,0 +1,82 @@
+//! JSON Schema of the payloads of every event and saga, the contracts shared with the
+//! services written in other languages.
+
+use crate::commence_saga::SagaTitle;
+use crate::events::MicroserviceEvent;
+use schemars::Schema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use strum::IntoEnumIterator;
+
+/// The JSON Schemas of the payloads, keyed by the wire name of their event or saga.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaCatalog {
+    pub events: BTreeMap<String, Schema>,
+    pub sagas: BTreeMap<String, Schema>,
+}
+
+/// The JSON Schema of the payload of `event`, with its schema version in the
+/// `x-schema-version` keyword.
+pub fn event_schema(event: MicroserviceEvent) -> Schema {
+    let mut schema = event.payload_schema();
+    schema.insert(
+        "x-schema-version".to_string(),
+        event.schema_version().into(),
+    );
+    schema
+}
+
+/// The schemas of the built-in events and sagas, the registered sagas have no known payload.
+pub fn catalog() -> SchemaCatalog {
+    SchemaCatalog {
+        events: MicroserviceEvent::iter()
+            .map(|event| (event.as_ref().to_string(), event_schema(event)))
+            .collect(),
+        sagas: SagaTitle::iter()
+            .filter_map(|title| Some((title.as_ref().to_string(), title.payload_schema()?)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test_json_schema {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn catalog_covers_every_event_and_saga() {
+        let catalog = catalog();
+        assert_eq!(catalog.events.len(), MicroserviceEvent::iter().count());
+        assert_eq!(catalog.sagas.len(), SagaTitle::iter().count());
+
+        let deleted_user = &catalog.events["auth.deleted_user"];
+        assert_eq!(
+            deleted_user.get("title"),
+            Some(&json!("AuthDeletedUserPayload"))
+        );
+        assert_eq!(deleted_user.get("required"), Some(&json!(["userId"])));
+        assert_eq!(deleted_user.get("x-schema-version"), Some(&json!(1)));
+        assert_eq!(
+            catalog.events["billing.subscription_created"].get("x-schema-version"),
+            Some(&json!(2))
+        );
+        assert!(catalog
+            .sagas
+            .contains_key("transfer_crypto_reward_to_mission_winner"));
+    }
+
+    #[test]
+    fn schemas_follow_the_wire_format() {
+        let social_user = serde_json::to_value(event_schema(MicroserviceEvent::SocialNewUser))
+            .unwrap()["$defs"]["SocialUser"]
+            .clone();
+        let properties = social_user["properties"].as_object().unwrap();
+        assert!(properties.contains_key("_id"));
+        assert!(properties.contains_key("RPMAvatarId"));
+        assert!(properties.contains_key("firstName"));
+        assert!(!social_user["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("firstName")));
+    }
+}
@@ -1,3 +1,6 @@
+// The derive macros name the crate by its path, which also has to resolve in here
+extern crate self as legend_saga;
+
 macro_rules! cfg_std {
     ($($item:item)*) => {
         $(
@@ -8,16 +11,30 @@ macro_rules! cfg_std {
 }
 
 cfg_std! {
+    pub mod circuit_breaker;
     pub mod commence_saga;
+    pub mod compression;
     mod consumers;
     mod emitter;
     mod fibo;
     mod my_delivery;
     mod nack;
     pub mod operation;
+    pub mod orchestrator;
+    pub mod payload_format;
     mod publish_event;
+    pub mod quarantine;
+    pub mod raw_payload;
+    pub mod registry;
     mod queue_consumer_props;
+    pub mod rpc;
     pub mod saga;
+    pub mod saga_definition;
+    pub mod saga_handle;
+    pub mod saga_metadata;
+    pub mod saga_store;
+    pub mod validation;
+    pub mod versioning;
     mod start;
     pub mod events_consume;
     pub mod connection;
@@ -29,5 +46,11 @@ pub mod events;
 #[cfg(feature = "grpc")]
 pub mod grpc;
 
+#[cfg(feature = "sqlite")]
+pub mod sqlite_saga_store;
+
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+
 #[cfg(test)]
 mod test;
@@ -23,6 +23,8 @@ pub struct MyDelivery {
     // pub properties: BasicProperties,
     app_id: Option<ShortString>,
     message_id: Option<ShortString>,
+    content_type: Option<ShortString>,
+    content_encoding: Option<ShortString>,
     /// The payload of the message in binary format.
     pub data: Vec<u8>,
     pub headers: FieldTable,
@@ -39,7 +41,9 @@ impl MyDelivery {
             // properties: delivery.properties.clone(),
             data: delivery.data.clone(),
             app_id: delivery.properties.app_id().to_owned(),
-            message_id: delivery.properties.message_id().to_owned()
+            message_id: delivery.properties.message_id().to_owned(),
+            content_type: delivery.properties.content_type().to_owned(),
+            content_encoding: delivery.properties.content_encoding().to_owned(),
         }
     }
     pub fn app_id(&self) -> &Option<ShortString> {
@@ -50,6 +54,16 @@ impl MyDelivery {
         &self.message_id
     }
     
+    /// The format of [`MyDelivery::data`], kept when the message is requeued.
+    pub fn content_type(&self) -> &Option<ShortString> {
+        &self.content_type
+    }
+
+    /// The compression of [`MyDelivery::data`], kept when the message is requeued.
+    pub fn content_encoding(&self) -> &Option<ShortString> {
+        &self.content_encoding
+    }
+
     pub fn with_app_id(mut self, value: ShortString) -> Self {
         self.app_id = Some(value);
         self
@@ -2,13 +2,284 @@ use crate::fibo::fibonacci;
 use crate::my_delivery::MyDelivery;
 use crate::queue_consumer_props::Exchange;
 use lapin::options::{BasicNackOptions, BasicPublishOptions};
-use lapin::types::{AMQPValue, FieldTable, ShortString};
+use lapin::types::{AMQPValue, FieldArray, FieldTable, ShortString};
 use lapin::{BasicProperties, Channel};
 use std::collections::BTreeMap;
-use std::time::Duration;
+use std::fmt::Display;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::info;
 use crate::connection::RabbitMQError;
 
+/// Header carrying how many times the message has been requeued by this library.
+pub(crate) const RETRY_COUNT_HEADER: &str = "x-retry-count";
+/// Header carrying the position in the fibonacci sequence used for the next delay.
+pub(crate) const OCCURRENCE_HEADER: &str = "x-occurrence";
+/// Header recording when the message failed for the first time (UNIX timestamp in milliseconds).
+pub(crate) const FIRST_FAILURE_HEADER: &str = "x-first-failure-at";
+/// Header carrying the error of the last failed attempt.
+pub(crate) const LAST_ERROR_HEADER: &str = "x-last-error";
+/// Header carrying the errors of the previous attempts, oldest first.
+pub(crate) const ERROR_HISTORY_HEADER: &str = "x-error-history";
+/// Header carrying how many times the message has been postponed by a circuit breaker.
+pub(crate) const POSTPONED_COUNT_HEADER: &str = "x-postponed-count";
+/// Header RabbitMQ appends every time a message is dead-lettered.
+const X_DEATH_HEADER: &str = "x-death";
+/// Errors are truncated to this many characters, the headers travel with every retry.
+pub(crate) const MAX_ERROR_LEN: usize = 1024;
+/// Only the most recent errors are kept in the history.
+pub(crate) const MAX_ERROR_HISTORY: usize = 10;
+
+/// What the previous attempts left in the headers of a delivery.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetryHistory {
+    /// How many times the message has already been retried, 0 on the first attempt.
+    pub retry_count: u32,
+    /// When the message failed for the first time (UNIX timestamp in milliseconds).
+    pub first_failure_at: Option<u64>,
+    /// The error given by the last failed attempt.
+    pub last_error: Option<String>,
+    /// The errors given by the previous attempts, oldest first, at most the last 10.
+    pub errors: Vec<String>,
+}
+
+impl RetryHistory {
+    pub(crate) fn from_headers(headers: &FieldTable) -> Self {
+        let errors = match headers.inner().get(ERROR_HISTORY_HEADER) {
+            Some(AMQPValue::FieldArray(errors)) => errors
+                .as_slice()
+                .iter()
+                .filter_map(header_as_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            retry_count: (retry_count_from_headers(headers) - 1).max(0) as u32,
+            first_failure_at: first_failure_from_headers(headers),
+            last_error: headers.inner().get(LAST_ERROR_HEADER).and_then(header_as_string),
+            errors,
+        }
+    }
+}
+
+fn header_as_string(value: &AMQPValue) -> Option<String> {
+    match value {
+        AMQPValue::LongString(s) => Some(s.to_string()),
+        AMQPValue::ShortString(s) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+/// Stores `error` as the last error and appends it to the error history.
+pub(crate) fn record_error(headers: &mut FieldTable, error: &str) {
+    let error: String = error.chars().take(MAX_ERROR_LEN).collect();
+
+    let mut history = RetryHistory::from_headers(headers).errors;
+    history.push(error.clone());
+    let skip = history.len().saturating_sub(MAX_ERROR_HISTORY);
+    let mut errors = FieldArray::default();
+    for e in history.into_iter().skip(skip) {
+        errors.push(AMQPValue::LongString(e.into()));
+    }
+
+    headers.insert(LAST_ERROR_HEADER.into(), AMQPValue::LongString(error.into()));
+    headers.insert(ERROR_HISTORY_HEADER.into(), AMQPValue::FieldArray(errors));
+}
+
+/// Reads any AMQP integer as an `i64`. Publishers in other languages (e.g. the
+/// TypeScript library) do not always pick `LongLongInt`, and a counter that fails
+/// to parse would reset to zero and retry forever.
+pub(crate) fn header_as_i64(value: &AMQPValue) -> Option<i64> {
+    match value {
+        AMQPValue::ShortShortInt(n) => Some(*n as i64),
+        AMQPValue::ShortShortUInt(n) => Some(*n as i64),
+        AMQPValue::ShortInt(n) => Some(*n as i64),
+        AMQPValue::ShortUInt(n) => Some(*n as i64),
+        AMQPValue::LongInt(n) => Some(*n as i64),
+        AMQPValue::LongUInt(n) => Some(*n as i64),
+        AMQPValue::LongLongInt(n) => Some(*n),
+        AMQPValue::Timestamp(n) => i64::try_from(*n).ok(),
+        // JS numbers may be encoded as doubles, only whole values are counters
+        AMQPValue::Float(n) if n.is_finite() && n.fract() == 0.0 => Some(*n as i64),
+        AMQPValue::Double(n) if n.is_finite() && n.fract() == 0.0 => Some(*n as i64),
+        _ => None,
+    }
+}
+
+/// Sum of the `count` of every `x-death` entry, that is, how many times RabbitMQ
+/// dead-lettered the message.
+fn x_death_count(headers: &FieldTable) -> i64 {
+    match headers.inner().get(X_DEATH_HEADER) {
+        Some(AMQPValue::FieldArray(deaths)) => deaths
+            .as_slice()
+            .iter()
+            .filter_map(|death| match death {
+                AMQPValue::FieldTable(table) => table.inner().get("count").and_then(header_as_i64),
+                _ => None,
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// The number of the retry about to happen: the highest of `x-retry-count` and the
+/// `x-death` counts, plus one. The dead-letterings of the postponed deliveries are not
+/// retries and are left out of the `x-death` counts.
+pub(crate) fn retry_count_from_headers(headers: &FieldTable) -> i64 {
+    let retry_count = headers
+        .inner()
+        .get(RETRY_COUNT_HEADER)
+        .and_then(header_as_i64)
+        .unwrap_or(0);
+    let retried_deaths = x_death_count(headers) - postponed_count(headers);
+    retry_count.max(retried_deaths) + 1
+}
+
+fn postponed_count(headers: &FieldTable) -> i64 {
+    headers
+        .inner()
+        .get(POSTPONED_COUNT_HEADER)
+        .and_then(header_as_i64)
+        .unwrap_or(0)
+}
+
+/// The headers of a postponed delivery, counting the postpone so that the
+/// dead-lettering through the requeue queue is not taken for a retry.
+fn postponed_headers(headers: &FieldTable) -> FieldTable {
+    let mut headers = headers.clone();
+    let count = postponed_count(&headers) + 1;
+    headers.insert(POSTPONED_COUNT_HEADER.into(), AMQPValue::LongLongInt(count));
+    headers
+}
+
+/// When the message failed for the first time, `None` if this is the first failure.
+pub(crate) fn first_failure_from_headers(headers: &FieldTable) -> Option<u64> {
+    headers
+        .inner()
+        .get(FIRST_FAILURE_HEADER)
+        .and_then(header_as_i64)
+        .and_then(|n| u64::try_from(n).ok())
+}
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Whether the retry budget is spent. The time budget counts from the first failure
+/// and is exhausted when the next attempt would happen after `max_elapsed`.
+fn retries_exhausted(
+    count: i64,
+    max_retries: i32,
+    first_failure_at: u64,
+    delay: Duration,
+    max_elapsed: Option<Duration>,
+) -> bool {
+    if count > max_retries as i64 {
+        return true;
+    }
+    match max_elapsed {
+        Some(max_elapsed) => {
+            let elapsed = Duration::from_millis(now_millis().saturating_sub(first_failure_at));
+            elapsed + delay > max_elapsed
+        }
+        None => false,
+    }
+}
+
+/// How a nacked message is requeued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryStrategy {
+    /// Requeued after `delay`, up to `max_retries` times.
+    Delay { delay: Duration, max_retries: i32 },
+    /// Requeued after the next number of the fibonacci sequence in seconds, up to
+    /// `max_retries` times. The sequence starts over after `max_occurrence`.
+    Fibonacci {
+        max_occurrence: i32,
+        max_retries: i32,
+    },
+}
+
+impl RetryStrategy {
+    /// Name of the strategy in the audit events.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            RetryStrategy::Delay { .. } => "delay",
+            RetryStrategy::Fibonacci { .. } => "fibonacci_strategy",
+        }
+    }
+}
+
+/// How the `nack` of a handler retries the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NackOptions {
+    pub strategy: RetryStrategy,
+    /// Stops retrying once this long has passed since the first failure of the message.
+    pub max_elapsed: Option<Duration>,
+    /// Recorded in the `x-last-error` header of the requeued message and reported by the
+    /// audit events, which otherwise report the name of the strategy.
+    pub error: Option<String>,
+}
+
+impl NackOptions {
+    pub fn delay(delay: Duration, max_retries: i32) -> Self {
+        RetryStrategy::Delay { delay, max_retries }.into()
+    }
+
+    pub fn fibonacci(max_occurrence: i32, max_retries: i32) -> Self {
+        RetryStrategy::Fibonacci {
+            max_occurrence,
+            max_retries,
+        }
+        .into()
+    }
+
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    pub fn error(mut self, error: impl Display) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+impl From<RetryStrategy> for NackOptions {
+    fn from(strategy: RetryStrategy) -> Self {
+        Self {
+            strategy,
+            max_elapsed: None,
+            error: None,
+        }
+    }
+}
+
+/// What a nack did with the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NackOutcome {
+    /// The attempt that failed, 1 for the first one.
+    pub count: i32,
+    pub delay: Duration,
+    /// Position in the fibonacci sequence, 0 for the delay strategy.
+    pub occurrence: i32,
+    /// The retries ran out and the message was not requeued.
+    pub exhausted: bool,
+}
+
+impl NackOutcome {
+    /// The `(count, delay)` returned by the public delay nacks.
+    pub(crate) fn delay_result(self) -> (i32, Duration) {
+        (self.count, self.delay)
+    }
+
+    /// The `(count, delay, occurrence)` returned by the public fibonacci nacks.
+    pub(crate) fn fibonacci_result(self) -> (i32, Duration, i32) {
+        (self.count, self.delay, self.occurrence)
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct Nack {
     channel: Channel,
@@ -23,69 +294,96 @@ impl Nack {
             queue_name,
         }
     }
-    pub(crate) async fn with_delay(
+    pub(crate) async fn nack(&self, options: &NackOptions) -> Result<NackOutcome, RabbitMQError> {
+        let error = options.error.as_deref();
+        match options.strategy {
+            RetryStrategy::Delay { delay, max_retries } => {
+                self.with_delay(delay, max_retries, options.max_elapsed, error)
+                    .await
+            }
+            RetryStrategy::Fibonacci {
+                max_occurrence,
+                max_retries,
+            } => {
+                self.with_fibonacci_strategy(max_occurrence, max_retries, options.max_elapsed, error)
+                    .await
+            }
+        }
+    }
+
+    async fn with_delay(
         &self,
         delay: Duration,
         max_retries: i32,
-    ) -> Result<(i32, Duration), RabbitMQError> {
+        max_elapsed: Option<Duration>,
+        error: Option<&str>,
+    ) -> Result<NackOutcome, RabbitMQError> {
         self.channel
             .basic_nack(self.delivery.delivery_tag, BasicNackOptions::default())
             .await?;
 
         let count = self.calculate_retry_count();
+        let first_failure_at = self.first_failure_at();
 
-        if count > max_retries as i64 {
+        if retries_exhausted(count, max_retries, first_failure_at, delay, max_elapsed) {
             info!(
-                "MAX NACK RETRIES REACHED: {} - NACKING {} - COUNT {}",
-                max_retries, self.queue_name, count
+                "MAX NACK RETRIES REACHED: {} - MAX ELAPSED: {:?} - NACKING {} - COUNT {}",
+                max_retries, max_elapsed, self.queue_name, count
             );
-            return Ok((count as i32, delay));
+            return Ok(NackOutcome {
+                count: count as i32,
+                delay,
+                occurrence: 0,
+                exhausted: true,
+            });
         }
         let mut headers = self.delivery.headers.clone();
-        headers.insert("x-retry-count".into(), AMQPValue::LongLongInt(count));
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongLongInt(count));
+        headers.insert(
+            FIRST_FAILURE_HEADER.into(),
+            AMQPValue::LongLongInt(first_failure_at as i64),
+        );
+        if let Some(error) = error {
+            record_error(&mut headers, error);
+        }
 
         self.publish_requeue(delay, headers).await?;
-        Ok((count as i32, delay))
+        Ok(NackOutcome {
+            count: count as i32,
+            delay,
+            occurrence: 0,
+            exhausted: false,
+        })
     }
 
     fn calculate_retry_count(&self) -> i64 {
-        self.delivery
-            .headers
-            .inner()
-            .get("x-retry-count")
-            .and_then(|v| {
-                if let AMQPValue::LongLongInt(n) = v {
-                    Some(*n)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0)
-            + 1
+        retry_count_from_headers(&self.delivery.headers)
+    }
+
+    fn first_failure_at(&self) -> u64 {
+        first_failure_from_headers(&self.delivery.headers).unwrap_or_else(now_millis)
     }
-    pub(crate) async fn with_fibonacci_strategy(
+
+    async fn with_fibonacci_strategy(
         &self,
         max_occurrence: i32,
         max_retries: i32,
-    ) -> Result<(i32, Duration, i32), RabbitMQError> {
+        max_elapsed: Option<Duration>,
+        error: Option<&str>,
+    ) -> Result<NackOutcome, RabbitMQError> {
         self.channel
             .basic_nack(self.delivery.delivery_tag, BasicNackOptions::default())
             .await?;
 
         let count = self.calculate_retry_count();
+        let first_failure_at = self.first_failure_at();
 
         let occurrence = self
             .delivery
             .headers
             .inner()
-            .get("x-occurrence")
-            .and_then(|v| {
-                if let AMQPValue::LongLongInt(n) = v {
-                    Some(*n)
-                } else {
-                    None
-                }
-            })
+            .get(OCCURRENCE_HEADER)
+            .and_then(header_as_i64)
             .unwrap_or(0);
         // the occurrence is reset to 0 to avoid large delay in the next nack
         let occurrence = if occurrence >= max_occurrence as i64 {
@@ -96,21 +394,48 @@ impl Nack {
 
         let delay = Duration::from_secs(fibonacci(occurrence as usize) as u64);
 
-        if count > max_retries as i64 {
+        if retries_exhausted(count, max_retries, first_failure_at, delay, max_elapsed) {
             info!(
-                "MAX NACK RETRIES REACHED: {} - NACKING {}",
-                max_retries, self.queue_name
+                "MAX NACK RETRIES REACHED: {} - MAX ELAPSED: {:?} - NACKING {}",
+                max_retries, max_elapsed, self.queue_name
             );
-            return Ok((count as i32, delay, occurrence as i32));
+            return Ok(NackOutcome {
+                count: count as i32,
+                delay,
+                occurrence: occurrence as i32,
+                exhausted: true,
+            });
         }
 
         let mut headers = self.delivery.headers.clone();
-        headers.insert("x-retry-count".into(), AMQPValue::LongLongInt(count));
-        headers.insert("x-occurrence".into(), AMQPValue::LongLongInt(occurrence));
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongLongInt(count));
+        headers.insert(OCCURRENCE_HEADER.into(), AMQPValue::LongLongInt(occurrence));
+        headers.insert(
+            FIRST_FAILURE_HEADER.into(),
+            AMQPValue::LongLongInt(first_failure_at as i64),
+        );
+        if let Some(error) = error {
+            record_error(&mut headers, error);
+        }
 
         self.publish_requeue(delay, headers).await?;
-        Ok((count as i32, delay, occurrence as i32))
+        Ok(NackOutcome {
+            count: count as i32,
+            delay,
+            occurrence: occurrence as i32,
+            exhausted: false,
+        })
     }
+    /// Requeues the message with `delay` keeping its headers, the attempt is not counted as a retry
+    /// even though RabbitMQ records the dead-lettering in `x-death`.
+    pub(crate) async fn postpone(&self, delay: Duration) -> Result<(), RabbitMQError> {
+        self.channel
+            .basic_nack(self.delivery.delivery_tag, BasicNackOptions::default())
+            .await?;
+        self.publish_requeue(delay, postponed_headers(&self.delivery.headers))
+            .await
+    }
+
     async fn publish_requeue(
         &self,
         delay: Duration,
@@ -138,18 +463,26 @@ impl Nack {
                 )
             };
 
+        let mut properties = BasicProperties::default()
+            .with_expiration(delay.as_millis().to_string().into())
+            .with_headers(new_headers)
+            .with_app_id(self.delivery.app_id().clone().unwrap_or_default())
+            .with_message_id(self.delivery.message_id().clone().unwrap_or_default())
+            .with_delivery_mode(2); // persistent
+        if let Some(content_type) = self.delivery.content_type() {
+            properties = properties.with_content_type(content_type.clone());
+        }
+        if let Some(content_encoding) = self.delivery.content_encoding() {
+            properties = properties.with_content_encoding(content_encoding.clone());
+        }
+
         self.channel
             .basic_publish(
                 exchange,
                 &routing_key,
                 BasicPublishOptions::default(),
                 &self.delivery.data.clone(),
-                BasicProperties::default()
-                    .with_expiration(delay.as_millis().to_string().into())
-                    .with_headers(new_headers)
-                    .with_app_id(self.delivery.app_id().clone().unwrap_or_default())
-                    .with_message_id(self.delivery.message_id().clone().unwrap_or_default())
-                    .with_delivery_mode(2), // persistent
+                properties,
             )
             .await?;
 
@@ -166,6 +499,7 @@ mod test_nack {
     use tokio::sync::Barrier;
     use tokio::time::timeout;
     use crate::connection::{AvailableMicroservices, RabbitMQClient};
+    use crate::nack::{NackOptions, RetryStrategy};
 
     /// Integration test, slow because of nack with fibo, min -> 1 sec
     #[test]
@@ -196,16 +530,24 @@ mod test_nack {
                 let barrier = c_barrier.clone();
 
                 async move {
+                    let history = handler.retry_history();
+                    assert_eq!(history.retry_count, count as u32);
                     if count == 3 {
                         let p: AuthLogoutUserPayload =
                             handler.parse_payload().expect("Failed to parse payload");
                         assert_eq!(p.user_id, "123");
+                        assert_eq!(history.last_error.as_deref(), Some("attempt 2 failed"));
+                        assert_eq!(history.errors.len(), 3);
+                        assert!(history.first_failure_at.is_some());
                         handler.ack().await.expect("Failed to ack");
                         barrier.wait().await;
                         return;
                     }
                     handler
-                        .nack_with_delay(Duration::from_millis(100), 30)
+                        .nack(
+                            NackOptions::delay(Duration::from_millis(100), 30)
+                                .error(format!("attempt {count} failed")),
+                        )
                         .await
                         .expect("Failed to nack");
                 }
@@ -271,4 +613,185 @@ mod test_nack {
             //because fetch_add, returns and adds
         });
     }
+
+    #[test]
+    fn nack_options() {
+        let options = NackOptions::fibonacci(10, 30)
+            .max_elapsed(Duration::from_secs(60))
+            .error("boom");
+        assert_eq!(
+            options,
+            NackOptions {
+                strategy: RetryStrategy::Fibonacci {
+                    max_occurrence: 10,
+                    max_retries: 30,
+                },
+                max_elapsed: Some(Duration::from_secs(60)),
+                error: Some("boom".to_string()),
+            }
+        );
+        assert_eq!(options.strategy.name(), "fibonacci_strategy");
+        assert_eq!(
+            NackOptions::delay(Duration::from_millis(100), 3).strategy.name(),
+            "delay"
+        );
+    }
+
+    mod headers {
+        use crate::nack::{
+            first_failure_from_headers, header_as_i64, now_millis, postponed_headers,
+            record_error, retries_exhausted, retry_count_from_headers, RetryHistory,
+            FIRST_FAILURE_HEADER, LAST_ERROR_HEADER, MAX_ERROR_HISTORY, MAX_ERROR_LEN,
+            RETRY_COUNT_HEADER,
+        };
+        use lapin::types::{AMQPValue, FieldArray, FieldTable};
+        use std::time::Duration;
+
+        fn with_retry_count(value: AMQPValue) -> FieldTable {
+            let mut headers = FieldTable::default();
+            headers.insert(RETRY_COUNT_HEADER.into(), value);
+            headers
+        }
+
+        fn x_death(counts: &[i64]) -> AMQPValue {
+            let mut deaths = FieldArray::default();
+            for count in counts {
+                let mut death = FieldTable::default();
+                death.insert("count".into(), AMQPValue::LongLongInt(*count));
+                death.insert("reason".into(), AMQPValue::LongString("expired".into()));
+                deaths.push(AMQPValue::FieldTable(death));
+            }
+            AMQPValue::FieldArray(deaths)
+        }
+
+        #[test]
+        fn every_integer_type_is_a_counter() {
+            assert_eq!(header_as_i64(&AMQPValue::ShortShortInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::ShortShortUInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::ShortInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::ShortUInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::LongInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::LongUInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::LongLongInt(3)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::Double(3.0)), Some(3));
+            assert_eq!(header_as_i64(&AMQPValue::Double(3.5)), None);
+            assert_eq!(header_as_i64(&AMQPValue::LongString("3".into())), None);
+        }
+
+        #[test]
+        fn retry_count_set_by_another_library() {
+            // amqplib sends small numbers as a signed 32 bits integer
+            let headers = with_retry_count(AMQPValue::LongInt(4));
+            assert_eq!(retry_count_from_headers(&headers), 5);
+
+            assert_eq!(retry_count_from_headers(&FieldTable::default()), 1);
+        }
+
+        #[test]
+        fn retry_count_honors_x_death() {
+            let mut headers = with_retry_count(AMQPValue::LongLongInt(2));
+            headers.insert("x-death".into(), x_death(&[3, 2]));
+            assert_eq!(retry_count_from_headers(&headers), 6);
+
+            let mut headers = with_retry_count(AMQPValue::LongLongInt(7));
+            headers.insert("x-death".into(), x_death(&[3]));
+            assert_eq!(retry_count_from_headers(&headers), 8);
+        }
+
+        #[test]
+        fn postpones_are_not_retries() {
+            // Every requeue goes through a TTL queue, RabbitMQ counts each in x-death
+            let mut headers = FieldTable::default();
+            let mut deaths = 0;
+            for _ in 0..5 {
+                headers = postponed_headers(&headers);
+                deaths += 1;
+                headers.insert("x-death".into(), x_death(&[deaths]));
+                assert_eq!(retry_count_from_headers(&headers), 1);
+            }
+
+            // The nack after the postpones is the first retry
+            let count = retry_count_from_headers(&headers);
+            assert_eq!(count, 1);
+            headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongLongInt(count));
+            deaths += 1;
+            headers.insert("x-death".into(), x_death(&[deaths]));
+            assert_eq!(retry_count_from_headers(&headers), 2);
+            assert!(!retries_exhausted(2, 3, now_millis(), Duration::ZERO, None));
+
+            // Postponed again once retrying, the count stays
+            headers = postponed_headers(&headers);
+            deaths += 1;
+            headers.insert("x-death".into(), x_death(&[deaths]));
+            assert_eq!(retry_count_from_headers(&headers), 2);
+        }
+
+        #[test]
+        fn first_failure_timestamp() {
+            assert_eq!(first_failure_from_headers(&FieldTable::default()), None);
+
+            let mut headers = FieldTable::default();
+            headers.insert(
+                FIRST_FAILURE_HEADER.into(),
+                AMQPValue::LongLongInt(1_700_000_000_000),
+            );
+            assert_eq!(first_failure_from_headers(&headers), Some(1_700_000_000_000));
+        }
+
+        #[test]
+        fn retries_exhausted_by_count_or_time() {
+            let now = now_millis();
+            let delay = Duration::from_secs(1);
+
+            assert!(!retries_exhausted(3, 3, now, delay, None));
+            assert!(retries_exhausted(4, 3, now, delay, None));
+
+            let budget = Some(Duration::from_secs(60));
+            assert!(!retries_exhausted(1, 30, now, delay, budget));
+            assert!(!retries_exhausted(1, 30, now - 58_000, delay, budget));
+            assert!(retries_exhausted(1, 30, now - 59_500, delay, budget));
+            // the count is still enforced when the time budget is not spent
+            assert!(retries_exhausted(31, 30, now, delay, budget));
+        }
+
+        #[test]
+        fn record_error_keeps_the_last_errors() {
+            let mut headers = with_retry_count(AMQPValue::LongLongInt(1));
+            for i in 0..MAX_ERROR_HISTORY + 2 {
+                record_error(&mut headers, &format!("error {i}"));
+            }
+
+            let history = RetryHistory::from_headers(&headers);
+            assert_eq!(history.retry_count, 1);
+            assert_eq!(history.last_error.as_deref(), Some("error 11"));
+            assert_eq!(history.errors.len(), MAX_ERROR_HISTORY);
+            assert_eq!(history.errors[0], "error 2");
+            assert_eq!(
+                history.errors.last(),
+                history.last_error.as_ref(),
+                "the last error is the newest entry of the history"
+            );
+        }
+
+        #[test]
+        fn record_error_truncates_long_errors() {
+            let mut headers = FieldTable::default();
+            record_error(&mut headers, &"é".repeat(MAX_ERROR_LEN + 10));
+
+            match headers.inner().get(LAST_ERROR_HEADER) {
+                Some(AMQPValue::LongString(error)) => {
+                    assert_eq!(error.to_string().chars().count(), MAX_ERROR_LEN)
+                }
+                other => panic!("unexpected header {other:?}"),
+            }
+        }
+
+        #[test]
+        fn retry_history_of_a_first_attempt() {
+            assert_eq!(
+                RetryHistory::from_headers(&FieldTable::default()),
+                RetryHistory::default()
+            );
+        }
+    }
 }
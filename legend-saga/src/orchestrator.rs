@@ -0,0 +1,1394 @@
+use crate::commence_saga::{CommenceSaga, SagaTitle};
+use crate::compression::compress;
+use crate::connection::{get_or_init_publish_channel, RabbitMQClient, RabbitMQError};
+use crate::nack::now_millis;
+use crate::operation::{operation_from_headers, operation_headers, with_operation};
+use crate::payload_format::decode_delivery;
+use crate::queue_consumer_props::{Exchange, Queue};
+use crate::saga::{SagaStep, Status, StepCommand};
+use crate::saga_definition::validate_definitions;
+pub use crate::saga_definition::{SagaDefinition, SagaStepDefinition};
+use crate::saga_handle::SagaUpdate;
+use crate::saga_store::{
+    BlockingSagaStore, CompletedStep, InMemorySagaStore, SagaRecord, SagaStore, SagaStoreError,
+    SagaTransition,
+};
+use futures_lite::StreamExt;
+use lapin::options::{
+    BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions,
+    QueueDeclareOptions,
+};
+use lapin::types::FieldTable;
+use lapin::BasicProperties;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStatus {
+    /// A step has been dispatched and its reply is awaited.
+    Running,
+    /// A step failed, the completed steps are being undone in reverse order.
+    Compensating,
+    /// Every step succeeded.
+    Completed,
+    /// A step failed and every completed step was undone.
+    Compensated,
+    /// A compensation failed, the saga needs manual intervention.
+    CompensationFailed,
+}
+
+impl SagaStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            SagaStatus::Completed | SagaStatus::Compensated | SagaStatus::CompensationFailed
+        )
+    }
+}
+
+/// Snapshot of a saga run by the orchestrator.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SagaSnapshot {
+    pub saga_id: i32,
+    pub title: SagaTitle,
+    pub status: SagaStatus,
+    /// The command whose reply is awaited, `None` once the saga finished.
+    pub current_command: Option<StepCommand>,
+    /// Payload returned by the last successful step.
+    pub payload: HashMap<String, Value>,
+    /// Reason sent by the step that failed, if any did.
+    pub failure_reason: Option<String>,
+    /// When the current step was first dispatched (UNIX timestamp in milliseconds).
+    pub step_started_at: u64,
+    /// When the reply of the current step is due, `None` for steps without timeout. While
+    /// the step was not sent, when the sweeper sends it again.
+    pub deadline: Option<u64>,
+    /// When the current step last reported progress (UNIX timestamp in milliseconds).
+    pub last_progress_at: Option<u64>,
+    /// Detail of the last progress of the current step.
+    pub progress_detail: Option<String>,
+}
+
+/// How often the orchestrator looks for steps whose timeout expired.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a stored step waits to be confirmed as sent before the sweeper sends it again.
+const DISPATCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// How long a delivery that failed with a store or broker error waits before it is requeued.
+const STORE_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Notices kept for a slow publisher before the oldest are dropped.
+const NOTICES_CAPACITY: usize = 1024;
+
+/// A change of a saga to report to whoever commenced it.
+#[derive(Debug, Clone)]
+pub(crate) struct SagaNotice {
+    pub reply_to: String,
+    pub operation_id: Option<String>,
+    pub update: SagaUpdate,
+}
+
+/// Outcome of applying a reply to a saga.
+enum Transition {
+    /// The reply is not the awaited one, nothing changes.
+    Ignored,
+    /// The saga changed, with the step to dispatch next if any.
+    Applied(Option<SagaStep>),
+}
+
+/// Runs sagas: consumes `commence_saga` and `reply_to_saga`, dispatches
+/// every step to the `_saga_commands` queue of its microservice and compensates the
+/// completed steps when one fails. The state of every saga lives in a [`SagaStore`].
+#[derive(Clone)]
+pub struct SagaOrchestrator {
+    definitions: Arc<HashMap<SagaTitle, SagaDefinition>>,
+    store: BlockingSagaStore,
+    notices: broadcast::Sender<SagaNotice>,
+}
+
+impl SagaOrchestrator {
+    pub(crate) fn new(definitions: Vec<SagaDefinition>, store: Arc<dyn SagaStore>) -> Self {
+        let definitions = definitions
+            .into_iter()
+            .map(|definition| (definition.title, definition))
+            .collect();
+        Self {
+            definitions: Arc::new(definitions),
+            store: BlockingSagaStore::new(store),
+            notices: broadcast::channel(NOTICES_CAPACITY).0,
+        }
+    }
+
+    pub(crate) fn notices(&self) -> broadcast::Receiver<SagaNotice> {
+        self.notices.subscribe()
+    }
+
+    /// Reports the stored state of a saga commenced with a `reply_to` queue.
+    fn notify(&self, record: &SagaRecord) {
+        let Some(reply_to) = record.reply_to.clone() else {
+            return;
+        };
+        let snapshot = self.snapshot(record);
+        let update = SagaUpdate {
+            correlation_id: record.correlation_id.clone(),
+            saga_id: snapshot.saga_id,
+            title: snapshot.title,
+            status: snapshot.status,
+            current_command: snapshot.current_command,
+            failure_reason: snapshot.failure_reason,
+            progress_detail: snapshot.progress_detail,
+        };
+        // Nobody listens until the orchestrator consumes
+        let _ = self.notices.send(SagaNotice {
+            reply_to,
+            operation_id: record.operation_id.clone(),
+            update,
+        });
+    }
+
+    /// Snapshot of the saga `saga_id`, `None` if the store does not have it.
+    pub async fn saga(&self, saga_id: i32) -> Result<Option<SagaSnapshot>, SagaStoreError> {
+        Ok(self
+            .store
+            .get(saga_id)
+            .await?
+            .map(|record| self.snapshot(&record)))
+    }
+
+    /// Snapshots of every saga that has not finished yet.
+    pub async fn running_sagas(&self) -> Result<Vec<SagaSnapshot>, SagaStoreError> {
+        Ok(self
+            .store
+            .unfinished()
+            .await?
+            .iter()
+            .map(|record| self.snapshot(record))
+            .collect())
+    }
+
+    fn snapshot(&self, record: &SagaRecord) -> SagaSnapshot {
+        let current_command = self.expected_step(record).map(|step| step.command.clone());
+        let payload = record
+            .completed
+            .last()
+            .map(|step| step.output.clone())
+            .unwrap_or_default();
+        SagaSnapshot {
+            saga_id: record.saga_id,
+            title: record.title,
+            status: record.status,
+            current_command,
+            payload,
+            failure_reason: record.failure_reason.clone(),
+            step_started_at: record.step_started_at,
+            deadline: record.deadline,
+            last_progress_at: record.last_progress_at,
+            progress_detail: record.progress_detail.clone(),
+        }
+    }
+
+    /// Unfinished sagas whose current step was dispatched, or last reported progress, at
+    /// least `threshold` ago, oldest first. These wait for a participant that may be down,
+    /// see also [`SagaStepDefinition::timeout`].
+    pub async fn stuck_sagas(
+        &self,
+        threshold: Duration,
+    ) -> Result<Vec<SagaSnapshot>, SagaStoreError> {
+        let now = now_millis();
+        let threshold = threshold.as_millis() as u64;
+        let mut stuck: Vec<SagaSnapshot> = self
+            .store
+            .unfinished()
+            .await?
+            .iter()
+            .filter(|record| now.saturating_sub(last_sign_of_life(record)) >= threshold)
+            .map(|record| self.snapshot(record))
+            .collect();
+        stuck.sort_by_key(|snapshot| {
+            snapshot
+                .last_progress_at
+                .unwrap_or(snapshot.step_started_at)
+        });
+        Ok(stuck)
+    }
+
+    /// Step whose reply the saga is waiting for, the one being run or compensated.
+    fn expected_step(&self, record: &SagaRecord) -> Option<&SagaStepDefinition> {
+        if record.status.is_finished() {
+            return None;
+        }
+        self.definitions
+            .get(&record.title)?
+            .steps
+            .get(record.current_step)
+    }
+
+    /// The step the saga is waiting for, as it was dispatched.
+    fn pending_step(&self, record: &SagaRecord) -> Option<SagaStep> {
+        let step = self.expected_step(record)?;
+        Some(new_step(
+            record.saga_id,
+            step,
+            record.input.clone(),
+            record.status == SagaStatus::Compensating,
+        ))
+    }
+
+    /// Stores a new saga and returns its first step.
+    async fn commence(
+        &self,
+        commence: CommenceSaga<Value>,
+        operation_id: Option<String>,
+    ) -> Result<SagaStep, RabbitMQError> {
+        let CommenceSaga {
+            title,
+            payload,
+            correlation_id,
+            reply_to,
+        } = commence;
+        let definition = self.definitions.get(&title).ok_or_else(|| {
+            RabbitMQError::InvalidPayload(format!("unknown saga {}", title.as_ref()))
+        })?;
+        let first = definition.steps.first().ok_or_else(|| {
+            RabbitMQError::InvalidPayload(format!("saga {} has no steps", title.as_ref()))
+        })?;
+        let input: HashMap<String, Value> = match payload {
+            Value::Object(map) => map.into_iter().collect(),
+            _ => {
+                return Err(RabbitMQError::InvalidPayload(
+                    "Expected an object".to_string(),
+                ))
+            }
+        };
+
+        let mut record = SagaRecord {
+            saga_id: 0,
+            title,
+            status: SagaStatus::Running,
+            current_step: 0,
+            input,
+            completed: Vec::new(),
+            failure_reason: None,
+            operation_id,
+            history: Vec::new(),
+            step_started_at: 0,
+            deadline: None,
+            dispatch_pending: false,
+            timeout_retries: 0,
+            last_progress_at: None,
+            progress_detail: None,
+            correlation_id,
+            reply_to,
+            version: 0,
+        };
+        record_transition(&mut record, &first.command);
+        self.start_step(&mut record, now_millis());
+        let record = self.store.create(record).await?;
+        info!("Saga {} ({}) commenced", record.saga_id, title.as_ref());
+        self.notify(&record);
+        Ok(new_step(record.saga_id, first, record.input, false))
+    }
+
+    /// Applies the reply of a participant and returns the step to dispatch next, if any.
+    /// Replies that are not the awaited one (duplicates, unknown sagas) are ignored. A
+    /// concurrent change of the saga makes the reply be applied again on the fresh state.
+    async fn on_reply(&self, reply: &SagaStep) -> Result<Option<SagaStep>, RabbitMQError> {
+        self.on_reply_at(reply, now_millis()).await
+    }
+
+    async fn on_reply_at(
+        &self,
+        reply: &SagaStep,
+        now: u64,
+    ) -> Result<Option<SagaStep>, RabbitMQError> {
+        loop {
+            let Some(mut record) = self.store.get(reply.saga_id).await? else {
+                warn!(
+                    "Reply {} to unknown saga {} ignored",
+                    reply.command.as_ref(),
+                    reply.saga_id
+                );
+                return Ok(None);
+            };
+            let next = match self.apply_reply(&mut record, reply, now) {
+                Transition::Ignored => return Ok(None),
+                Transition::Applied(next) => next,
+            };
+            match self.store.update(&record).await {
+                Ok(updated) => {
+                    self.notify(&updated);
+                    return Ok(next);
+                }
+                Err(SagaStoreError::Conflict { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn apply_reply(&self, record: &mut SagaRecord, reply: &SagaStep, now: u64) -> Transition {
+        let Some(expected) = self.expected_step(record) else {
+            warn!(
+                "Reply {} to finished saga {} ignored",
+                reply.command.as_ref(),
+                reply.saga_id
+            );
+            return Transition::Ignored;
+        };
+        let compensating = record.status == SagaStatus::Compensating;
+        if expected.command != reply.command || compensating != reply.is_compensation {
+            warn!(
+                "Reply {} to saga {} ignored, waiting for {}",
+                reply.command.as_ref(),
+                reply.saga_id,
+                expected.command.as_ref()
+            );
+            return Transition::Ignored;
+        }
+
+        let Some(definition) = self.definitions.get(&record.title) else {
+            return Transition::Ignored;
+        };
+        let steps = &definition.steps;
+        if matches!(reply.status, Status::Sent | Status::Pending) {
+            // The participant is alive: the step gets its whole timeout again
+            record.last_progress_at = Some(now);
+            record.progress_detail = reply.progress_detail.clone();
+            record.dispatch_pending = false;
+            record.deadline = expected
+                .timeout
+                .map(|timeout| now + timeout.as_millis() as u64);
+            return Transition::Applied(None);
+        }
+        let next = match (record.status, &reply.status) {
+            (_, Status::Failure) => fail_current_step(record, reply.failure_reason.clone(), steps),
+            (SagaStatus::Running, Status::Success) => {
+                record.completed.push(CompletedStep {
+                    input: std::mem::take(&mut record.input),
+                    output: reply.payload.clone(),
+                });
+                record.current_step += 1;
+                match steps.get(record.current_step) {
+                    Some(next) => {
+                        record.input = reply.payload.clone();
+                        Some(new_step(record.saga_id, next, reply.payload.clone(), false))
+                    }
+                    None => {
+                        record.status = SagaStatus::Completed;
+                        info!(
+                            "Saga {} ({}) completed",
+                            record.saga_id,
+                            record.title.as_ref()
+                        );
+                        None
+                    }
+                }
+            }
+            (SagaStatus::Compensating, Status::Success) => next_compensation(record, steps),
+            _ => return Transition::Ignored,
+        };
+        record_transition(record, &reply.command);
+        self.start_step(record, now);
+        Transition::Applied(next)
+    }
+
+    /// Moves the saga to a new step, pending until [`SagaOrchestrator::dispatched`]
+    /// confirms it was sent.
+    fn start_step(&self, record: &mut SagaRecord, now: u64) {
+        record.step_started_at = now;
+        record.timeout_retries = 0;
+        record.last_progress_at = None;
+        record.progress_detail = None;
+        record.dispatch_pending = self.expected_step(record).is_some();
+        record.deadline = record
+            .dispatch_pending
+            .then(|| now + DISPATCH_RETRY_DELAY.as_millis() as u64);
+    }
+
+    /// Records that `step` was sent: the clock of its timeout starts. Changes nothing when
+    /// the saga already moved on or the step was confirmed before.
+    async fn dispatched(&self, step: &SagaStep) -> Result<(), SagaStoreError> {
+        self.dispatched_at(step, now_millis()).await
+    }
+
+    async fn dispatched_at(&self, step: &SagaStep, now: u64) -> Result<(), SagaStoreError> {
+        loop {
+            let Some(mut record) = self.store.get(step.saga_id).await? else {
+                return Ok(());
+            };
+            let Some(expected) = self.expected_step(&record) else {
+                return Ok(());
+            };
+            let compensating = record.status == SagaStatus::Compensating;
+            if !record.dispatch_pending
+                || expected.command != step.command
+                || compensating != step.is_compensation
+            {
+                return Ok(());
+            }
+            record.dispatch_pending = false;
+            record.deadline = expected
+                .timeout
+                .map(|timeout| now + timeout.as_millis() as u64);
+            match self.store.update(&record).await {
+                Ok(_) => return Ok(()),
+                Err(SagaStoreError::Conflict { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Handles the steps whose timeout expired: they are dispatched again while they have
+    /// [`SagaStepDefinition::retry_on_timeout`] retries left, otherwise they fail like a
+    /// `Failure` reply would. Steps that were stored but not confirmed as sent are dispatched
+    /// again without using a retry. Returns the steps to dispatch with their operation.
+    pub(crate) async fn sweep_expired(
+        &self,
+    ) -> Result<Vec<(SagaStep, Option<String>)>, SagaStoreError> {
+        self.sweep_expired_at(now_millis()).await
+    }
+
+    async fn sweep_expired_at(
+        &self,
+        now: u64,
+    ) -> Result<Vec<(SagaStep, Option<String>)>, SagaStoreError> {
+        let mut to_dispatch = Vec::new();
+        for mut record in self.store.expired(now).await? {
+            if record.deadline.is_none_or(|deadline| deadline > now) {
+                continue;
+            }
+            let (Some(definition), Some(step)) = (
+                self.definitions.get(&record.title),
+                self.expected_step(&record).cloned(),
+            ) else {
+                continue;
+            };
+            let timeout = step.timeout.unwrap_or_default();
+
+            let next = if record.dispatch_pending {
+                record.deadline = Some(now + DISPATCH_RETRY_DELAY.as_millis() as u64);
+                warn!(
+                    "Step {} of saga {} was not sent, dispatching it again",
+                    step.command.as_ref(),
+                    record.saga_id
+                );
+                self.pending_step(&record)
+            } else if record.timeout_retries < step.timeout_retries {
+                record.timeout_retries += 1;
+                record.deadline = Some(now + timeout.as_millis() as u64);
+                warn!(
+                    "Step {} of saga {} timed out, dispatching it again ({}/{})",
+                    step.command.as_ref(),
+                    record.saga_id,
+                    record.timeout_retries,
+                    step.timeout_retries
+                );
+                self.pending_step(&record)
+            } else {
+                let reason = format!(
+                    "{} timed out after {} ms",
+                    step.command.as_ref(),
+                    timeout.as_millis()
+                );
+                let next = fail_current_step(&mut record, Some(reason), &definition.steps);
+                record_transition(&mut record, &step.command);
+                self.start_step(&mut record, now);
+                next
+            };
+
+            match self.store.update(&record).await {
+                Ok(updated) => {
+                    self.notify(&updated);
+                    if let Some(next) = next {
+                        to_dispatch.push((next, record.operation_id.clone()));
+                    }
+                }
+                // A reply arrived meanwhile, the saga is evaluated again in the next sweep
+                Err(SagaStoreError::Conflict { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(to_dispatch)
+    }
+
+    /// The pending step of every saga that was mid-flight, to dispatch them again after a
+    /// restart: the orchestrator may have stopped between storing a step and sending it.
+    async fn recover(&self) -> Result<Vec<(SagaStep, Option<String>)>, SagaStoreError> {
+        Ok(self
+            .store
+            .unfinished()
+            .await?
+            .into_iter()
+            .filter_map(|record| {
+                self.pending_step(&record)
+                    .map(|step| (step, record.operation_id))
+            })
+            .collect())
+    }
+
+    async fn operation_of(&self, saga_id: i32) -> Result<Option<String>, SagaStoreError> {
+        Ok(self
+            .store
+            .get(saga_id)
+            .await?
+            .and_then(|record| record.operation_id))
+    }
+}
+
+fn last_sign_of_life(record: &SagaRecord) -> u64 {
+    record.last_progress_at.unwrap_or(record.step_started_at)
+}
+
+fn record_transition(record: &mut SagaRecord, command: &StepCommand) {
+    record.history.push(SagaTransition {
+        step: record.current_step,
+        command: command.clone(),
+        status: record.status,
+        at: now_millis(),
+    });
+}
+
+/// Fails the step the saga is waiting for: a failed step starts the compensation, a failed
+/// compensation leaves the saga for manual intervention.
+fn fail_current_step(
+    record: &mut SagaRecord,
+    reason: Option<String>,
+    steps: &[SagaStepDefinition],
+) -> Option<SagaStep> {
+    let command = steps[record.current_step].command.as_ref();
+    let reason_text = reason.as_deref().unwrap_or("no reason").to_string();
+    if record.status == SagaStatus::Compensating {
+        record.status = SagaStatus::CompensationFailed;
+        error!(
+            "Compensation of {} in saga {} failed, the saga needs manual intervention: {}",
+            command, record.saga_id, reason_text
+        );
+        return None;
+    }
+    warn!(
+        "Step {} of saga {} failed, compensating: {}",
+        command, record.saga_id, reason_text
+    );
+    record.status = SagaStatus::Compensating;
+    record.failure_reason = reason;
+    next_compensation(record, steps)
+}
+
+/// Moves the saga to the previous completed step that is compensable and returns it,
+/// or finishes the saga as compensated when there is none left.
+fn next_compensation(record: &mut SagaRecord, steps: &[SagaStepDefinition]) -> Option<SagaStep> {
+    while let Some(completed) = record.completed.pop() {
+        record.current_step = record.completed.len();
+        let step = &steps[record.current_step];
+        if step.compensable {
+            // The compensation knows what the step received and what it did
+            let mut payload = completed.input;
+            payload.extend(completed.output);
+            record.input = payload.clone();
+            return Some(new_step(record.saga_id, step, payload, true));
+        }
+    }
+    record.input.clear();
+    record.status = SagaStatus::Compensated;
+    info!(
+        "Saga {} ({}) compensated",
+        record.saga_id,
+        record.title.as_ref()
+    );
+    None
+}
+
+fn new_step(
+    saga_id: i32,
+    step: &SagaStepDefinition,
+    previous_payload: HashMap<String, Value>,
+    is_compensation: bool,
+) -> SagaStep {
+    SagaStep {
+        microservice: step.microservice.clone(),
+        command: step.command.clone(),
+        status: Status::Sent,
+        saga_id,
+        payload: HashMap::new(),
+        previous_payload,
+        is_current_step: true,
+        is_compensation,
+        failure_reason: None,
+        progress_detail: None,
+    }
+}
+
+impl RabbitMQClient {
+    /// Runs this microservice as the saga orchestrator for `definitions`, the role of the
+    /// "transactional" microservice. Consumes `commence_saga` and `reply_to_saga`, fails
+    /// before consuming when a definition does not pass [`SagaDefinition::validate`].
+    /// The sagas are kept in memory, see [`RabbitMQClient::start_saga_orchestrator_with_store`].
+    pub async fn start_saga_orchestrator(
+        &self,
+        definitions: Vec<SagaDefinition>,
+    ) -> Result<SagaOrchestrator, RabbitMQError> {
+        self.start_saga_orchestrator_with_store(definitions, Arc::new(InMemorySagaStore::new()))
+            .await
+    }
+
+    /// Like [`RabbitMQClient::start_saga_orchestrator`], keeping the sagas in `store`. The
+    /// pending step of every unfinished saga in the store is dispatched again before consuming.
+    /// A client runs one orchestrator, later calls return it and ignore their arguments.
+    pub async fn start_saga_orchestrator_with_store(
+        &self,
+        definitions: Vec<SagaDefinition>,
+        store: Arc<dyn SagaStore>,
+    ) -> Result<SagaOrchestrator, RabbitMQError> {
+        validate_definitions(&definitions)?;
+        // Held until the consumers run, a concurrent call waits and gets this orchestrator
+        let mut orchestrator_guard = self.orchestrator.lock().await;
+        if let Some(orchestrator) = orchestrator_guard.as_ref() {
+            warn!("The saga orchestrator is already running, the new definitions are ignored");
+            return Ok(orchestrator.clone());
+        }
+        self.create_orchestrator_resources().await?;
+        // Undecodable commence requests and replies are quarantined and audited as dead letters
+        self.create_quarantine_resources().await?;
+        self.create_audit_logging_resources().await?;
+        let orchestrator = SagaOrchestrator::new(definitions, store);
+
+        let pending = orchestrator.recover().await?;
+        if !pending.is_empty() {
+            info!("Resuming {} unfinished sagas", pending.len());
+        }
+        for (step, operation_id) in pending {
+            Self::dispatch_pending_step(&orchestrator, &step, operation_id).await;
+        }
+        *orchestrator_guard = Some(orchestrator.clone());
+        drop(orchestrator_guard);
+
+        Self::start_notifying(orchestrator.notices());
+        self.start_orchestrating(orchestrator.clone());
+        Self::start_sweeping(orchestrator.clone());
+
+        Ok(orchestrator)
+    }
+
+    /// Periodically fails or dispatches again the steps whose timeout expired.
+    fn start_sweeping(orchestrator: SagaOrchestrator) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let to_dispatch = match orchestrator.sweep_expired().await {
+                    Ok(to_dispatch) => to_dispatch,
+                    Err(e) => {
+                        error!("Error sweeping expired saga steps: {:?}", e);
+                        continue;
+                    }
+                };
+                for (step, operation_id) in to_dispatch {
+                    Self::dispatch_pending_step(&orchestrator, &step, operation_id).await;
+                }
+            }
+        });
+    }
+
+    /// Sends every change of a saga to the `reply_to` queue of its [`crate::saga_handle::SagaHandle`].
+    fn start_notifying(mut notices: broadcast::Receiver<SagaNotice>) {
+        tokio::spawn(async move {
+            loop {
+                let notice = match notices.recv().await {
+                    Ok(notice) => notice,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!(
+                            "{} saga updates were not sent, the publisher fell behind",
+                            missed
+                        );
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                let sent = with_operation(
+                    notice.operation_id,
+                    Self::send(&notice.reply_to, &notice.update),
+                )
+                .await;
+                if let Err(e) = sent {
+                    error!(
+                        "Error sending update of saga {}: {:?}",
+                        notice.update.saga_id, e
+                    );
+                }
+            }
+        });
+    }
+
+    pub(crate) fn start_orchestrating(&self, orchestrator: SagaOrchestrator) {
+        tokio::spawn({
+            let client = self.clone();
+            let orchestrator = orchestrator.clone();
+
+            async move {
+                if let Err(e) = client
+                    .consume_orchestrator_queue(Queue::COMMENCE_SAGA, orchestrator)
+                    .await
+                {
+                    error!("Error consuming {}: {:?}", Queue::COMMENCE_SAGA, e);
+                }
+            }
+        });
+
+        tokio::spawn({
+            let client = self.clone();
+
+            async move {
+                if let Err(e) = client
+                    .consume_orchestrator_queue(Queue::REPLY_TO_SAGA, orchestrator)
+                    .await
+                {
+                    error!("Error consuming {}: {:?}", Queue::REPLY_TO_SAGA, e);
+                }
+            }
+        });
+    }
+
+    /// The orchestrator owns the queues the participants send to
+    async fn create_orchestrator_resources(&self) -> Result<(), lapin::Error> {
+        let channel = self.saga_channel.lock().await;
+        for queue_name in [Queue::COMMENCE_SAGA, Queue::REPLY_TO_SAGA] {
+            channel
+                .queue_declare(
+                    queue_name,
+                    QueueDeclareOptions {
+                        durable: true,
+                        ..QueueDeclareOptions::default()
+                    },
+                    FieldTable::default(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn consume_orchestrator_queue(
+        &self,
+        queue_name: &str,
+        orchestrator: SagaOrchestrator,
+    ) -> Result<(), RabbitMQError> {
+        let channel = self.saga_channel.lock().await;
+
+        let mut consumer = channel
+            .basic_consume(
+                queue_name,
+                &format!("{queue_name}_consumer"),
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        // it needs to drop manually, next is an infinite loop
+        drop(channel);
+
+        while let Some(delivery) = consumer.next().await {
+            match delivery {
+                Ok(delivery) => {
+                    let result = if queue_name == Queue::COMMENCE_SAGA {
+                        self.handle_commence_saga(&delivery, &orchestrator).await
+                    } else {
+                        self.handle_saga_reply(&delivery, &orchestrator).await
+                    };
+                    match result {
+                        Ok(()) => {
+                            let _ = delivery.ack(BasicAckOptions::default()).await;
+                        }
+                        Err(e) if requeues(&e) => {
+                            error!(
+                                "Error handling {} message, it is requeued in {:?}: {:?}",
+                                queue_name, STORE_RETRY_DELAY, e
+                            );
+                            // The queue is consumed one delivery at a time, waiting before
+                            // the requeue keeps a failing store from being retried in a loop
+                            tokio::time::sleep(STORE_RETRY_DELAY).await;
+                            let _ = delivery
+                                .nack(BasicNackOptions {
+                                    requeue: true,
+                                    ..BasicNackOptions::default()
+                                })
+                                .await;
+                        }
+                        Err(e) => {
+                            error!("Error handling {} message: {:?}", queue_name, e);
+                            self.reject_delivery(&delivery, queue_name, &e).await;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Error receiving message: {:?}", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_commence_saga(
+        &self,
+        delivery: &lapin::message::Delivery,
+        orchestrator: &SagaOrchestrator,
+    ) -> Result<(), RabbitMQError> {
+        let commence: CommenceSaga<Value> = decode_delivery(delivery)?;
+        let operation_id = delivery
+            .properties
+            .headers()
+            .as_ref()
+            .and_then(operation_from_headers);
+        let step = orchestrator
+            .commence(commence, operation_id.clone())
+            .await?;
+        Self::dispatch_pending_step(orchestrator, &step, operation_id).await;
+        Ok(())
+    }
+
+    async fn handle_saga_reply(
+        &self,
+        delivery: &lapin::message::Delivery,
+        orchestrator: &SagaOrchestrator,
+    ) -> Result<(), RabbitMQError> {
+        let reply: SagaStep = decode_delivery(delivery)?;
+        match orchestrator.on_reply(&reply).await? {
+            Some(step) => {
+                let operation_id = orchestrator.operation_of(reply.saga_id).await?;
+                Self::dispatch_pending_step(orchestrator, &step, operation_id).await;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Sends a stored step and confirms it in the store. A step that cannot be sent stays
+    /// pending and the sweeper sends it again, the delivery that produced it is acked:
+    /// handling it again would commence the saga twice or ignore the applied reply.
+    async fn dispatch_pending_step(
+        orchestrator: &SagaOrchestrator,
+        step: &SagaStep,
+        operation_id: Option<String>,
+    ) {
+        if let Err(e) = with_operation(operation_id, Self::dispatch_step(step)).await {
+            error!(
+                "Error dispatching step {} of saga {}, it is sent again in {:?}: {:?}",
+                step.command.as_ref(),
+                step.saga_id,
+                DISPATCH_RETRY_DELAY,
+                e
+            );
+            return;
+        }
+        if let Err(e) = orchestrator.dispatched(step).await {
+            error!(
+                "Error confirming step {} of saga {}: {:?}",
+                step.command.as_ref(),
+                step.saga_id,
+                e
+            );
+        }
+    }
+
+    /// Sends the step to the `_saga_commands` queue of its microservice through the commands exchange
+    async fn dispatch_step(step: &SagaStep) -> Result<(), RabbitMQError> {
+        let channel_arc = get_or_init_publish_channel().await?;
+        let channel = channel_arc.lock().await;
+
+        let routing_key = format!("{}_saga_commands_routing_key", step.microservice.as_ref());
+        let (body, properties) = compress(
+            serde_json::to_vec(step)?,
+            BasicProperties::default()
+                .with_headers(operation_headers())
+                .with_delivery_mode(2) // persistent
+                .with_content_type("application/json".into()),
+        )?;
+
+        channel
+            .basic_publish(
+                Exchange::COMMANDS,
+                &routing_key,
+                BasicPublishOptions::default(),
+                &body,
+                properties,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Whether a delivery whose handling failed with `error` goes back to its queue. Only
+/// undecodable deliveries are quarantined, a store or broker failure is temporary and
+/// dropping the delivery would lose the reply or the commence request.
+fn requeues(error: &RabbitMQError) -> bool {
+    !error.is_decode_error()
+}
+
+#[cfg(test)]
+mod test_orchestrator {
+    use super::*;
+    use crate::connection::AvailableMicroservices;
+    use serde_json::json;
+
+    fn definitions() -> Vec<SagaDefinition> {
+        vec![
+            SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner)
+                .compensable_step(AvailableMicroservices::TestImage, StepCommand::CreateImage)
+                .step(AvailableMicroservices::TestMint, StepCommand::MintImage)
+                .step(
+                    AvailableMicroservices::Blockchain,
+                    StepCommand::TransferMissionRewardToWinner,
+                ),
+        ]
+    }
+
+    fn orchestrator() -> SagaOrchestrator {
+        SagaOrchestrator::new(definitions(), Arc::new(InMemorySagaStore::new()))
+    }
+
+    fn commence_saga(payload: Value) -> CommenceSaga<Value> {
+        CommenceSaga {
+            title: SagaTitle::TransferCryptoRewardToMissionWinner,
+            payload,
+            correlation_id: None,
+            reply_to: None,
+        }
+    }
+
+    async fn commence(orchestrator: &SagaOrchestrator, payload: Value) -> SagaStep {
+        orchestrator
+            .commence(commence_saga(payload), None)
+            .await
+            .unwrap()
+    }
+
+    fn reply(step: &SagaStep, status: Status, payload: Value) -> SagaStep {
+        let mut reply = step.clone();
+        reply.status = status;
+        reply.payload = serde_json::from_value(payload).unwrap();
+        reply
+    }
+
+    async fn saga(orchestrator: &SagaOrchestrator, saga_id: i32) -> SagaSnapshot {
+        orchestrator.saga(saga_id).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn runs_every_step_in_order() {
+        let orchestrator = orchestrator();
+        let first = commence(&orchestrator, json!({"userId": "user1"})).await;
+        assert_eq!(first.command, StepCommand::CreateImage);
+        assert_eq!(first.previous_payload["userId"], json!("user1"));
+
+        let second = orchestrator
+            .on_reply(&reply(&first, Status::Success, json!({"imageId": 7}))).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.microservice, AvailableMicroservices::TestMint);
+        assert_eq!(second.previous_payload["imageId"], json!(7));
+
+        let third = orchestrator
+            .on_reply(&reply(&second, Status::Success, json!({"minted": true}))).await
+            .unwrap()
+            .unwrap();
+        assert_eq!(third.command, StepCommand::TransferMissionRewardToWinner);
+        assert_eq!(
+            orchestrator
+                .on_reply(&reply(&third, Status::Success, json!({}))).await
+                .unwrap(),
+            None
+        );
+
+        let saga = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(saga.status, SagaStatus::Completed);
+        assert_eq!(saga.current_command, None);
+        assert!(orchestrator.running_sagas().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_step_compensates_the_completed_ones() {
+        let orchestrator = orchestrator();
+        let first = commence(&orchestrator, json!({"userId": "user1"})).await;
+        let second = orchestrator
+            .on_reply(&reply(&first, Status::Success, json!({"imageId": 7}))).await
+            .unwrap()
+            .unwrap();
+
+        let mut failure = reply(&second, Status::Failure, json!({}));
+        failure.failure_reason = Some("out of stock".to_string());
+        let compensation = orchestrator.on_reply(&failure).await.unwrap().unwrap();
+        assert_eq!(compensation.command, StepCommand::CreateImage);
+        assert!(compensation.is_compensation);
+        assert_eq!(compensation.microservice, AvailableMicroservices::TestImage);
+        assert_eq!(compensation.previous_payload["userId"], json!("user1"));
+        assert_eq!(compensation.previous_payload["imageId"], json!(7));
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(snapshot.status, SagaStatus::Compensating);
+        assert_eq!(snapshot.failure_reason.as_deref(), Some("out of stock"));
+
+        // The regular reply of the command is not the compensation reply
+        let mut not_compensation = reply(&compensation, Status::Success, json!({}));
+        not_compensation.is_compensation = false;
+        assert_eq!(orchestrator.on_reply(&not_compensation).await.unwrap(), None);
+        assert_eq!(
+            saga(&orchestrator, first.saga_id).await.status,
+            SagaStatus::Compensating
+        );
+
+        assert_eq!(
+            orchestrator
+                .on_reply(&reply(&compensation, Status::Success, json!({}))).await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            saga(&orchestrator, first.saga_id).await.status,
+            SagaStatus::Compensated
+        );
+    }
+
+    #[tokio::test]
+    async fn unexpected_replies_are_ignored() {
+        let orchestrator = orchestrator();
+        let first = commence(&orchestrator, json!({})).await;
+
+        let mut wrong_command = reply(&first, Status::Success, json!({}));
+        wrong_command.command = StepCommand::MintImage;
+        assert_eq!(orchestrator.on_reply(&wrong_command).await.unwrap(), None);
+
+        let mut unknown_saga = reply(&first, Status::Success, json!({}));
+        unknown_saga.saga_id = 99;
+        assert_eq!(orchestrator.on_reply(&unknown_saga).await.unwrap(), None);
+
+        // A duplicated reply is applied once
+        let success = reply(&first, Status::Success, json!({}));
+        assert!(orchestrator.on_reply(&success).await.unwrap().is_some());
+        assert_eq!(orchestrator.on_reply(&success).await.unwrap(), None);
+
+        let saga = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(saga.status, SagaStatus::Running);
+        assert_eq!(saga.current_command, Some(StepCommand::MintImage));
+    }
+
+    #[tokio::test]
+    async fn unknown_saga_title_is_rejected() {
+        let orchestrator = SagaOrchestrator::new(vec![], Arc::new(InMemorySagaStore::new()));
+        assert!(matches!(
+            orchestrator
+                .commence(
+                    CommenceSaga {
+                        title: SagaTitle::TransferCryptoRewardToRankingWinners,
+                        ..commence_saga(json!({}))
+                    },
+                    None
+                )
+                .await,
+            Err(RabbitMQError::InvalidPayload(_))
+        ));
+    }
+
+    fn timed_orchestrator() -> SagaOrchestrator {
+        SagaOrchestrator::new(
+            vec![
+                SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner)
+                    .compensable_step(AvailableMicroservices::TestImage, StepCommand::CreateImage)
+                    .then(
+                        SagaStepDefinition::new(
+                            AvailableMicroservices::TestMint,
+                            StepCommand::MintImage,
+                        )
+                        .timeout(Duration::from_secs(10))
+                        .retry_on_timeout(1),
+                    ),
+            ],
+            Arc::new(InMemorySagaStore::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn expired_step_is_retried_then_compensated() {
+        let orchestrator = timed_orchestrator();
+        let first = commence(&orchestrator, json!({"userId": "user1"})).await;
+        orchestrator.dispatched(&first).await.unwrap();
+        // Steps without timeout never expire
+        assert_eq!(saga(&orchestrator, first.saga_id).await.deadline, None);
+        let second = orchestrator
+            .on_reply(&reply(&first, Status::Success, json!({"imageId": 7}))).await
+            .unwrap()
+            .unwrap();
+
+        // The timeout runs from the dispatch of the step
+        orchestrator.dispatched_at(&second, 1_000).await.unwrap();
+        let deadline = saga(&orchestrator, first.saga_id).await.deadline.unwrap();
+        assert_eq!(deadline, 11_000);
+        assert!(orchestrator
+            .sweep_expired_at(deadline - 1).await
+            .unwrap()
+            .is_empty());
+
+        let retried = orchestrator.sweep_expired_at(deadline).await.unwrap();
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].0, second);
+        assert_eq!(
+            saga(&orchestrator, first.saga_id).await.deadline,
+            Some(deadline + 10_000)
+        );
+
+        let compensation = orchestrator.sweep_expired_at(deadline + 10_000).await.unwrap();
+        assert_eq!(compensation.len(), 1);
+        assert_eq!(compensation[0].0.command, StepCommand::CreateImage);
+        assert!(compensation[0].0.is_compensation);
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(snapshot.status, SagaStatus::Compensating);
+        assert_eq!(
+            snapshot.failure_reason.as_deref(),
+            Some("mint_image timed out after 10000 ms")
+        );
+        // The compensation has no timeout
+        orchestrator.dispatched(&compensation[0].0).await.unwrap();
+        assert_eq!(saga(&orchestrator, first.saga_id).await.deadline, None);
+
+        // The late reply of the expired step is ignored
+        assert_eq!(
+            orchestrator
+                .on_reply(&reply(&second, Status::Success, json!({}))).await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn progress_extends_the_deadline() {
+        let orchestrator = timed_orchestrator();
+        let first = commence(&orchestrator, json!({})).await;
+        let second = orchestrator
+            .on_reply(&reply(&first, Status::Success, json!({}))).await
+            .unwrap()
+            .unwrap();
+        orchestrator.dispatched_at(&second, 1_000).await.unwrap();
+        let deadline = saga(&orchestrator, first.saga_id).await.deadline.unwrap();
+        assert_eq!(deadline, 11_000);
+
+        // Progress of a step that is not awaited changes nothing
+        let mut stale = reply(&first, Status::Pending, json!({}));
+        stale.progress_detail = Some("late".to_string());
+        assert_eq!(orchestrator.on_reply_at(&stale, 2_000).await.unwrap(), None);
+        assert_eq!(saga(&orchestrator, first.saga_id).await.progress_detail, None);
+
+        let mut progress = reply(&second, Status::Pending, json!({}));
+        progress.progress_detail = Some("waiting for confirmations".to_string());
+        assert_eq!(
+            orchestrator.on_reply_at(&progress, 2_000).await.unwrap(),
+            None
+        );
+
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(snapshot.status, SagaStatus::Running);
+        assert_eq!(snapshot.current_command, Some(StepCommand::MintImage));
+        assert_eq!(
+            snapshot.progress_detail.as_deref(),
+            Some("waiting for confirmations")
+        );
+        assert_eq!(snapshot.last_progress_at, Some(2_000));
+        assert_eq!(snapshot.deadline, Some(12_000));
+        assert!(orchestrator.sweep_expired_at(deadline).await.unwrap().is_empty());
+
+        // The next step starts without progress
+        orchestrator
+            .on_reply(&reply(&second, Status::Success, json!({}))).await
+            .unwrap();
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(snapshot.status, SagaStatus::Completed);
+        assert_eq!(snapshot.progress_detail, None);
+    }
+
+    #[tokio::test]
+    async fn unsent_step_is_dispatched_again() {
+        let orchestrator = timed_orchestrator();
+        let first = commence(&orchestrator, json!({})).await;
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        let deadline = snapshot.deadline.unwrap();
+        assert_eq!(
+            deadline,
+            snapshot.step_started_at + DISPATCH_RETRY_DELAY.as_millis() as u64
+        );
+        assert!(orchestrator
+            .sweep_expired_at(deadline - 1).await
+            .unwrap()
+            .is_empty());
+
+        let resent = orchestrator.sweep_expired_at(deadline).await.unwrap();
+        assert_eq!(resent.len(), 1);
+        assert_eq!(resent[0].0, first);
+        let record = orchestrator.store.get(first.saga_id).await.unwrap().unwrap();
+        assert!(record.dispatch_pending);
+        assert_eq!(record.timeout_retries, 0);
+        assert_eq!(
+            record.deadline,
+            Some(deadline + DISPATCH_RETRY_DELAY.as_millis() as u64)
+        );
+
+        orchestrator.dispatched(&first).await.unwrap();
+        let snapshot = saga(&orchestrator, first.saga_id).await;
+        assert_eq!(snapshot.deadline, None);
+        assert!(orchestrator
+            .sweep_expired_at(u64::MAX).await
+            .unwrap()
+            .is_empty());
+
+        // Confirming a step the saga moved past changes nothing
+        let second = orchestrator
+            .on_reply(&reply(&first, Status::Success, json!({}))).await
+            .unwrap()
+            .unwrap();
+        orchestrator.dispatched(&first).await.unwrap();
+        assert!(orchestrator.store.get(second.saga_id).await.unwrap().unwrap().dispatch_pending);
+    }
+
+    #[tokio::test]
+    async fn lists_stuck_sagas() {
+        let orchestrator = timed_orchestrator();
+        let first = commence(&orchestrator, json!({})).await;
+        assert_eq!(
+            orchestrator
+                .stuck_sagas(Duration::ZERO).await
+                .unwrap()
+                .iter()
+                .map(|snapshot| snapshot.saga_id)
+                .collect::<Vec<_>>(),
+            vec![first.saga_id]
+        );
+        assert!(orchestrator
+            .stuck_sagas(Duration::from_secs(3600)).await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn restarted_orchestrator_resumes_pending_steps() {
+        let store: Arc<dyn SagaStore> = Arc::new(InMemorySagaStore::new());
+        let orchestrator = SagaOrchestrator::new(definitions(), store.clone());
+        let first = commence(&orchestrator, json!({"__trace": "abc"})).await;
+        let second = orchestrator
+            .on_reply(&reply(
+                &first,
+                Status::Success,
+                json!({"__trace": "abc", "imageId": 7}),
+            )).await
+            .unwrap()
+            .unwrap();
+        let finished = commence(&orchestrator, json!({})).await;
+        let mut failure = reply(&finished, Status::Failure, json!({}));
+        failure.failure_reason = Some("boom".to_string());
+        // Nothing to compensate before the first step
+        assert_eq!(orchestrator.on_reply(&failure).await.unwrap(), None);
+
+        let restarted = SagaOrchestrator::new(definitions(), store);
+        let pending = restarted.recover().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        let (step, _) = &pending[0];
+        assert_eq!(step.saga_id, second.saga_id);
+        assert_eq!(step.command, second.command);
+        assert_eq!(step.previous_payload, second.previous_payload);
+
+        let history = restarted
+            .store
+            .get(second.saga_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .history;
+        assert_eq!(
+            history
+                .iter()
+                .map(|transition| transition.command.clone())
+                .collect::<Vec<_>>(),
+            vec![StepCommand::CreateImage, StepCommand::CreateImage]
+        );
+    }
+
+    #[tokio::test]
+    async fn notifies_the_commencer_of_every_change() {
+        let orchestrator = orchestrator();
+        let mut notices = orchestrator.notices();
+
+        // Sagas commenced without reply queue are not reported
+        commence(&orchestrator, json!({})).await;
+        let first = orchestrator
+            .commence(
+                CommenceSaga {
+                    correlation_id: Some("corr-1".to_string()),
+                    reply_to: Some("amq.gen-replies".to_string()),
+                    ..commence_saga(json!({}))
+                },
+                Some("op-1".to_string()),
+            )
+            .await
+            .unwrap();
+        let notice = notices.try_recv().unwrap();
+        assert_eq!(notice.reply_to, "amq.gen-replies");
+        assert_eq!(notice.operation_id.as_deref(), Some("op-1"));
+        assert_eq!(notice.update.correlation_id.as_deref(), Some("corr-1"));
+        assert_eq!(notice.update.saga_id, first.saga_id);
+        assert_eq!(notice.update.status, SagaStatus::Running);
+        assert_eq!(
+            notice.update.current_command,
+            Some(StepCommand::CreateImage)
+        );
+
+        let mut failure = reply(&first, Status::Failure, json!({}));
+        failure.failure_reason = Some("boom".to_string());
+        orchestrator.on_reply(&failure).await.unwrap();
+        let notice = notices.try_recv().unwrap();
+        assert_eq!(notice.update.status, SagaStatus::Compensated);
+        assert_eq!(notice.update.current_command, None);
+        assert_eq!(notice.update.failure_reason.as_deref(), Some("boom"));
+
+        // Ignored replies change nothing
+        orchestrator.on_reply(&failure).await.unwrap();
+        assert!(notices.try_recv().is_err());
+    }
+
+    /// A store whose every call fails, like a locked SQLite database.
+    struct FailingSagaStore;
+
+    impl SagaStore for FailingSagaStore {
+        fn create(&self, _: SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+            Err(SagaStoreError::Backend("database is locked".to_string()))
+        }
+
+        fn get(&self, _: i32) -> Result<Option<SagaRecord>, SagaStoreError> {
+            Err(SagaStoreError::Backend("database is locked".to_string()))
+        }
+
+        fn update(&self, _: &SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+            Err(SagaStoreError::Backend("database is locked".to_string()))
+        }
+
+        fn unfinished(&self) -> Result<Vec<SagaRecord>, SagaStoreError> {
+            Err(SagaStoreError::Backend("database is locked".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn store_failures_requeue_the_delivery() {
+        let step = commence(&orchestrator(), json!({})).await;
+        let orchestrator = SagaOrchestrator::new(definitions(), Arc::new(FailingSagaStore));
+
+        let commenced = orchestrator.commence(commence_saga(json!({})), None).await;
+        let error = commenced.unwrap_err();
+        assert!(matches!(error, RabbitMQError::SagaStore(_)));
+        assert!(requeues(&error));
+
+        let replied = orchestrator
+            .on_reply(&reply(&step, Status::Success, json!({})))
+            .await;
+        assert!(requeues(&replied.unwrap_err()));
+
+        // Undecodable deliveries are quarantined instead
+        let undecodable = serde_json::from_str::<SagaStep>("{").unwrap_err();
+        assert!(!requeues(&RabbitMQError::from(undecodable)));
+    }
+}
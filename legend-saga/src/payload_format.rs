@@ -0,0 +1,244 @@
+//! Serialization formats of the event payloads. Events are published in the format set for
+//! them, JSON unless changed, with its content type, and consumers decode each delivery by
+//! its `content_type` property. Publishers can then move to another format one at a time,
+//! as long as their consumers enable its feature first.
+
+use crate::compression::decompress;
+use crate::connection::{RabbitMQClient, RabbitMQError};
+use crate::events::MicroserviceEvent;
+use crate::raw_payload::RawPayload;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PayloadFormat {
+    #[default]
+    Json,
+    /// Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    /// Requires the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl PayloadFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            PayloadFormat::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            PayloadFormat::MessagePack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            PayloadFormat::Cbor => "application/cbor",
+        }
+    }
+
+    /// The format of a delivery by its content type, JSON when it has none. Parameters such
+    /// as `; charset=utf-8` are ignored.
+    pub fn from_content_type(content_type: Option<&str>) -> Result<Self, RabbitMQError> {
+        let Some(content_type) = content_type else {
+            return Ok(PayloadFormat::Json);
+        };
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "application/json" => Ok(PayloadFormat::Json),
+            #[cfg(feature = "msgpack")]
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Ok(PayloadFormat::MessagePack)
+            }
+            #[cfg(feature = "cbor")]
+            "application/cbor" => Ok(PayloadFormat::Cbor),
+            _ => Err(RabbitMQError::UnsupportedContentType(
+                content_type.to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, RabbitMQError> {
+        match self {
+            PayloadFormat::Json => Ok(serde_json::to_vec(value)?),
+            // Named fields, so the serde renames hold as in JSON
+            #[cfg(feature = "msgpack")]
+            PayloadFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| self.error(e)),
+            #[cfg(feature = "cbor")]
+            PayloadFormat::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(value, &mut body).map_err(|e| self.error(e))?;
+                Ok(body)
+            }
+        }
+    }
+
+    pub(crate) fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, RabbitMQError> {
+        match self {
+            PayloadFormat::Json => Ok(serde_json::from_slice(data)?),
+            #[cfg(feature = "msgpack")]
+            PayloadFormat::MessagePack => rmp_serde::from_slice(data).map_err(|e| self.error(e)),
+            #[cfg(feature = "cbor")]
+            PayloadFormat::Cbor => ciborium::from_reader(data).map_err(|e| self.error(e)),
+        }
+    }
+
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    fn error(&self, error: impl std::fmt::Display) -> RabbitMQError {
+        RabbitMQError::PayloadFormatError {
+            content_type: self.content_type(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PublishFormats {
+    default: PayloadFormat,
+    events: HashMap<MicroserviceEvent, PayloadFormat>,
+}
+
+// Shared by the clients of the process, like the publish channel
+static PUBLISH_FORMATS: Lazy<RwLock<PublishFormats>> = Lazy::new(Default::default);
+
+/// The format `event` is published in.
+pub(crate) fn publish_format(event: MicroserviceEvent) -> PayloadFormat {
+    let formats = PUBLISH_FORMATS.read().unwrap();
+    formats
+        .events
+        .get(&event)
+        .copied()
+        .unwrap_or(formats.default)
+}
+
+/// Decodes the body of a delivery in the format of its content type, decompressing it
+/// first by its content encoding.
+pub(crate) fn decode_delivery<T: DeserializeOwned>(
+    delivery: &lapin::message::Delivery,
+) -> Result<T, RabbitMQError> {
+    let (format, data) = delivery_body(delivery)?;
+    format.decode(&data)
+}
+
+/// The format of a delivery and its body, decompressed by its content encoding.
+pub(crate) fn delivery_body(
+    delivery: &lapin::message::Delivery,
+) -> Result<(PayloadFormat, Cow<'_, [u8]>), RabbitMQError> {
+    let properties = &delivery.properties;
+    let data = decompress(
+        properties.content_encoding().as_ref().map(|e| e.as_str()),
+        &delivery.data,
+    )?;
+    let format =
+        PayloadFormat::from_content_type(properties.content_type().as_ref().map(|c| c.as_str()))?;
+    Ok((format, data))
+}
+
+/// The payload of a delivery, decompressed by its content encoding. JSON payloads are kept
+/// as received, those of other formats are decoded.
+pub(crate) fn decode_payload(
+    delivery: &lapin::message::Delivery,
+) -> Result<RawPayload, RabbitMQError> {
+    let (format, data) = delivery_body(delivery)?;
+    match format {
+        PayloadFormat::Json => Ok(RawPayload::from_json(&data)?),
+        #[allow(unreachable_patterns)]
+        format => Ok(RawPayload::from_value(format.decode(&data)?)?),
+    }
+}
+
+impl RabbitMQClient {
+    /// Sets the format of the events published, except those given their own with
+    /// [`RabbitMQClient::set_event_format`]. Like `publish_event`, it applies to the whole
+    /// process rather than to one client.
+    pub fn set_publish_format(format: PayloadFormat) {
+        PUBLISH_FORMATS.write().unwrap().default = format;
+    }
+
+    /// Sets the format `event` is published in, for the whole process.
+    pub fn set_event_format(event: MicroserviceEvent, format: PayloadFormat) {
+        PUBLISH_FORMATS
+            .write()
+            .unwrap()
+            .events
+            .insert(event, format);
+    }
+}
+
+#[cfg(test)]
+mod test_payload_format {
+    use super::*;
+    use crate::events::BillingPaymentCreatedPayload;
+    use serde_json::{json, Value};
+
+    fn payment() -> BillingPaymentCreatedPayload {
+        BillingPaymentCreatedPayload {
+            payment_id: "pay_1".to_string(),
+            user_id: "user1".to_string(),
+            amount: 1000,
+            currency: "usd".to_string(),
+            status: "pending".to_string(),
+            metadata: HashMap::from([("plan".to_string(), "pro".to_string())]),
+            occurred_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn formats_are_chosen_by_content_type() {
+        assert_eq!(
+            PayloadFormat::from_content_type(None).unwrap(),
+            PayloadFormat::Json
+        );
+        assert_eq!(
+            PayloadFormat::from_content_type(Some("Application/JSON; charset=utf-8")).unwrap(),
+            PayloadFormat::Json
+        );
+        let error = PayloadFormat::from_content_type(Some("text/plain")).unwrap_err();
+        assert!(matches!(&error, RabbitMQError::UnsupportedContentType(c) if c == "text/plain"));
+        assert!(error.is_decode_error());
+        #[cfg(feature = "msgpack")]
+        assert_eq!(
+            PayloadFormat::from_content_type(Some("application/x-msgpack")).unwrap(),
+            PayloadFormat::MessagePack
+        );
+        #[cfg(feature = "cbor")]
+        assert_eq!(
+            PayloadFormat::from_content_type(Some("application/cbor")).unwrap(),
+            PayloadFormat::Cbor
+        );
+    }
+
+    #[test]
+    fn payloads_round_trip_in_every_format() {
+        let formats = [
+            PayloadFormat::Json,
+            #[cfg(feature = "msgpack")]
+            PayloadFormat::MessagePack,
+            #[cfg(feature = "cbor")]
+            PayloadFormat::Cbor,
+        ];
+        for format in formats {
+            let body = format.encode(&payment()).unwrap();
+            // Consumers decode into a map of JSON values, with the wire names
+            let payload: HashMap<String, Value> = format.decode(&body).unwrap();
+            assert_eq!(payload["userId"], json!("user1"), "{format:?}");
+            assert_eq!(payload["amount"], json!(1000), "{format:?}");
+            assert_eq!(payload["metadata"], json!({"plan": "pro"}), "{format:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "msgpack")]
+    fn events_are_published_in_their_format() {
+        // Not published by the integration tests, which run in the same process
+        let event = MicroserviceEvent::BillingPaymentCreated;
+        assert_eq!(publish_format(event), PayloadFormat::Json);
+        RabbitMQClient::set_event_format(event, PayloadFormat::MessagePack);
+        assert_eq!(publish_format(event), PayloadFormat::MessagePack);
+        assert_eq!(
+            publish_format(MicroserviceEvent::BillingSubscriptionCreated),
+            PayloadFormat::Json
+        );
+        PUBLISH_FORMATS.write().unwrap().events.remove(&event);
+    }
+}
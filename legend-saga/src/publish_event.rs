@@ -1,4 +1,4 @@
-use crate::events::{AuditPublishedPayload, PayloadEvent};
+use crate::events::{AuditPublishedPayload, PayloadEvent, Validate};
 use crate::queue_consumer_props::Exchange;
 use lapin::{
     options::BasicPublishOptions, types::AMQPValue,
@@ -6,15 +6,22 @@ use lapin::{
 };
 use serde::Serialize;
 use crate::connection::{get_or_init_publish_channel, get_stored_microservice, RabbitMQClient, RabbitMQError};
+use crate::compression::compress;
+use crate::payload_format::publish_format;
 use crate::operation::{apply_operation_header, current_operation, operation_headers, with_operation};
+use crate::validation::validate_payload;
+use crate::versioning::apply_schema_version_header;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::error;
 use uuid::Uuid;
 
 impl RabbitMQClient {
-    pub async fn publish_event<T: PayloadEvent + Serialize>(
+    /// Publishes an event to every microservice listening to it. The payload is validated
+    /// first, an invalid one is rejected with [`RabbitMQError::InvalidPayload`].
+    pub async fn publish_event<T: PayloadEvent + Validate + Serialize>(
         payload: T,
     ) -> Result<(), RabbitMQError> {
+        validate_payload(&payload)?;
         let channel_arc = get_or_init_publish_channel().await?;
         let channel = channel_arc.lock().await;
 
@@ -32,8 +39,18 @@ impl RabbitMQClient {
         );
         header_event.insert("all-micro".into(), AMQPValue::LongString("yes".into()));
         apply_operation_header(&mut header_event);
-
-        let body = serde_json::to_vec(&payload)?;
+        apply_schema_version_header(&mut header_event, event_type);
+
+        let format = publish_format(event_type);
+        let (body, properties) = compress(
+            format.encode(&payload)?,
+            BasicProperties::default()
+                .with_headers(header_event)
+                .with_content_type(format.content_type().into())
+                .with_delivery_mode(2) // persistent
+                .with_message_id(event_id.clone().into())
+                .with_app_id(publisher_microservice.clone().into()),
+        )?;
 
         // Publish main event with message properties for tracking
         channel
@@ -42,12 +59,7 @@ impl RabbitMQClient {
                 "",
                 BasicPublishOptions::default(),
                 &body,
-                BasicProperties::default()
-                    .with_headers(header_event)
-                    .with_content_type("application/json".into())
-                    .with_delivery_mode(2) // persistent
-                    .with_message_id(event_id.clone().into())
-                    .with_app_id(publisher_microservice.clone().into()),
+                properties,
             )
             .await?;
 
@@ -112,6 +124,23 @@ impl RabbitMQClient {
 
 }
 
+/// Publishes an audit event in the background inside the `operation_id` scope, a failing
+/// audit is logged and never fails the main flow.
+pub(crate) fn spawn_audit_event<T>(payload: T, operation_id: Option<String>)
+where
+    T: PayloadEvent + Serialize + Send + 'static,
+{
+    tokio::spawn(async move {
+        with_operation(operation_id, async {
+            let event_type = payload.event_type();
+            if let Err(e) = RabbitMQClient::publish_audit_event(payload).await {
+                error!("Failed to emit {} event: {:?}", event_type.as_ref(), e);
+            }
+        })
+        .await;
+    });
+}
+
 /// Integration test, the micro publishes an event and the "same" microservice client listens to it
 #[cfg(test)]
 mod test_publish_event {
@@ -0,0 +1,293 @@
+use crate::connection::{RabbitMQClient, RabbitMQError};
+use crate::events::{AuditDeadLetterPayload, MicroserviceEvent};
+use crate::nack::now_millis;
+use crate::operation::operation_from_headers;
+use crate::publish_event::spawn_audit_event;
+use crate::queue_consumer_props::Queue;
+use lapin::message::Delivery;
+use lapin::options::{
+    BasicAckOptions, BasicNackOptions, BasicPublishOptions, ConfirmSelectOptions,
+};
+use lapin::types::AMQPValue;
+use lapin::BasicProperties;
+use std::str::FromStr;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Header with the decoding error of a quarantined message.
+pub const QUARANTINE_ERROR_HEADER: &str = "x-quarantine-error";
+/// Header with the queue the quarantined message was consumed from.
+pub const QUARANTINE_SOURCE_QUEUE_HEADER: &str = "x-quarantine-source-queue";
+/// Header with the microservice that quarantined the message.
+pub const QUARANTINE_MICROSERVICE_HEADER: &str = "x-quarantine-microservice";
+/// Header with when the message was quarantined (UNIX timestamp in milliseconds).
+pub const QUARANTINED_AT_HEADER: &str = "x-quarantined-at";
+
+/// Rejection reason of the audit.dead_letter emitted for a quarantined message.
+const DECODE_ERROR_REASON: &str = "decode_error";
+
+impl RabbitMQError {
+    /// Whether the error comes from a delivery that can never be handled: a body that is not
+    /// the expected JSON (or format and encoding of its content type), headers without a
+    /// known event, a payload of a schema version that cannot be brought to the current one
+    /// or an invalid payload. Retrying those is pointless.
+    pub(crate) fn is_decode_error(&self) -> bool {
+        matches!(
+            self,
+            RabbitMQError::SerializationError(_)
+                | RabbitMQError::InvalidHeader
+                | RabbitMQError::InvalidEventKey(_)
+                | RabbitMQError::InvalidPayload(_)
+                | RabbitMQError::UnsupportedContentType(_)
+                | RabbitMQError::PayloadFormatError { .. }
+                | RabbitMQError::UnsupportedContentEncoding(_)
+                | RabbitMQError::CompressionError { .. }
+                | RabbitMQError::UnsupportedSchemaVersion { .. }
+                | RabbitMQError::UpcastError { .. }
+        )
+    }
+}
+
+impl RabbitMQClient {
+    /// Rejects a delivery whose handling failed with `error`. Undecodable deliveries are
+    /// quarantined, the rest (or a failed quarantine) are nacked.
+    pub(crate) async fn reject_delivery(
+        &self,
+        delivery: &Delivery,
+        queue_name: &str,
+        error: &RabbitMQError,
+    ) {
+        if error.is_decode_error() {
+            match self.quarantine(delivery, queue_name, error).await {
+                Ok(()) => return,
+                Err(e) => error!("Error quarantining message from {}: {:?}", queue_name, e),
+            }
+        }
+        let _ = delivery.nack(BasicNackOptions::default()).await;
+    }
+
+    /// Moves an undecodable delivery to [`Queue::QUARANTINE`] keeping the raw body and all
+    /// its headers, plus the error and the source queue, then acks the original delivery
+    /// and emits an audit.dead_letter with the `decode_error` reason. Fails without acking
+    /// when the broker does not confirm the copy, e.g. the quarantine queue does not exist.
+    pub(crate) async fn quarantine(
+        &self,
+        delivery: &Delivery,
+        queue_name: &str,
+        error: &RabbitMQError,
+    ) -> Result<(), RabbitMQError> {
+        let original_headers = delivery.properties.headers().clone().unwrap_or_default();
+        let mut headers = original_headers.clone();
+        headers.insert(
+            QUARANTINE_ERROR_HEADER.into(),
+            AMQPValue::LongString(error.to_string().into()),
+        );
+        headers.insert(
+            QUARANTINE_SOURCE_QUEUE_HEADER.into(),
+            AMQPValue::LongString(queue_name.into()),
+        );
+        headers.insert(
+            QUARANTINE_MICROSERVICE_HEADER.into(),
+            AMQPValue::LongString(self.microservice.as_ref().into()),
+        );
+        headers.insert(
+            QUARANTINED_AT_HEADER.into(),
+            AMQPValue::LongLongInt(now_millis() as i64),
+        );
+
+        let mut properties = BasicProperties::default()
+            .with_headers(headers)
+            .with_delivery_mode(2); // persistent
+        if let Some(content_type) = delivery.properties.content_type() {
+            properties = properties.with_content_type(content_type.clone());
+        }
+        if let Some(content_encoding) = delivery.properties.content_encoding() {
+            properties = properties.with_content_encoding(content_encoding.clone());
+        }
+        if let Some(app_id) = delivery.properties.app_id() {
+            properties = properties.with_app_id(app_id.clone());
+        }
+        if let Some(message_id) = delivery.properties.message_id() {
+            properties = properties.with_message_id(message_id.clone());
+        }
+
+        // A channel of its own in confirm mode: the original is acked only once the broker
+        // took the copy, and a missing quarantine queue returns it instead of dropping it
+        let channel = self
+            .current_connection()
+            .await?
+            .read()
+            .await
+            .create_channel()
+            .await?;
+        let published = async {
+            channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await?;
+            let confirmation = channel
+                .basic_publish(
+                    "",
+                    Queue::QUARANTINE,
+                    BasicPublishOptions {
+                        mandatory: true,
+                        ..BasicPublishOptions::default()
+                    },
+                    &delivery.data,
+                    properties,
+                )
+                .await?
+                .await?;
+            if confirmation.is_nack() {
+                return Err(RabbitMQError::QuarantineError(format!(
+                    "the broker did not take the message into {}",
+                    Queue::QUARANTINE
+                )));
+            }
+            if let Some(returned) = confirmation.take_message() {
+                return Err(RabbitMQError::QuarantineError(format!(
+                    "{} is missing: {}",
+                    Queue::QUARANTINE,
+                    returned.reply_text
+                )));
+            }
+            Ok::<_, RabbitMQError>(())
+        }
+        .await;
+        let _ = channel.close(200, "OK").await;
+        published?;
+
+        delivery.ack(BasicAckOptions::default()).await?;
+        warn!(
+            "Undecodable message from {} moved to {}: {}",
+            queue_name,
+            Queue::QUARANTINE,
+            error
+        );
+
+        let rejected_event = original_headers
+            .inner()
+            .values()
+            .find_map(|value| match value {
+                AMQPValue::LongString(s) => MicroserviceEvent::from_str(&s.to_string()).ok(),
+                _ => None,
+            })
+            .map(|event| event.as_ref().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let audit_payload = AuditDeadLetterPayload {
+            publisher_microservice: delivery
+                .properties
+                .app_id()
+                .as_ref()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            rejector_microservice: self.microservice.as_ref().to_string(),
+            rejected_event,
+            rejected_at: now_millis(),
+            queue_name: queue_name.to_string(),
+            rejection_reason: DECODE_ERROR_REASON.to_string(),
+            retry_count: None,
+            retry_strategy: None,
+            event_id: delivery
+                .properties
+                .message_id()
+                .as_ref()
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| Uuid::now_v7().to_string()),
+        };
+
+        spawn_audit_event(audit_payload, operation_from_headers(&original_headers));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_quarantine {
+    use super::*;
+    use crate::connection::AvailableMicroservices;
+    use crate::events::MicroserviceEvent::AuthDeletedUser;
+    use crate::queue_consumer_props::Exchange;
+    use crate::test::setup::{Config, TestSetup};
+    use futures_lite::StreamExt;
+    use lapin::options::BasicConsumeOptions;
+    use lapin::types::FieldTable;
+    use std::time::Duration;
+
+    /// Integration test, a body that is not JSON ends in the quarantine queue untouched
+    #[test]
+    fn undecodable_event_is_quarantined() {
+        let setup = TestSetup::new(Some(Config {
+            events: &[AuthDeletedUser],
+            microservice: AvailableMicroservices::Auth,
+        }));
+        setup.rt.block_on(async {
+            let _emitter = setup
+                .client
+                .connect_to_events()
+                .await
+                .expect("Failed to connect to events");
+
+            let mut headers = FieldTable::default();
+            headers.insert(
+                AuthDeletedUser.as_ref().to_uppercase().into(),
+                AMQPValue::LongString(AuthDeletedUser.as_ref().into()),
+            );
+            headers.insert("all-micro".into(), AMQPValue::LongString("yes".into()));
+
+            let channel = setup.client.events_channel.lock().await.clone();
+            channel
+                .basic_publish(
+                    Exchange::MATCHING,
+                    "",
+                    BasicPublishOptions::default(),
+                    b"not json",
+                    BasicProperties::default()
+                        .with_headers(headers)
+                        .with_app_id("auth".into()),
+                )
+                .await
+                .expect("Failed to publish");
+
+            let mut consumer = channel
+                .basic_consume(
+                    Queue::QUARANTINE,
+                    "quarantine_consumer",
+                    BasicConsumeOptions {
+                        no_ack: true,
+                        ..Default::default()
+                    },
+                    FieldTable::default(),
+                )
+                .await
+                .expect("Failed to consume");
+
+            let delivery = tokio::time::timeout(Duration::from_secs(5), consumer.next())
+                .await
+                .expect("Timed out waiting for the quarantined message")
+                .expect("Failed to receive message")
+                .expect("Error in received message");
+
+            assert_eq!(delivery.data, b"not json");
+            let headers = delivery.properties.headers().clone().unwrap_or_default();
+            assert_eq!(
+                headers.inner().get(QUARANTINE_SOURCE_QUEUE_HEADER),
+                Some(&AMQPValue::LongString("auth_match_commands".into()))
+            );
+            assert!(headers.inner().contains_key(QUARANTINE_ERROR_HEADER));
+            assert!(headers.inner().contains_key("all-micro"));
+        });
+    }
+
+    #[test]
+    fn decode_errors() {
+        let serde_error = serde_json::from_slice::<serde_json::Value>(b"not json").unwrap_err();
+        assert!(RabbitMQError::SerializationError(serde_error).is_decode_error());
+        assert!(RabbitMQError::InvalidHeader.is_decode_error());
+        assert!(RabbitMQError::InvalidEventKey("nope".to_string()).is_decode_error());
+        assert!(RabbitMQError::InvalidPayload("empty userId".to_string()).is_decode_error());
+
+        assert!(!RabbitMQError::ChannelClosed.is_decode_error());
+        assert!(!RabbitMQError::TimeoutError.is_decode_error());
+    }
+}
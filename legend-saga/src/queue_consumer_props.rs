@@ -14,6 +14,10 @@ impl Queue {
     pub const AUDIT_PROCESSED_COMMANDS: &'static str = "audit_processed_commands";
     /// Queue for audit.dead_letter events
     pub const AUDIT_DEAD_LETTER_COMMANDS: &'static str = "audit_dead_letter_commands";
+    /// Queue for the audit.saga_step_* events (received, succeeded, retried, exhausted)
+    pub const AUDIT_SAGA_STEP_COMMANDS: &'static str = "audit_saga_step_commands";
+    /// Queue holding the deliveries that could not be decoded, with the error and the source queue in the headers
+    pub const QUARANTINE: &'static str = "quarantine_commands";
 }
 
 /// Represents the names of exchanges, which act as message routing hubs in the RabbitMQ context.
@@ -0,0 +1,154 @@
+//! The payload of a delivery, kept as received and parsed on demand. Handlers that parse it
+//! into their own type read the JSON text once, straight into that type, and the map of
+//! [`RawPayload::as_map`] is only built for the handlers that ask for it.
+
+use crate::events::{AnyEvent, MicroserviceEvent};
+use once_cell::sync::OnceCell;
+use serde::de::{DeserializeOwned, Error as _, Unexpected};
+use serde::{Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A JSON object payload, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct RawPayload(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    body: Body,
+    map: OnceCell<HashMap<String, Value>>,
+}
+
+#[derive(Debug)]
+enum Body {
+    Json(Box<RawValue>),
+    /// Payloads of the binary formats, and the upcast ones, already decoded.
+    Value(Value),
+}
+
+impl RawPayload {
+    /// Checks that `data` is a JSON object without parsing its values.
+    pub fn from_json(data: &[u8]) -> Result<Self, serde_json::Error> {
+        Self::from_raw(serde_json::from_slice(data)?)
+    }
+
+    /// Like [`RawPayload::from_json`], for a value already split out of a larger document.
+    pub fn from_raw(raw: Box<RawValue>) -> Result<Self, serde_json::Error> {
+        if !raw.get().starts_with('{') {
+            return Err(not_an_object(Unexpected::Other(raw.get())));
+        }
+        Ok(Self::new(Body::Json(raw), OnceCell::new()))
+    }
+
+    pub(crate) fn from_value(value: Value) -> Result<Self, serde_json::Error> {
+        if !value.is_object() {
+            return Err(not_an_object(Unexpected::Other(&value.to_string())));
+        }
+        Ok(Self::new(Body::Value(value), OnceCell::new()))
+    }
+
+    pub(crate) fn from_map(map: HashMap<String, Value>) -> Self {
+        let value = Value::Object(map.clone().into_iter().collect());
+        Self::new(Body::Value(value), OnceCell::with_value(map))
+    }
+
+    fn new(body: Body, map: OnceCell<HashMap<String, Value>>) -> Self {
+        Self(Arc::new(Inner { body, map }))
+    }
+
+    /// Deserializes the payload into `T`.
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        match &self.0.body {
+            Body::Json(raw) => serde_json::from_str(raw.get()),
+            Body::Value(value) => T::deserialize(value),
+        }
+    }
+
+    /// The payload as the payload of `event`.
+    pub fn typed(&self, event: MicroserviceEvent) -> Result<AnyEvent, serde_json::Error> {
+        match &self.0.body {
+            Body::Json(raw) => {
+                let mut deserializer = serde_json::Deserializer::from_str(raw.get());
+                let payload = AnyEvent::deserialize(event, &mut deserializer)?;
+                deserializer.end()?;
+                Ok(payload)
+            }
+            Body::Value(value) => AnyEvent::deserialize(event, value),
+        }
+    }
+
+    /// The payload as a map, parsed the first time it is asked for.
+    pub fn as_map(&self) -> &HashMap<String, Value> {
+        self.0.map.get_or_init(|| {
+            self.parse()
+                .expect("the payload was checked to be a JSON object")
+        })
+    }
+}
+
+/// Serializes the payload as received, e.g. forwarding the JSON text of a saga step as is.
+impl Serialize for RawPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0.body {
+            Body::Json(raw) => raw.serialize(serializer),
+            Body::Value(value) => value.serialize(serializer),
+        }
+    }
+}
+
+fn not_an_object(unexpected: Unexpected<'_>) -> serde_json::Error {
+    serde_json::Error::invalid_type(unexpected, &"a JSON object")
+}
+
+#[cfg(test)]
+mod test_raw_payload {
+    use super::*;
+    use crate::events::{AuthDeletedUserPayload, MicroserviceEvent};
+    use serde_json::json;
+
+    const BODY: &[u8] = br#" {"userId": "user1", "extra": [1, 2]} "#;
+
+    #[test]
+    fn parses_straight_from_the_json() {
+        let payload = RawPayload::from_json(BODY).unwrap();
+        assert!(payload.0.map.get().is_none());
+        let parsed: AuthDeletedUserPayload = payload.parse().unwrap();
+        assert_eq!(parsed.user_id, "user1");
+        assert!(matches!(
+            payload.typed(MicroserviceEvent::AuthDeletedUser).unwrap(),
+            AnyEvent::AuthDeletedUser(p) if p.user_id == "user1"
+        ));
+        // The map is only built when asked for
+        assert!(payload.0.map.get().is_none());
+        assert_eq!(payload.as_map()["extra"], json!([1, 2]));
+        assert!(payload.0.map.get().is_some());
+        assert_eq!(
+            serde_json::to_string(&payload).unwrap(),
+            r#"{"userId": "user1", "extra": [1, 2]}"#
+        );
+    }
+
+    #[test]
+    fn only_objects_are_payloads() {
+        assert!(RawPayload::from_json(b"[1, 2]").is_err());
+        assert!(RawPayload::from_json(b"{\"userId\": ").is_err());
+        assert!(RawPayload::from_value(json!("user1")).is_err());
+
+        let decoded = RawPayload::from_value(json!({"userId": "user1"})).unwrap();
+        let parsed: AuthDeletedUserPayload = decoded.parse().unwrap();
+        assert_eq!(parsed.user_id, "user1");
+        assert_eq!(decoded.as_map()["userId"], json!("user1"));
+    }
+
+    #[test]
+    fn maps_parse_without_a_json_round_trip() {
+        let map = HashMap::from([("userId".to_string(), json!("user1"))]);
+        let upcast = RawPayload::from_map(map);
+        assert_eq!(
+            upcast.parse::<AuthDeletedUserPayload>().unwrap().user_id,
+            "user1"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Names declared by the services themselves: step commands, saga titles and microservices
+//! that are not built into this crate. They are registered once at startup and then used
+//! like the built-in variants, through the `Custom` variant of [`StepCommand`],
+//! [`SagaTitle`] and [`AvailableMicroservices`], with the same wire format.
+
+use crate::commence_saga::SagaTitle;
+use crate::connection::AvailableMicroservices;
+use crate::saga::StepCommand;
+use once_cell::sync::Lazy;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    #[error("{kind} {name:?} is built into legend-saga")]
+    BuiltIn { kind: &'static str, name: String },
+    #[error("invalid {kind} name {name:?}")]
+    InvalidName { kind: &'static str, name: String },
+    #[error("step command {name:?} is already registered for {microservice}")]
+    CommandConflict { name: String, microservice: String },
+}
+
+/// A name added to the registry. It can only be obtained by registering it, so every
+/// `Custom` value is known to the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisteredName(&'static str);
+
+impl RegisteredName {
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl AsRef<str> for RegisteredName {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl fmt::Display for RegisteredName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Serialize for RegisteredName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    microservices: HashSet<&'static str>,
+    commands: HashMap<&'static str, AvailableMicroservices>,
+    titles: HashSet<&'static str>,
+}
+
+impl Registry {
+    fn microservice(&self, name: &str) -> Option<RegisteredName> {
+        self.microservices.get(name).copied().map(RegisteredName)
+    }
+
+    fn command(&self, name: &str) -> Option<RegisteredName> {
+        let (name, _) = self.commands.get_key_value(name)?;
+        Some(RegisteredName(name))
+    }
+
+    fn title(&self, name: &str) -> Option<RegisteredName> {
+        self.titles.get(name).copied().map(RegisteredName)
+    }
+}
+
+static REGISTRY: Lazy<RwLock<Registry>> = Lazy::new(Default::default);
+
+fn check_name<T: FromStr>(kind: &'static str, name: &str) -> Result<(), RegistryError> {
+    if name.is_empty() || name.trim() != name {
+        return Err(RegistryError::InvalidName {
+            kind,
+            name: name.to_string(),
+        });
+    }
+    if T::from_str(name).is_ok() {
+        return Err(RegistryError::BuiltIn {
+            kind,
+            name: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Declares a microservice that is not built into the crate. Registering a name twice
+/// returns the same value.
+pub fn register_microservice(name: &'static str) -> Result<AvailableMicroservices, RegistryError> {
+    check_name::<AvailableMicroservices>("microservice", name)?;
+    REGISTRY.write().unwrap().microservices.insert(name);
+    Ok(AvailableMicroservices::Custom(RegisteredName(name)))
+}
+
+/// Declares a step command handled by `microservice`, e.g.
+/// `register_step_command("crypto_reward:refund", AvailableMicroservices::Blockchain)`.
+/// Registering it again for another microservice fails.
+pub fn register_step_command(
+    name: &'static str,
+    microservice: AvailableMicroservices,
+) -> Result<StepCommand, RegistryError> {
+    check_name::<StepCommand>("step command", name)?;
+    let mut registry = REGISTRY.write().unwrap();
+    let handler = registry
+        .commands
+        .entry(name)
+        .or_insert_with(|| microservice.clone());
+    if *handler != microservice {
+        return Err(RegistryError::CommandConflict {
+            name: name.to_string(),
+            microservice: handler.as_ref().to_string(),
+        });
+    }
+    Ok(StepCommand::Custom(RegisteredName(name)))
+}
+
+/// Declares a saga title. Registering a name twice returns the same value.
+pub fn register_saga_title(name: &'static str) -> Result<SagaTitle, RegistryError> {
+    check_name::<SagaTitle>("saga title", name)?;
+    REGISTRY.write().unwrap().titles.insert(name);
+    Ok(SagaTitle::Custom(RegisteredName(name)))
+}
+
+/// The built-in or registered microservice called `name`.
+pub fn microservice(name: &str) -> Option<AvailableMicroservices> {
+    AvailableMicroservices::from_str(name).ok().or_else(|| {
+        let name = REGISTRY.read().unwrap().microservice(name)?;
+        Some(AvailableMicroservices::Custom(name))
+    })
+}
+
+/// The built-in or registered step command called `name`.
+pub fn step_command(name: &str) -> Option<StepCommand> {
+    StepCommand::from_str(name).ok().or_else(|| {
+        let name = REGISTRY.read().unwrap().command(name)?;
+        Some(StepCommand::Custom(name))
+    })
+}
+
+/// The built-in or registered saga title called `name`.
+pub fn saga_title(name: &str) -> Option<SagaTitle> {
+    SagaTitle::from_str(name).ok().or_else(|| {
+        let name = REGISTRY.read().unwrap().title(name)?;
+        Some(SagaTitle::Custom(name))
+    })
+}
+
+pub(crate) fn command_microservice(name: &RegisteredName) -> AvailableMicroservices {
+    REGISTRY
+        .read()
+        .unwrap()
+        .commands
+        .get(name.0)
+        .cloned()
+        .expect("registered commands have a microservice")
+}
+
+// The serde derives try the built-in variants first, these resolve the `Custom` ones
+
+fn deserialize_registered<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    kind: &str,
+    lookup: fn(&Registry, &str) -> Option<RegisteredName>,
+) -> Result<RegisteredName, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    lookup(&REGISTRY.read().unwrap(), &name)
+        .ok_or_else(|| D::Error::custom(format!("unknown {kind} {name:?}")))
+}
+
+pub(crate) fn deserialize_microservice<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<RegisteredName, D::Error> {
+    deserialize_registered(deserializer, "microservice", Registry::microservice)
+}
+
+pub(crate) fn deserialize_step_command<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<RegisteredName, D::Error> {
+    deserialize_registered(deserializer, "step command", Registry::command)
+}
+
+pub(crate) fn deserialize_saga_title<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<RegisteredName, D::Error> {
+    deserialize_registered(deserializer, "saga title", Registry::title)
+}
+
+#[cfg(test)]
+mod test_registry {
+    use super::*;
+    use serde_json::json;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn built_in_names_match_the_wire_format() {
+        for command in StepCommand::iter() {
+            assert_eq!(
+                serde_json::to_value(&command).unwrap(),
+                json!(command.as_ref())
+            );
+        }
+        for title in SagaTitle::iter() {
+            assert_eq!(serde_json::to_value(title).unwrap(), json!(title.as_ref()));
+        }
+        for microservice in AvailableMicroservices::iter() {
+            assert_eq!(
+                serde_json::to_value(&microservice).unwrap(),
+                json!(microservice.as_ref())
+            );
+        }
+        assert_eq!(
+            StepCommand::TransferRewardToWinners.as_ref(),
+            "crypto_reward:transfer_reward_to_winners"
+        );
+    }
+
+    #[test]
+    fn registered_names_travel_like_built_in_ones() {
+        let payments = register_microservice("payments").unwrap();
+        let refund = register_step_command("payments:refund", payments.clone()).unwrap();
+        let title = register_saga_title("refund_purchase").unwrap();
+
+        assert_eq!(refund.as_ref(), "payments:refund");
+        assert_eq!(refund.microservice(), payments);
+        assert_eq!(
+            serde_json::to_value(&refund).unwrap(),
+            json!("payments:refund")
+        );
+        assert_eq!(
+            serde_json::from_value::<StepCommand>(json!("payments:refund")).unwrap(),
+            refund
+        );
+        assert_eq!(
+            serde_json::from_value::<SagaTitle>(json!("refund_purchase")).unwrap(),
+            title
+        );
+        assert_eq!(
+            serde_json::from_value::<AvailableMicroservices>(json!("payments")).unwrap(),
+            payments
+        );
+        // Built-in names still resolve to their variant
+        assert_eq!(
+            serde_json::from_value::<StepCommand>(json!("mint_image")).unwrap(),
+            StepCommand::MintImage
+        );
+        assert_eq!(step_command("payments:refund"), Some(refund));
+        assert_eq!(saga_title("refund_purchase"), Some(title));
+        assert_eq!(microservice("auth"), Some(AvailableMicroservices::Auth));
+    }
+
+    #[test]
+    fn unknown_and_conflicting_names_are_rejected() {
+        assert!(serde_json::from_value::<StepCommand>(json!("never:registered")).is_err());
+        assert_eq!(step_command("never:registered"), None);
+
+        assert!(matches!(
+            register_step_command("mint_image", AvailableMicroservices::TestMint),
+            Err(RegistryError::BuiltIn { .. })
+        ));
+        assert!(matches!(
+            register_microservice("auth"),
+            Err(RegistryError::BuiltIn { .. })
+        ));
+        assert!(matches!(
+            register_saga_title(""),
+            Err(RegistryError::InvalidName { .. })
+        ));
+
+        register_step_command("rankings:close", AvailableMicroservices::Rankings).unwrap();
+        // Registering again is fine, for another microservice it is not
+        register_step_command("rankings:close", AvailableMicroservices::Rankings).unwrap();
+        assert!(matches!(
+            register_step_command("rankings:close", AvailableMicroservices::Missions),
+            Err(RegistryError::CommandConflict { .. })
+        ));
+    }
+}
@@ -0,0 +1,458 @@
+use crate::connection::{
+    get_or_init_publish_channel, AvailableMicroservices, RabbitMQClient, RabbitMQError,
+};
+use crate::operation::{operation_from_headers, operation_headers, with_operation};
+use futures_lite::StreamExt;
+use lapin::message::Delivery;
+use lapin::options::{
+    BasicAckOptions, BasicConsumeOptions, BasicPublishOptions, BasicQosOptions,
+    ConfirmSelectOptions, QueueDeclareOptions,
+};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Pseudo-queue of RabbitMQ direct reply-to, the replies skip any real queue.
+const DIRECT_REPLY_TO: &str = "amq.rabbitmq.reply-to";
+const RPC_METHOD_HEADER: &str = "x-rpc-method";
+/// Requests a microservice answers at once, the rest wait in its queue.
+const RPC_PREFETCH: u16 = 32;
+
+/// A request another microservice answers with [`RabbitMQClient::on_request`].
+pub trait RpcRequest: Serialize + DeserializeOwned + Send + 'static {
+    /// Name the handler is registered under in the target microservice.
+    const METHOD: &'static str;
+}
+
+/// Body of a reply: the response of the handler or the error it failed with.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum RpcReply<T> {
+    Ok(T),
+    Error(String),
+}
+
+type PendingReplies = Arc<StdMutex<HashMap<String, oneshot::Sender<Vec<u8>>>>>;
+
+type RpcHandler = Arc<
+    dyn Fn(Vec<u8>) -> Pin<Box<dyn Future<Output = Result<Value, String>> + Send>> + Send + Sync,
+>;
+
+/// Requester side: the channel consuming direct reply-to and the requests waiting for it.
+#[derive(Clone)]
+struct RpcRequester {
+    channel: Channel,
+    pending: PendingReplies,
+}
+
+/// State of the request/reply primitive shared by the clones of a [`RabbitMQClient`].
+#[derive(Clone, Default)]
+pub(crate) struct Rpc {
+    requester: Arc<Mutex<Option<RpcRequester>>>,
+    handlers: Arc<StdMutex<HashMap<&'static str, RpcHandler>>>,
+    serving: Arc<Mutex<bool>>,
+}
+
+pub(crate) fn rpc_queue_name(microservice: &AvailableMicroservices) -> String {
+    format!("{}_rpc_requests", microservice.as_ref())
+}
+
+impl RabbitMQClient {
+    /// Sends `payload` to `target` and waits up to `timeout` for the response of its
+    /// [`RabbitMQClient::on_request`] handler. The operation of the current task travels
+    /// with the request. Fails right away with [`RabbitMQError::RpcError`] when `target`
+    /// has no request queue, i.e. it never called [`RabbitMQClient::on_request`].
+    pub async fn request<Req: RpcRequest, Resp: DeserializeOwned>(
+        &self,
+        target: AvailableMicroservices,
+        payload: &Req,
+        timeout: Duration,
+    ) -> Result<Resp, RabbitMQError> {
+        let requester = self.rpc_requester().await?;
+        let correlation_id = Uuid::now_v7().to_string();
+        let (reply_tx, reply_rx) = oneshot::channel();
+        requester
+            .pending
+            .lock()
+            .unwrap()
+            .insert(correlation_id.clone(), reply_tx);
+
+        let mut headers = operation_headers();
+        headers.insert(
+            RPC_METHOD_HEADER.into(),
+            AMQPValue::LongString(Req::METHOD.into()),
+        );
+        let published = async {
+            let body = serde_json::to_vec(payload)?;
+            let queue_name = rpc_queue_name(&target);
+            let confirmation = requester
+                .channel
+                .basic_publish(
+                    "",
+                    &queue_name,
+                    BasicPublishOptions {
+                        mandatory: true,
+                        ..BasicPublishOptions::default()
+                    },
+                    &body,
+                    BasicProperties::default()
+                        .with_headers(headers)
+                        .with_content_type("application/json".into())
+                        .with_reply_to(DIRECT_REPLY_TO.into())
+                        .with_correlation_id(correlation_id.clone().into())
+                        // Nobody waits for the reply after the timeout
+                        .with_expiration(timeout.as_millis().to_string().into()),
+                )
+                .await?
+                .await?;
+            if let Some(returned) = confirmation.take_message() {
+                return Err(RabbitMQError::RpcError(format!(
+                    "{} does not answer requests, {queue_name} is missing: {}",
+                    target.as_ref(),
+                    returned.reply_text
+                )));
+            }
+            Ok::<_, RabbitMQError>(())
+        };
+        if let Err(e) = published.await {
+            requester.pending.lock().unwrap().remove(&correlation_id);
+            return Err(e);
+        }
+
+        let body = match tokio::time::timeout(timeout, reply_rx).await {
+            Ok(Ok(body)) => body,
+            // The reply consumer stopped, i.e. the channel was closed
+            Ok(Err(_)) => return Err(RabbitMQError::ChannelClosed),
+            Err(_) => {
+                requester.pending.lock().unwrap().remove(&correlation_id);
+                return Err(RabbitMQError::TimeoutError);
+            }
+        };
+        match serde_json::from_slice::<RpcReply<Resp>>(&body)? {
+            RpcReply::Ok(response) => Ok(response),
+            RpcReply::Error(reason) => Err(RabbitMQError::RpcError(reason)),
+        }
+    }
+
+    /// Answers the requests of type `Req` sent to this microservice. The handler runs
+    /// inside the operation of the requester and its error is returned to it as
+    /// [`RabbitMQError::RpcError`].
+    pub async fn on_request<Req, Resp, E, F, Fut>(&self, handler: F) -> Result<(), RabbitMQError>
+    where
+        Req: RpcRequest,
+        Resp: Serialize,
+        E: Display,
+        F: Fn(Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Resp, E>> + Send + 'static,
+    {
+        let handler: RpcHandler = Arc::new(move |body: Vec<u8>| {
+            let response = serde_json::from_slice::<Req>(&body).map(&handler);
+            Box::pin(async move {
+                match response {
+                    Ok(response) => {
+                        let response = response.await.map_err(|e| e.to_string())?;
+                        serde_json::to_value(response).map_err(|e| e.to_string())
+                    }
+                    Err(e) => Err(format!("invalid {} request: {e}", Req::METHOD)),
+                }
+            })
+        });
+        self.rpc
+            .handlers
+            .lock()
+            .unwrap()
+            .insert(Req::METHOD, handler);
+        self.start_serving_requests().await
+    }
+
+    /// Forgets the reply channel and consumes the requests again after a reconnection.
+    pub(crate) async fn reconnect_rpc(&self) -> Result<(), RabbitMQError> {
+        self.rpc.requester.lock().await.take();
+        let mut serving = self.rpc.serving.lock().await;
+        if !*serving {
+            return Ok(());
+        }
+        *serving = false;
+        drop(serving);
+        self.start_serving_requests().await
+    }
+
+    async fn rpc_requester(&self) -> Result<RpcRequester, RabbitMQError> {
+        let mut requester = self.rpc.requester.lock().await;
+        if let Some(current) = requester.as_ref() {
+            if current.channel.status().connected() {
+                return Ok(current.clone());
+            }
+        }
+
+        let channel = self
+            .current_connection()
+            .await?
+            .read()
+            .await
+            .create_channel()
+            .await?;
+        // The confirms carry back the requests no queue could take
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await?;
+        // Direct reply-to requires consuming in no-ack mode on the publishing channel
+        let mut consumer = channel
+            .basic_consume(
+                DIRECT_REPLY_TO,
+                &format!("{}_rpc_replies_consumer", self.microservice.as_ref()),
+                BasicConsumeOptions {
+                    no_ack: true,
+                    ..BasicConsumeOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        let pending = PendingReplies::default();
+        tokio::spawn({
+            let pending = pending.clone();
+            async move {
+                while let Some(Ok(delivery)) = consumer.next().await {
+                    let Some(correlation_id) = delivery.properties.correlation_id() else {
+                        continue;
+                    };
+                    let sender = pending.lock().unwrap().remove(correlation_id.as_str());
+                    match sender {
+                        Some(sender) => {
+                            let _ = sender.send(delivery.data);
+                        }
+                        None => warn!("Discarding the late reply {}", correlation_id),
+                    }
+                }
+                // Fail the requests still waiting instead of letting them time out
+                pending.lock().unwrap().clear();
+            }
+        });
+
+        let current = RpcRequester { channel, pending };
+        *requester = Some(current.clone());
+        Ok(current)
+    }
+
+    async fn start_serving_requests(&self) -> Result<(), RabbitMQError> {
+        let mut serving = self.rpc.serving.lock().await;
+        if *serving {
+            return Ok(());
+        }
+
+        let queue_name = rpc_queue_name(&self.microservice);
+        let channel = self
+            .current_connection()
+            .await?
+            .read()
+            .await
+            .create_channel()
+            .await?;
+        channel
+            .queue_declare(
+                &queue_name,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        // Every request is answered in its own task, acked once answered
+        channel
+            .basic_qos(RPC_PREFETCH, BasicQosOptions::default())
+            .await?;
+        let mut consumer = channel
+            .basic_consume(
+                &queue_name,
+                &format!("{queue_name}_consumer"),
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        *serving = true;
+
+        let client = self.clone();
+        tokio::spawn(async move {
+            // The channel lives as long as its consumer
+            let _channel = channel;
+            while let Some(delivery) = consumer.next().await {
+                match delivery {
+                    Ok(delivery) => {
+                        let client = client.clone();
+                        tokio::spawn(async move { client.answer_request(delivery).await });
+                    }
+                    Err(e) => {
+                        error!("Error consuming {}: {:?}", queue_name, e);
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn answer_request(&self, delivery: Delivery) {
+        let headers = delivery.properties.headers().clone().unwrap_or_default();
+        let method = match headers.inner().get(RPC_METHOD_HEADER) {
+            Some(AMQPValue::LongString(method)) => method.to_string(),
+            _ => String::new(),
+        };
+        let handler = self
+            .rpc
+            .handlers
+            .lock()
+            .unwrap()
+            .get(method.as_str())
+            .cloned();
+
+        with_operation(operation_from_headers(&headers), async {
+            let reply = match handler {
+                Some(handler) => match handler(delivery.data.clone()).await {
+                    Ok(response) => RpcReply::Ok(response),
+                    Err(reason) => RpcReply::Error(reason),
+                },
+                None => RpcReply::Error(format!("no handler for {method:?}")),
+            };
+            if let Err(e) = reply_to_request(&delivery, &reply).await {
+                error!("Error replying to {}: {:?}", method, e);
+            }
+        })
+        .await;
+
+        // The request is answered, or it cannot be: retrying would not help
+        let _ = delivery.ack(BasicAckOptions::default()).await;
+    }
+}
+
+async fn reply_to_request(
+    delivery: &Delivery,
+    reply: &RpcReply<Value>,
+) -> Result<(), RabbitMQError> {
+    let Some(reply_to) = delivery.properties.reply_to() else {
+        return Ok(());
+    };
+    let mut properties = BasicProperties::default()
+        .with_headers(operation_headers())
+        .with_content_type("application/json".into());
+    if let Some(correlation_id) = delivery.properties.correlation_id() {
+        properties = properties.with_correlation_id(correlation_id.clone());
+    }
+
+    let body = serde_json::to_vec(reply)?;
+    let channel = get_or_init_publish_channel().await?;
+    channel
+        .lock()
+        .await
+        .basic_publish(
+            "",
+            reply_to.as_str(),
+            BasicPublishOptions::default(),
+            &body,
+            properties,
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_rpc {
+    use super::*;
+    use crate::test::setup::TestSetup;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Ping {
+        count: u32,
+    }
+
+    impl RpcRequest for Ping {
+        const METHOD: &'static str = "ping";
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Pong {
+        count: u32,
+    }
+
+    #[test]
+    fn reply_envelope() {
+        assert_eq!(
+            serde_json::to_value(RpcReply::Ok(json!({"count": 1}))).unwrap(),
+            json!({"ok": {"count": 1}})
+        );
+        assert_eq!(
+            serde_json::from_value::<RpcReply<Pong>>(json!({"error": "boom"})).unwrap(),
+            RpcReply::Error("boom".to_string())
+        );
+    }
+
+    #[test]
+    fn rpc_round_trip() {
+        let setup = TestSetup::new(None);
+        setup.rt.block_on(async {
+            let client = &setup.client;
+            client
+                .on_request(|ping: Ping| async move {
+                    if ping.count == 0 {
+                        return Err("count must be positive");
+                    }
+                    Ok(Pong {
+                        count: ping.count + 1,
+                    })
+                })
+                .await
+                .unwrap();
+
+            let target = client.microservice.clone();
+            let pong: Pong = with_operation(Some("op-rpc".to_string()), async {
+                client
+                    .request(target.clone(), &Ping { count: 1 }, Duration::from_secs(5))
+                    .await
+            })
+            .await
+            .unwrap();
+            assert_eq!(pong, Pong { count: 2 });
+
+            let failed = client
+                .request::<Ping, Pong>(target, &Ping { count: 0 }, Duration::from_secs(5))
+                .await;
+            assert!(matches!(failed, Err(RabbitMQError::RpcError(reason)) if reason == "count must be positive"));
+
+            // Without a request queue the request fails before its timeout
+            let target = AvailableMicroservices::LegendGameAnalytics;
+            let started = std::time::Instant::now();
+            let unroutable = client
+                .request::<Ping, Pong>(target.clone(), &Ping { count: 1 }, Duration::from_secs(5))
+                .await;
+            assert!(matches!(unroutable, Err(RabbitMQError::RpcError(_))));
+            assert!(started.elapsed() < Duration::from_secs(5));
+
+            client
+                .declare_queue(
+                    &rpc_queue_name(&target),
+                    QueueDeclareOptions::default(),
+                    FieldTable::default(),
+                )
+                .await
+                .unwrap();
+            let unanswered = client
+                .request::<Ping, Pong>(target, &Ping { count: 1 }, Duration::from_millis(200))
+                .await;
+            assert!(matches!(unanswered, Err(RabbitMQError::TimeoutError)));
+            setup.clean_topology(None).await;
+        });
+    }
+}
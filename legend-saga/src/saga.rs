@@ -1,24 +1,36 @@
+use crate::circuit_breaker::{Admission, CircuitBreakers};
 use crate::emitter::Emitter;
 use crate::my_delivery::MyDelivery;
-use crate::nack::Nack;
+use crate::events::{
+    AuditSagaStepExhaustedPayload, AuditSagaStepReceivedPayload, AuditSagaStepRetriedPayload,
+    AuditSagaStepSucceededPayload,
+};
+use crate::nack::{now_millis, Nack, NackOptions, NackOutcome, RetryHistory};
+use crate::publish_event::spawn_audit_event;
 use crate::operation::{operation_from_headers, report_missing_operation, with_operation};
 use crate::queue_consumer_props::Queue;
+use crate::registry::{command_microservice, RegisteredName};
+use crate::saga_metadata::{SagaMetadata, SagaMetadataError};
 use futures_lite::StreamExt;
-use lapin::options::{
-    BasicAckOptions, BasicConsumeOptions, BasicNackOptions,
-};
+use lapin::options::{BasicAckOptions, BasicConsumeOptions};
 use lapin::types::FieldTable;
 use lapin::Channel;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::time::Duration;
-use strum_macros::{AsRefStr, Display, EnumIter, EnumString};
-use tracing::error;
+use strum::EnumMessage;
+use strum_macros::{AsRefStr, Display, EnumIter, EnumMessage, EnumString};
+use tracing::{error, warn};
 use crate::connection::{AvailableMicroservices, RabbitMQClient, RabbitMQError};
+use crate::payload_format::{delivery_body, PayloadFormat};
+use crate::raw_payload::RawPayload;
+use serde_json::value::RawValue;
 
 #[derive(
-    Debug, Clone, PartialEq, Eq, EnumString, AsRefStr, EnumIter, Serialize, Deserialize, Hash,
+    Debug, Clone, PartialEq, Eq, EnumString, EnumMessage, EnumIter, Serialize, Deserialize, Hash,
 )]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
@@ -49,6 +61,40 @@ pub enum StepCommand {
 
     // Storage commands
     UploadFile,
+
+    /// A command declared by a service, see [`crate::registry::register_step_command`].
+    #[strum(disabled)]
+    #[serde(untagged, deserialize_with = "crate::registry::deserialize_step_command")]
+    Custom(RegisteredName),
+}
+
+impl AsRef<str> for StepCommand {
+    fn as_ref(&self) -> &str {
+        match self {
+            StepCommand::Custom(name) => name.as_str(),
+            // The strum name of a built-in command is also its serde name
+            builtin => builtin.get_serializations()[0],
+        }
+    }
+}
+
+impl StepCommand {
+    /// The microservice that handles the command.
+    pub fn microservice(&self) -> AvailableMicroservices {
+        match self {
+            StepCommand::CreateImage | StepCommand::UpdateToken => AvailableMicroservices::TestImage,
+            StepCommand::MintImage => AvailableMicroservices::TestMint,
+            StepCommand::CreateUser => AvailableMicroservices::Auth,
+            StepCommand::TransferMissionRewardToWinner | StepCommand::TransferRewardToWinners => {
+                AvailableMicroservices::Blockchain
+            }
+            StepCommand::UpdateUserImage | StepCommand::CreateSocialUser => {
+                AvailableMicroservices::Social
+            }
+            StepCommand::UploadFile => AvailableMicroservices::Storage,
+            StepCommand::Custom(name) => command_microservice(name),
+        }
+    }
 }
 
 #[derive(
@@ -56,32 +102,89 @@ pub enum StepCommand {
 )]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
-enum Status {
+pub enum Status {
     Success,
     Failure,
+    /// The step was received and is being worked on.
     Sent,
+    /// The step waits for something external, e.g. a transaction to be confirmed.
     Pending,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A step of a saga. The orchestrator keeps the previous payload as a map, participants as
+/// the [`RawPayload`] they received.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-struct SagaStep {
-    microservice: AvailableMicroservices, // Assuming this type exists
-    command: StepCommand,
-    status: Status,
-    saga_id: i32,
-    payload: HashMap<String, Value>,
-    previous_payload: HashMap<String, Value>,
-    is_current_step: bool,
+pub(crate) struct SagaStep<P = HashMap<String, Value>> {
+    pub(crate) microservice: AvailableMicroservices, // Assuming this type exists
+    pub(crate) command: StepCommand,
+    pub(crate) status: Status,
+    pub(crate) saga_id: i32,
+    pub(crate) payload: HashMap<String, Value>,
+    pub(crate) previous_payload: P,
+    pub(crate) is_current_step: bool,
+    /// Set when the orchestrator asks to undo the command, the step reaches the
+    /// compensation handler of the command instead of the regular one.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) is_compensation: bool,
+    /// Why the step failed, sent with `Status::Failure`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) failure_reason: Option<String>,
+    /// What the step is doing, sent with `Status::Sent` and `Status::Pending`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) progress_detail: Option<String>,
+}
+
+impl<P> SagaStep<P> {
+    fn try_map_previous_payload<Q, E>(
+        self,
+        f: impl FnOnce(P) -> Result<Q, E>,
+    ) -> Result<SagaStep<Q>, E> {
+        Ok(SagaStep {
+            microservice: self.microservice,
+            command: self.command,
+            status: self.status,
+            saga_id: self.saga_id,
+            payload: self.payload,
+            previous_payload: f(self.previous_payload)?,
+            is_current_step: self.is_current_step,
+            is_compensation: self.is_compensation,
+            failure_reason: self.failure_reason,
+            progress_detail: self.progress_detail,
+        })
+    }
+}
+
+/// Decodes the step of a delivery like [`crate::payload_format::decode_delivery`], keeping the previous payload of
+/// JSON steps as received.
+fn decode_step(delivery: &lapin::message::Delivery) -> Result<SagaStep<RawPayload>, RabbitMQError> {
+    let (format, data) = delivery_body(delivery)?;
+    match format {
+        PayloadFormat::Json => serde_json::from_slice::<SagaStep<Box<RawValue>>>(&data)?
+            .try_map_previous_payload(RawPayload::from_raw)
+            .map_err(RabbitMQError::from),
+        #[allow(unreachable_patterns)]
+        format => format
+            .decode::<SagaStep<Value>>(&data)?
+            .try_map_previous_payload(RawPayload::from_value)
+            .map_err(RabbitMQError::from),
+    }
 }
 
+/// Retry strategy of audit.saga_step_exhausted when the handler failed the step.
+const FAIL_STRATEGY: &str = "fail";
+
+/// Failure reason of the compensation steps of commands without a compensation handler.
+pub(crate) const NO_COMPENSATION_HANDLER: &str = "no compensation handler";
+
 #[derive(Clone)]
 pub struct CommandHandler {
     channel: MicroserviceConsumeChannel,
-    payload: HashMap<String, Value>,
-    #[allow(dead_code)]
+    payload: RawPayload,
+    metadata: SagaMetadata,
     saga_id: i32,
     operation_id: Option<String>,
+    circuit_breakers: CircuitBreakers,
 }
 
 impl CommandHandler {
@@ -89,12 +192,29 @@ impl CommandHandler {
     where
         T: for<'de> Deserialize<'de>,
     {
-        let json_value = serde_json::to_value(self.payload.clone())?;
-        serde_json::from_value(json_value)
+        self.payload.parse()
     }
 
+    /// The payload as a map, built the first time it is asked for. Prefer
+    /// [`CommandHandler::parse_payload`], which parses the received JSON straight into `T`.
     pub fn get_payload(&self) -> &HashMap<String, Value> {
-        &self.payload
+        self.payload.as_map()
+    }
+
+    pub fn saga_id(&self) -> i32 {
+        self.saga_id
+    }
+
+    /// Saga metadata under `key`, set by this step or forwarded by the previous ones.
+    pub fn metadata<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, SagaMetadataError> {
+        self.metadata.get(key)
+    }
+
+    /// Sets saga metadata, forwarded to every following step by [`CommandHandler::ack`] and
+    /// [`CommandHandler::fail`]. Keys must not start with `_` and the metadata of the saga
+    /// cannot exceed [`crate::saga_metadata::MAX_METADATA_BYTES`].
+    pub fn set_metadata<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), SagaMetadataError> {
+        self.metadata.set(key, value)
     }
 
     /// The operation (tenant) the saga step belongs to, or `None` while a
@@ -103,27 +223,145 @@ impl CommandHandler {
         &self.operation_id
     }
 
+    /// Whether the orchestrator is asking to undo the command, see
+    /// [`RabbitMQClient::saga_compensations`].
+    pub fn is_compensation(&self) -> bool {
+        self.channel.step.is_compensation
+    }
+
+    /// Retry count, first failure and errors recorded by the previous attempts of the step.
+    pub fn retry_history(&self) -> RetryHistory {
+        RetryHistory::from_headers(&self.channel.delivery.headers)
+    }
+
     pub async fn ack(&self, payload_for_next_step: Value) -> Result<(), RabbitMQError> {
-        self.channel.ack(payload_for_next_step).await
+        self.channel.ack(payload_for_next_step, &self.metadata).await?;
+        self.circuit_breakers
+            .record_success(self.channel.step.command.as_ref());
+
+        let step = &self.channel.step;
+        spawn_audit_event(
+            AuditSagaStepSucceededPayload {
+                saga_id: step.saga_id,
+                command: step.command.as_ref().to_string(),
+                microservice: step.microservice.as_ref().to_string(),
+                operation_id: self.operation_id.clone(),
+                succeeded_at: now_millis(),
+                queue_name: self.channel.queue_name.clone(),
+            },
+            self.operation_id.clone(),
+        );
+        Ok(())
+    }
+
+    /// Replies to the saga with a `Failure` and `reason`, the orchestrator then compensates
+    /// the steps already completed. `payload` must be an object, like in [`CommandHandler::ack`].
+    /// Like a nack with its retries exhausted, the failure counts in the circuit breaker of
+    /// the command and emits audit.saga_step_exhausted.
+    pub async fn fail(&self, reason: impl Display, payload: Value) -> Result<(), RabbitMQError> {
+        let reason = reason.to_string();
+        self.channel
+            .reply(Status::Failure, payload, &self.metadata, Some(reason.clone()))
+            .await?;
+        self.circuit_breakers
+            .record_failure(self.channel.step.command.as_ref());
+        let retry_count = self.retry_history().retry_count;
+        self.audit_exhausted(FAIL_STRATEGY, retry_count, Some(reason));
+        Ok(())
+    }
+
+    /// Tells the orchestrator that the step is still running without acking it, which
+    /// extends the timeout of the step. Only `Status::Sent` and `Status::Pending` are
+    /// progress, the step finishes with [`CommandHandler::ack`] or [`CommandHandler::fail`].
+    pub async fn report_progress(
+        &self,
+        status: Status,
+        detail: impl Display,
+    ) -> Result<(), RabbitMQError> {
+        if matches!(status, Status::Success | Status::Failure) {
+            return Err(RabbitMQError::InvalidPayload(format!(
+                "{status} is not a progress status, use ack or fail"
+            )));
+        }
+        self.channel
+            .report_progress(status, detail.to_string())
+            .await
     }
 
+    /// Rejects the step and requeues it as `options` says until its retries run out. The
+    /// failure counts in the circuit breaker of the command and emits
+    /// audit.saga_step_retried, or audit.saga_step_exhausted once the step is not requeued.
+    pub async fn nack(&self, options: NackOptions) -> Result<NackOutcome, RabbitMQError> {
+        let outcome = self.channel.nack.nack(&options).await?;
+        self.on_nack(options.strategy.name(), options.error, outcome);
+        Ok(outcome)
+    }
+
+    /// [`CommandHandler::nack`] with [`NackOptions::delay`].
     pub async fn nack_with_delay(
         &self,
         delay: Duration,
         max_retries: i32,
     ) -> Result<(i32, Duration), RabbitMQError> {
-        self.channel.nack.with_delay(delay, max_retries).await
+        self.nack(NackOptions::delay(delay, max_retries))
+            .await
+            .map(NackOutcome::delay_result)
     }
 
+    /// [`CommandHandler::nack`] with [`NackOptions::fibonacci`].
     pub async fn nack_with_fibonacci_strategy(
         &self,
         max_occurrence: i32,
         max_retries: i32,
     ) -> Result<(i32, Duration, i32), RabbitMQError> {
-        self.channel
-            .nack
-            .with_fibonacci_strategy(max_occurrence, max_retries)
+        self.nack(NackOptions::fibonacci(max_occurrence, max_retries))
             .await
+            .map(NackOutcome::fibonacci_result)
+    }
+
+    /// Counts the failure in the circuit breaker of the command and emits
+    /// audit.saga_step_retried, or audit.saga_step_exhausted when the step was not requeued.
+    fn on_nack(&self, retry_strategy: &str, error: Option<String>, outcome: NackOutcome) {
+        let step = &self.channel.step;
+        self.circuit_breakers.record_failure(step.command.as_ref());
+
+        if outcome.exhausted {
+            self.audit_exhausted(retry_strategy, outcome.count as u32, error);
+        } else {
+            spawn_audit_event(
+                AuditSagaStepRetriedPayload {
+                    saga_id: step.saga_id,
+                    command: step.command.as_ref().to_string(),
+                    microservice: step.microservice.as_ref().to_string(),
+                    operation_id: self.operation_id.clone(),
+                    retried_at: now_millis(),
+                    queue_name: self.channel.queue_name.clone(),
+                    retry_count: outcome.count as u32,
+                    retry_strategy: retry_strategy.to_string(),
+                    delay_ms: outcome.delay.as_millis() as u64,
+                    error,
+                },
+                self.operation_id.clone(),
+            );
+        }
+    }
+
+    fn audit_exhausted(&self, retry_strategy: &str, retry_count: u32, error: Option<String>) {
+        let step = &self.channel.step;
+        spawn_audit_event(
+            AuditSagaStepExhaustedPayload {
+                saga_id: step.saga_id,
+                command: step.command.as_ref().to_string(),
+                microservice: step.microservice.as_ref().to_string(),
+                operation_id: self.operation_id.clone(),
+                exhausted_at: now_millis(),
+                queue_name: self.channel.queue_name.clone(),
+                retry_count,
+                retry_strategy: retry_strategy.to_string(),
+                error,
+            },
+            self.operation_id.clone(),
+        );
     }
 }
 
@@ -131,9 +369,8 @@ impl CommandHandler {
 struct MicroserviceConsumeChannel {
     channel: Channel,
     delivery: MyDelivery,
-    #[allow(dead_code)]
     queue_name: String,
-    step: SagaStep,
+    step: SagaStep<RawPayload>,
     nack: Nack,
     operation_id: Option<String>,
 }
@@ -163,7 +400,7 @@ impl RabbitMQClient {
                 Ok(delivery) => {
                     if let Err(e) = self.handle_saga_step(&delivery, &emitter, queue_name).await {
                         error!("Error handling event: {:?}", e);
-                        let _ = delivery.nack(BasicNackOptions::default()).await;
+                        self.reject_delivery(&delivery, queue_name, &e).await;
                     }
                 }
                 Err(e) => {
@@ -180,7 +417,7 @@ impl RabbitMQClient {
         emitter: &Emitter<CommandHandler, StepCommand>,
         queue_name: &str,
     ) -> Result<(), RabbitMQError> {
-        let current_step: SagaStep = serde_json::from_slice(&delivery.data)?;
+        let current_step = decode_step(delivery)?;
         let channel = self.saga_channel.lock().await;
         let delivery = MyDelivery::new(delivery);
 
@@ -201,13 +438,59 @@ impl RabbitMQClient {
             operation_id.clone(),
         );
 
+        let metadata = SagaMetadata::from_payload(previous_payload.clone());
+        if response_channel.step.is_compensation
+            && !self.compensation_emitter.has_handler(&command).await
+        {
+            // Nobody would ack nor reply, and compensations have no timeout: the saga would
+            // stay compensating forever
+            warn!(
+                "No compensation handler for {} of saga {}, replying failure",
+                command.as_ref(),
+                saga_id
+            );
+            return response_channel
+                .reply(
+                    Status::Failure,
+                    Value::Object(Default::default()),
+                    &metadata,
+                    Some(NO_COMPENSATION_HANDLER.to_string()),
+                )
+                .await;
+        }
+
+        if let Admission::Postpone(delay) = self.circuit_breakers.admit(command.as_ref()) {
+            // The breaker of the command is open, the step waits in the requeue queue
+            // without reaching the handler nor counting as a retry
+            return response_channel.nack.postpone(delay).await;
+        }
+
+        spawn_audit_event(
+            AuditSagaStepReceivedPayload {
+                saga_id,
+                command: command.as_ref().to_string(),
+                microservice: response_channel.step.microservice.as_ref().to_string(),
+                operation_id: operation_id.clone(),
+                received_at: now_millis(),
+                queue_name: queue_name.to_string(),
+            },
+            operation_id.clone(),
+        );
+
         let event_handler = CommandHandler {
+            metadata,
             payload: previous_payload,
             channel: response_channel,
             saga_id,
             operation_id: operation_id.clone(),
+            circuit_breakers: self.circuit_breakers.clone(),
         };
 
+        let emitter = if event_handler.is_compensation() {
+            &self.compensation_emitter
+        } else {
+            emitter
+        };
         // Running the handler inside the scope makes the operation propagate to
         // anything it publishes, with no code in the consuming service.
         with_operation(operation_id, emitter.emit(command, event_handler)).await;
@@ -220,7 +503,7 @@ impl MicroserviceConsumeChannel {
         channel: Channel,
         delivery: MyDelivery,
         queue_name: String,
-        step: SagaStep,
+        step: SagaStep<RawPayload>,
         operation_id: Option<String>,
     ) -> Self {
         let nack = Nack::new(channel.clone(), delivery.clone(), queue_name.clone());
@@ -233,18 +516,30 @@ impl MicroserviceConsumeChannel {
             operation_id,
         }
     }
-    async fn ack(&self, payload_for_next_step: Value) -> Result<(), RabbitMQError> {
+    async fn ack(&self, payload_for_next_step: Value, metadata: &SagaMetadata) -> Result<(), RabbitMQError> {
+        self.reply(Status::Success, payload_for_next_step, metadata, None).await
+    }
+
+    /// Sends an intermediate status, the delivery stays unacked until the step finishes.
+    async fn report_progress(&self, status: Status, detail: String) -> Result<(), RabbitMQError> {
         let mut step = self.step.clone();
-        step.status = Status::Success;
+        step.status = status;
+        step.progress_detail = Some(detail);
+        with_operation(self.operation_id.clone(), RabbitMQClient::send(Queue::REPLY_TO_SAGA, &step)).await
+    }
 
-        let mut next_payload = HashMap::new();
+    async fn reply(
+        &self,
+        status: Status,
+        payload_for_next_step: Value,
+        metadata: &SagaMetadata,
+        failure_reason: Option<String>,
+    ) -> Result<(), RabbitMQError> {
+        let mut step = self.step.clone();
+        step.status = status;
+        step.failure_reason = failure_reason;
 
-        // Transfer metadata from previous payload
-        for (key, value) in step.previous_payload.iter() {
-            if key.len() > 2 && key.starts_with("__") {
-                next_payload.insert(key.clone(), value.clone());
-            }
-        }
+        let mut next_payload = HashMap::new();
 
         //  Alternative if  payload_for_next_step: HashMap<String, Value>, u can iter, but it misses object check
         //   for (key, value) in payload_for_next_step.iter() {
@@ -263,6 +558,8 @@ impl MicroserviceConsumeChannel {
             }
         }
 
+        // Forward the saga metadata, see crate::saga_metadata
+        metadata.apply_to(&mut next_payload)?;
         step.payload = next_payload;
 
         // Para que este micro pueda realizar pasos del saga y realizar commence_saga ops las queue's deben existir, no es responsabilidad
@@ -276,3 +573,319 @@ impl MicroserviceConsumeChannel {
             .map_err(RabbitMQError::from)
     }
 }
+
+#[cfg(test)]
+mod test_saga_step {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn compensation_fields_are_optional_on_the_wire() {
+        let step: SagaStep = serde_json::from_value(json!({
+            "microservice": "test-image",
+            "command": "create_image",
+            "status": "sent",
+            "sagaId": 1,
+            "payload": {},
+            "previousPayload": {"userId": "user1"},
+            "isCurrentStep": true
+        }))
+        .unwrap();
+        assert!(!step.is_compensation);
+        assert_eq!(step.failure_reason, None);
+        assert_eq!(step.progress_detail, None);
+
+        let value = serde_json::to_value(&step).unwrap();
+        assert!(value.get("isCompensation").is_none());
+        assert!(value.get("failureReason").is_none());
+        assert!(value.get("progressDetail").is_none());
+
+        let mut failed = step;
+        failed.status = Status::Failure;
+        failed.is_compensation = true;
+        failed.failure_reason = Some("insufficient funds".to_string());
+        let value = serde_json::to_value(&failed).unwrap();
+        assert_eq!(value["status"], json!("failure"));
+        assert_eq!(value["isCompensation"], json!(true));
+        assert_eq!(value["failureReason"], json!("insufficient funds"));
+    }
+
+    #[test]
+    fn progress_is_sent_with_its_detail() {
+        let mut step: SagaStep = serde_json::from_value(json!({
+            "microservice": "blockchain",
+            "command": "crypto_reward:transfer_mission_reward_to_winner",
+            "status": "sent",
+            "sagaId": 1,
+            "payload": {},
+            "previousPayload": {},
+            "isCurrentStep": true
+        }))
+        .unwrap();
+        step.status = Status::Pending;
+        step.progress_detail = Some("waiting for 3 confirmations".to_string());
+        let value = serde_json::to_value(&step).unwrap();
+        assert_eq!(value["status"], json!("pending"));
+        assert_eq!(value["progressDetail"], json!("waiting for 3 confirmations"));
+    }
+
+    #[test]
+    fn participants_keep_the_previous_payload_as_received() {
+        let body = br#"{"microservice":"test-image","command":"create_image","status":"sent","sagaId":1,"payload":{},"previousPayload":{"userId": "user1", "__trace": {"id": "abc"}},"isCurrentStep":true}"#;
+        let step = serde_json::from_slice::<SagaStep<Box<RawValue>>>(body)
+            .unwrap()
+            .try_map_previous_payload(RawPayload::from_raw)
+            .unwrap();
+        assert_eq!(step.previous_payload.as_map()["userId"], json!("user1"));
+
+        // The reply forwards it untouched and the orchestrator reads it as a map
+        let reply = serde_json::to_string(&step).unwrap();
+        assert!(reply.contains(r#""previousPayload":{"userId": "user1", "__trace": {"id": "abc"}}"#));
+        let reply: SagaStep = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply.previous_payload["__trace"], json!({"id": "abc"}));
+
+        let not_an_object = br#"{"microservice":"test-image","command":"create_image","status":"sent","sagaId":1,"payload":{},"previousPayload":[],"isCurrentStep":true}"#;
+        assert!(serde_json::from_slice::<SagaStep<Box<RawValue>>>(not_an_object)
+            .unwrap()
+            .try_map_previous_payload(RawPayload::from_raw)
+            .is_err());
+    }
+
+    /// Integration tests, the steps are sent to the saga queue like the orchestrator does and
+    /// the replies are read from reply_to_saga
+    mod integration {
+        use super::*;
+        use crate::circuit_breaker::{CircuitBreakerConfig, CircuitState};
+        use crate::events::MicroserviceEvent;
+        use crate::nack::NackOptions;
+        use crate::test::setup::{Config, TestSetup};
+        use futures::{Stream, StreamExt};
+        use lapin::BasicProperties;
+        use std::pin::Pin;
+        use tokio::time::timeout;
+
+        type Replies = Pin<Box<dyn Stream<Item = Result<SagaStep, RabbitMQError>> + Send>>;
+
+        fn step(command: StepCommand, saga_id: i32, is_compensation: bool) -> SagaStep {
+            SagaStep {
+                microservice: AvailableMicroservices::TestImage,
+                command,
+                status: Status::Sent,
+                saga_id,
+                payload: HashMap::new(),
+                previous_payload: HashMap::from([("userId".to_string(), json!("user1"))]),
+                is_current_step: true,
+                is_compensation,
+                failure_reason: None,
+                progress_detail: None,
+            }
+        }
+
+        async fn dispatch(setup: &TestSetup, step: &SagaStep) {
+            setup
+                .client
+                .publish_message(&setup.client.saga_queue_name, step, BasicProperties::default())
+                .await
+                .expect("Failed to send the step");
+        }
+
+        async fn next_reply(replies: &mut Replies) -> SagaStep {
+            timeout(Duration::from_secs(5), replies.next())
+                .await
+                .expect("No reply to the saga")
+                .expect("Reply stream closed")
+                .expect("Failed to decode the reply")
+        }
+
+        #[test]
+        fn steps_reply_to_the_saga() {
+            let setup = TestSetup::new(Some(Config {
+                events: &[],
+                microservice: AvailableMicroservices::TestImage,
+            }));
+            setup.rt.block_on(async {
+                let commands = setup
+                    .client
+                    .connect_to_saga_commands()
+                    .await
+                    .expect("Failed to connect to saga commands");
+                let mut replies: Replies = Box::pin(
+                    setup
+                        .client
+                        .consume_messages::<SagaStep>(Queue::REPLY_TO_SAGA, Default::default())
+                        .await
+                        .expect("Failed to consume reply_to_saga"),
+                );
+
+                commands
+                    .on_with_async_handler(StepCommand::CreateImage, |handler| async move {
+                        panic!("compensation {} reached the command handler", handler.saga_id());
+                    })
+                    .await;
+                setup
+                    .client
+                    .saga_compensations()
+                    .on_with_async_handler(StepCommand::CreateImage, |handler| async move {
+                        assert!(handler.is_compensation());
+                        handler
+                            .ack(json!({"imageDeleted": true}))
+                            .await
+                            .expect("Failed to ack the compensation");
+                    })
+                    .await;
+
+                // Compensations reach the compensation handler of the command
+                dispatch(&setup, &step(StepCommand::CreateImage, 1, true)).await;
+                let reply = next_reply(&mut replies).await;
+                assert_eq!(reply.saga_id, 1);
+                assert_eq!(reply.status, Status::Success);
+                assert!(reply.is_compensation);
+                assert_eq!(reply.payload["imageDeleted"], json!(true));
+
+                // Without a compensation handler the compensation fails instead of hanging
+                dispatch(&setup, &step(StepCommand::UpdateToken, 2, true)).await;
+                let reply = next_reply(&mut replies).await;
+                assert_eq!(reply.saga_id, 2);
+                assert_eq!(reply.status, Status::Failure);
+                assert!(reply.is_compensation);
+                assert_eq!(
+                    reply.failure_reason.as_deref(),
+                    Some(NO_COMPENSATION_HANDLER)
+                );
+
+                // A failed step is replied as such, audited and counted by the breaker
+                setup.client.enable_saga_circuit_breaker(
+                    StepCommand::MintImage,
+                    CircuitBreakerConfig {
+                        failure_threshold: 1,
+                        ..Default::default()
+                    },
+                );
+                let (audits, mut exhausted) = tokio::sync::mpsc::unbounded_channel();
+                setup
+                    .client
+                    .connect_to_audit()
+                    .await
+                    .expect("Failed to connect to audit")
+                    .on_with_async_handler(MicroserviceEvent::AuditSagaStepExhausted, move |handler| {
+                        let audits = audits.clone();
+                        async move {
+                            let payload: AuditSagaStepExhaustedPayload =
+                                handler.parse_payload().expect("Failed to parse the audit");
+                            handler.audit_ack().await.expect("Failed to ack the audit");
+                            let _ = audits.send(payload);
+                        }
+                    })
+                    .await;
+                commands
+                    .on_with_async_handler(StepCommand::MintImage, |handler| async move {
+                        handler
+                            .fail("insufficient funds", json!({}))
+                            .await
+                            .expect("Failed to fail the step");
+                    })
+                    .await;
+
+                dispatch(&setup, &step(StepCommand::MintImage, 3, false)).await;
+                let reply = next_reply(&mut replies).await;
+                assert_eq!(reply.saga_id, 3);
+                assert_eq!(reply.status, Status::Failure);
+                assert!(!reply.is_compensation);
+                assert_eq!(reply.failure_reason.as_deref(), Some("insufficient funds"));
+
+                let audit = timeout(Duration::from_secs(5), async {
+                    loop {
+                        let audit = exhausted.recv().await.expect("Audit handler dropped");
+                        if audit.saga_id == 3 {
+                            return audit;
+                        }
+                    }
+                })
+                .await
+                .expect("No audit.saga_step_exhausted");
+                assert_eq!(audit.command, "mint_image");
+                assert_eq!(audit.retry_strategy, FAIL_STRATEGY);
+                assert_eq!(audit.error.as_deref(), Some("insufficient funds"));
+                assert_eq!(
+                    setup.client.circuit_breakers()["mint_image"].state,
+                    CircuitState::Open
+                );
+            });
+        }
+
+        #[test]
+        fn steps_are_audited() {
+            let setup = TestSetup::new(Some(Config {
+                events: &[],
+                microservice: AvailableMicroservices::TestMint,
+            }));
+            setup.rt.block_on(async {
+                let audit = setup
+                    .client
+                    .connect_to_audit()
+                    .await
+                    .expect("Failed to connect to audit");
+                let (audits, mut received) = tokio::sync::mpsc::unbounded_channel();
+                for event in [
+                    MicroserviceEvent::AuditSagaStepReceived,
+                    MicroserviceEvent::AuditSagaStepSucceeded,
+                    MicroserviceEvent::AuditSagaStepRetried,
+                    MicroserviceEvent::AuditSagaStepExhausted,
+                ] {
+                    let audits = audits.clone();
+                    audit
+                        .on_with_async_handler(event, move |handler| {
+                            let audits = audits.clone();
+                            async move {
+                                let saga_id = handler.get_payload()["saga_id"].as_i64();
+                                handler.audit_ack().await.expect("Failed to ack the audit");
+                                let _ = audits.send((event, saga_id));
+                            }
+                        })
+                        .await;
+                }
+
+                setup
+                    .client
+                    .connect_to_saga_commands()
+                    .await
+                    .expect("Failed to connect to saga commands")
+                    .on_with_async_handler(StepCommand::MintImage, |handler| async move {
+                        // Saga 20 succeeds on its second attempt, saga 21 has no retries
+                        if handler.saga_id() == 20 && handler.retry_history().retry_count > 0 {
+                            handler.ack(json!({})).await.expect("Failed to ack the step");
+                            return;
+                        }
+                        let max_retries = if handler.saga_id() == 20 { 1 } else { 0 };
+                        handler
+                            .nack(NackOptions::delay(Duration::from_millis(10), max_retries))
+                            .await
+                            .expect("Failed to nack the step");
+                    })
+                    .await;
+
+                dispatch(&setup, &step(StepCommand::MintImage, 20, false)).await;
+                dispatch(&setup, &step(StepCommand::MintImage, 21, false)).await;
+
+                let mut expected = vec![
+                    (MicroserviceEvent::AuditSagaStepReceived, Some(20)),
+                    (MicroserviceEvent::AuditSagaStepRetried, Some(20)),
+                    (MicroserviceEvent::AuditSagaStepReceived, Some(20)),
+                    (MicroserviceEvent::AuditSagaStepSucceeded, Some(20)),
+                    (MicroserviceEvent::AuditSagaStepReceived, Some(21)),
+                    (MicroserviceEvent::AuditSagaStepExhausted, Some(21)),
+                ];
+                timeout(Duration::from_secs(5), async {
+                    while !expected.is_empty() {
+                        let audit = received.recv().await.expect("Audit handlers dropped");
+                        if let Some(index) = expected.iter().position(|e| *e == audit) {
+                            expected.remove(index);
+                        }
+                    }
+                })
+                .await
+                .unwrap_or_else(|_| panic!("Missing audits {expected:?}"));
+            });
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::commence_saga::{PayloadCommenceSaga, SagaTitle};
+use crate::connection::AvailableMicroservices;
+use crate::saga::StepCommand;
+use serde::{Serialize, Serializer};
+use std::any::{type_name, TypeId};
+use std::collections::HashSet;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SagaDefinitionError {
+    #[error("saga {0} has no steps")]
+    NoSteps(String),
+    #[error("saga {0} is defined more than once")]
+    Duplicated(String),
+    #[error(
+        "step {step} of saga {title} sends {command} to {found}, but it is handled by {expected}"
+    )]
+    WrongMicroservice {
+        title: String,
+        step: usize,
+        command: String,
+        expected: String,
+        found: String,
+    },
+    #[error("step {step} of saga {title} expects {expected} but receives {found}")]
+    PayloadMismatch {
+        title: String,
+        step: usize,
+        expected: String,
+        found: String,
+    },
+}
+
+/// A payload type declared in a definition. The types are compared by [`TypeId`] and exported
+/// by their name without module paths, the `title` of their JSON Schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadType {
+    id: TypeId,
+    type_name: &'static str,
+}
+
+impl PayloadType {
+    pub fn of<T: 'static>() -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+        }
+    }
+
+    /// The name of the type without module paths, e.g. `Vec<Image>`.
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        let mut rest = self.type_name;
+        while let Some(separator) = rest.find("::") {
+            let module = &rest[..separator];
+            name.push_str(module.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_'));
+            rest = &rest[separator + 2..];
+        }
+        name.push_str(rest);
+        name
+    }
+}
+
+impl Serialize for PayloadType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+/// A step of a saga: the command and the microservice that owns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SagaStepDefinition {
+    pub microservice: AvailableMicroservices,
+    pub command: StepCommand,
+    /// Whether the microservice registered a compensation handler that undoes the command
+    /// when a later step fails, see [`crate::connection::RabbitMQClient::saga_compensations`].
+    pub compensable: bool,
+    /// How long the orchestrator waits for the reply of the step.
+    #[serde(
+        rename = "timeoutMs",
+        serialize_with = "serialize_millis",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout: Option<Duration>,
+    /// Times the step is dispatched again when its timeout expires before it fails.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub timeout_retries: u32,
+    /// Type the step parses its payload into, checked against the output of the previous step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<PayloadType>,
+    /// Type the step acks with, the payload of the next step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_type: Option<PayloadType>,
+}
+
+impl SagaStepDefinition {
+    pub fn new(microservice: AvailableMicroservices, command: StepCommand) -> Self {
+        Self {
+            microservice,
+            command,
+            compensable: false,
+            timeout: None,
+            timeout_retries: 0,
+            input_type: None,
+            output_type: None,
+        }
+    }
+
+    /// The step is undone by its compensation handler when a later step fails.
+    pub fn compensable(mut self) -> Self {
+        self.compensable = true;
+        self
+    }
+
+    /// The step fails, and the saga is compensated, when its reply does not arrive in
+    /// `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Dispatches the step again up to `retries` times when its timeout expires, before
+    /// failing it.
+    pub fn retry_on_timeout(mut self, retries: u32) -> Self {
+        self.timeout_retries = retries;
+        self
+    }
+
+    /// Declares the payload the step receives (`I`) and acks with (`O`), so that
+    /// [`SagaDefinition::validate`] can check that consecutive steps chain together.
+    pub fn payloads<I: 'static, O: 'static>(mut self) -> Self {
+        self.input_type = Some(PayloadType::of::<I>());
+        self.output_type = Some(PayloadType::of::<O>());
+        self
+    }
+}
+
+/// The ordered steps the orchestrator runs for a [`SagaTitle`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SagaDefinition {
+    pub title: SagaTitle,
+    /// Type of the payload the saga is commenced with, the input of the first step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_type: Option<PayloadType>,
+    pub steps: Vec<SagaStepDefinition>,
+}
+
+impl SagaDefinition {
+    pub fn new(title: SagaTitle) -> Self {
+        Self {
+            title,
+            payload_type: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Declares the payload the saga is commenced with.
+    pub fn payload<T: PayloadCommenceSaga + 'static>(mut self) -> Self {
+        self.payload_type = Some(PayloadType::of::<T>());
+        self
+    }
+
+    pub fn then(mut self, step: SagaStepDefinition) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Appends a step without compensation.
+    pub fn step(self, microservice: AvailableMicroservices, command: StepCommand) -> Self {
+        self.then(SagaStepDefinition::new(microservice, command))
+    }
+
+    /// Appends a step that is undone by its compensation handler when a later step fails.
+    pub fn compensable_step(
+        self,
+        microservice: AvailableMicroservices,
+        command: StepCommand,
+    ) -> Self {
+        self.then(SagaStepDefinition::new(microservice, command).compensable())
+    }
+
+    /// Checks that the saga has steps, that every command is sent to the microservice that
+    /// handles it and that each declared payload matches the one produced before it.
+    pub fn validate(&self) -> Result<(), SagaDefinitionError> {
+        let title = self.title.as_ref();
+        if self.steps.is_empty() {
+            return Err(SagaDefinitionError::NoSteps(title.to_string()));
+        }
+
+        let mut produced = self.payload_type;
+        for (index, step) in self.steps.iter().enumerate() {
+            let expected = step.command.microservice();
+            if expected != step.microservice {
+                return Err(SagaDefinitionError::WrongMicroservice {
+                    title: title.to_string(),
+                    step: index,
+                    command: step.command.as_ref().to_string(),
+                    expected: expected.as_ref().to_string(),
+                    found: step.microservice.as_ref().to_string(),
+                });
+            }
+
+            // Undeclared payloads are not checked
+            if let (Some(found), Some(expected)) = (produced, step.input_type) {
+                if found != expected {
+                    return Err(SagaDefinitionError::PayloadMismatch {
+                        title: title.to_string(),
+                        step: index,
+                        expected: expected.type_name.to_string(),
+                        found: found.type_name.to_string(),
+                    });
+                }
+            }
+            produced = step.output_type;
+        }
+        Ok(())
+    }
+
+    /// The definition as the JSON the orchestrator is configured with.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Validates every definition and that no saga is defined twice.
+pub fn validate_definitions(definitions: &[SagaDefinition]) -> Result<(), SagaDefinitionError> {
+    let mut titles = HashSet::new();
+    for definition in definitions {
+        if !titles.insert(definition.title) {
+            return Err(SagaDefinitionError::Duplicated(
+                definition.title.as_ref().to_string(),
+            ));
+        }
+        definition.validate()?;
+    }
+    Ok(())
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn serialize_millis<S: Serializer>(
+    timeout: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match timeout {
+        Some(timeout) => serializer.serialize_u64(timeout.as_millis() as u64),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod test_saga_definition {
+    use super::*;
+    use crate::commence_saga::TransferCryptoRewardToMissionWinnerPayload;
+    use serde_json::json;
+
+    struct Image;
+    struct Minted;
+
+    fn definition() -> SagaDefinition {
+        SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner)
+            .payload::<TransferCryptoRewardToMissionWinnerPayload>()
+            .then(
+                SagaStepDefinition::new(
+                    AvailableMicroservices::TestImage,
+                    StepCommand::CreateImage,
+                )
+                .compensable()
+                .timeout(Duration::from_secs(30))
+                .payloads::<TransferCryptoRewardToMissionWinnerPayload, Image>(),
+            )
+            .then(
+                SagaStepDefinition::new(AvailableMicroservices::TestMint, StepCommand::MintImage)
+                    .payloads::<Image, Minted>(),
+            )
+            .step(
+                AvailableMicroservices::Blockchain,
+                StepCommand::TransferMissionRewardToWinner,
+            )
+    }
+
+    #[test]
+    fn valid_definition() {
+        assert_eq!(definition().validate(), Ok(()));
+        assert_eq!(
+            validate_definitions(&[
+                definition(),
+                SagaDefinition::new(SagaTitle::TransferCryptoRewardToRankingWinners).step(
+                    AvailableMicroservices::Blockchain,
+                    StepCommand::TransferRewardToWinners
+                )
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn command_sent_to_another_microservice() {
+        let definition = SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner)
+            .step(AvailableMicroservices::Social, StepCommand::MintImage);
+        assert!(matches!(
+            definition.validate(),
+            Err(SagaDefinitionError::WrongMicroservice { step: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn payloads_that_do_not_chain() {
+        let definition = definition().then(
+            SagaStepDefinition::new(AvailableMicroservices::TestImage, StepCommand::UpdateToken)
+                .payloads::<Image, Minted>(),
+        );
+        // The blockchain step in between declares no output, so nothing is checked
+        assert_eq!(definition.validate(), Ok(()));
+
+        let definition = SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner)
+            .then(
+                SagaStepDefinition::new(
+                    AvailableMicroservices::TestImage,
+                    StepCommand::CreateImage,
+                )
+                .payloads::<Image, Image>(),
+            )
+            .then(
+                SagaStepDefinition::new(AvailableMicroservices::TestMint, StepCommand::MintImage)
+                    .payloads::<Minted, Minted>(),
+            );
+        assert!(matches!(
+            definition.validate(),
+            Err(SagaDefinitionError::PayloadMismatch { step: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn payload_types_are_named_without_modules() {
+        assert_eq!(PayloadType::of::<Image>().name(), "Image");
+        assert_eq!(
+            PayloadType::of::<Vec<Option<Minted>>>().name(),
+            "Vec<Option<Minted>>"
+        );
+        assert_eq!(
+            PayloadType::of::<HashSet<String>>().name(),
+            "HashSet<String>"
+        );
+        assert_ne!(PayloadType::of::<Image>(), PayloadType::of::<Minted>());
+    }
+
+    #[test]
+    fn empty_and_duplicated_sagas() {
+        let empty = SagaDefinition::new(SagaTitle::TransferCryptoRewardToMissionWinner);
+        assert!(matches!(
+            empty.validate(),
+            Err(SagaDefinitionError::NoSteps(_))
+        ));
+        assert!(matches!(
+            validate_definitions(&[definition(), definition()]),
+            Err(SagaDefinitionError::Duplicated(_))
+        ));
+    }
+
+    #[test]
+    fn exports_json() {
+        let exported: serde_json::Value =
+            serde_json::from_str(&definition().to_json().unwrap()).unwrap();
+        assert_eq!(
+            exported["title"],
+            json!("transfer_crypto_reward_to_mission_winner")
+        );
+        assert_eq!(exported["steps"][0]["microservice"], json!("test-image"));
+        assert_eq!(exported["steps"][0]["command"], json!("create_image"));
+        assert_eq!(exported["steps"][0]["compensable"], json!(true));
+        assert_eq!(exported["steps"][0]["timeoutMs"], json!(30000));
+        assert_eq!(
+            exported["payloadType"],
+            json!("TransferCryptoRewardToMissionWinnerPayload")
+        );
+        assert_eq!(exported["steps"][1]["inputType"], json!("Image"));
+        assert_eq!(exported["steps"][1]["outputType"], json!("Minted"));
+        assert!(exported["steps"][2].get("timeoutMs").is_none());
+        assert_eq!(
+            exported["steps"][2]["command"],
+            json!("crypto_reward:transfer_mission_reward_to_winner")
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::commence_saga::SagaTitle;
+use crate::connection::{RabbitMQClient, RabbitMQError, RABBIT_URI};
+use crate::orchestrator::SagaStatus;
+use crate::saga::StepCommand;
+use futures_lite::StreamExt;
+use lapin::options::{BasicConsumeOptions, QueueDeclareOptions};
+use lapin::types::FieldTable;
+use lapin::Channel;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tracing::warn;
+
+/// State of a saga as reported by the orchestrator to the microservice that commenced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SagaUpdate {
+    pub correlation_id: Option<String>,
+    pub saga_id: i32,
+    pub title: SagaTitle,
+    pub status: SagaStatus,
+    /// The command whose reply is awaited, `None` once the saga finished.
+    pub current_command: Option<StepCommand>,
+    pub failure_reason: Option<String>,
+    /// Detail of the last progress reported by the current step.
+    #[serde(default)]
+    pub progress_detail: Option<String>,
+}
+
+type Watchers = Arc<StdMutex<HashMap<String, watch::Sender<Option<SagaUpdate>>>>>;
+
+/// Consumer of the updates of the sagas commenced by this process.
+struct SagaTracker {
+    channel: Channel,
+    queue_name: String,
+    watchers: Watchers,
+}
+
+static SAGA_TRACKER: Mutex<Option<SagaTracker>> = Mutex::const_new(None);
+
+/// Tracks a saga started with [`RabbitMQClient::commence_tracked_saga`]. The updates are
+/// sent by the orchestrator of [`RabbitMQClient::start_saga_orchestrator`], orchestrators
+/// that do not report them leave the handle without updates. The saga stops being tracked
+/// once it finishes or the last clone of the handle is dropped.
+#[derive(Debug, Clone)]
+pub struct SagaHandle {
+    correlation_id: String,
+    title: SagaTitle,
+    updates: watch::Receiver<Option<SagaUpdate>>,
+    _watcher: Arc<WatcherGuard>,
+}
+
+/// Forgets the watcher of a saga when the last clone of its handle is dropped.
+#[derive(Debug)]
+struct WatcherGuard {
+    correlation_id: String,
+    watchers: Watchers,
+}
+
+impl Drop for WatcherGuard {
+    fn drop(&mut self) {
+        self.watchers.lock().unwrap().remove(&self.correlation_id);
+    }
+}
+
+impl SagaHandle {
+    fn new(
+        correlation_id: String,
+        title: SagaTitle,
+        updates: watch::Receiver<Option<SagaUpdate>>,
+        watchers: Watchers,
+    ) -> Self {
+        let watcher = WatcherGuard {
+            correlation_id: correlation_id.clone(),
+            watchers,
+        };
+        Self {
+            correlation_id,
+            title,
+            updates,
+            _watcher: Arc::new(watcher),
+        }
+    }
+
+    pub fn correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    pub fn title(&self) -> SagaTitle {
+        self.title
+    }
+
+    /// Id assigned by the orchestrator, `None` until its first update arrives.
+    pub fn saga_id(&self) -> Option<i32> {
+        self.updates.borrow().as_ref().map(|update| update.saga_id)
+    }
+
+    /// The last update received, `None` if the orchestrator did not report yet.
+    pub fn latest(&self) -> Option<SagaUpdate> {
+        self.updates.borrow().clone()
+    }
+
+    /// The command the saga is waiting for, if it is known and the saga did not finish.
+    pub fn current_command(&self) -> Option<StepCommand> {
+        self.updates
+            .borrow()
+            .as_ref()
+            .and_then(|update| update.current_command.clone())
+    }
+
+    /// Waits up to `timeout` for the saga to finish, whatever its outcome.
+    pub async fn finished(&mut self, timeout: Duration) -> Result<SagaUpdate, RabbitMQError> {
+        let update = tokio::time::timeout(
+            timeout,
+            self.updates.wait_for(|update| {
+                update
+                    .as_ref()
+                    .is_some_and(|update| update.status.is_finished())
+            }),
+        )
+        .await
+        .map_err(|_| RabbitMQError::TimeoutError)?
+        .map_err(|_| RabbitMQError::ChannelClosed)?;
+        Ok(update.clone().expect("finished sagas have an update"))
+    }
+
+    /// Waits up to `timeout` for the saga to finish and fails unless every step succeeded.
+    pub async fn completed(&mut self, timeout: Duration) -> Result<SagaUpdate, RabbitMQError> {
+        let update = self.finished(timeout).await?;
+        if update.status != SagaStatus::Completed {
+            return Err(RabbitMQError::SagaNotCompleted(Box::new(update)));
+        }
+        Ok(update)
+    }
+}
+
+impl RabbitMQClient {
+    /// Queue the orchestrator reports to and a handle for a saga about to be commenced. The
+    /// queue and its channel are created by the first saga tracked.
+    pub(crate) async fn track_saga(
+        title: SagaTitle,
+        correlation_id: String,
+    ) -> Result<(String, SagaHandle), RabbitMQError> {
+        let mut tracker = SAGA_TRACKER.lock().await;
+        let connected = tracker
+            .as_ref()
+            .is_some_and(|tracker| tracker.channel.status().connected());
+        if !connected {
+            // The watchers survive a reconnection, the updates sent to the old queue are lost
+            let watchers = tracker
+                .take()
+                .map(|tracker| tracker.watchers)
+                .unwrap_or_default();
+            *tracker = Some(Self::start_tracking(watchers).await?);
+        }
+        let tracker = tracker.as_ref().expect("the tracker was just started");
+
+        let (sender, updates) = watch::channel(None);
+        tracker
+            .watchers
+            .lock()
+            .unwrap()
+            .insert(correlation_id.clone(), sender);
+        let handle = SagaHandle::new(correlation_id, title, updates, tracker.watchers.clone());
+        Ok((tracker.queue_name.clone(), handle))
+    }
+
+    async fn start_tracking(watchers: Watchers) -> Result<SagaTracker, RabbitMQError> {
+        let rabbit_uri = RABBIT_URI
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or(RabbitMQError::ValueIsNotSet("rabbit_uri".to_string()))?;
+        let channel = Self::get_connection(rabbit_uri)
+            .await?
+            .read()
+            .await
+            .create_channel()
+            .await?;
+        // A queue named by the broker that lives as long as this connection
+        let queue = channel
+            .queue_declare(
+                "",
+                QueueDeclareOptions {
+                    exclusive: true,
+                    auto_delete: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        let queue_name = queue.name().to_string();
+        let mut consumer = channel
+            .basic_consume(
+                &queue_name,
+                &format!("{queue_name}_consumer"),
+                BasicConsumeOptions {
+                    no_ack: true,
+                    ..BasicConsumeOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        tokio::spawn({
+            let watchers = watchers.clone();
+            async move {
+                while let Some(Ok(delivery)) = consumer.next().await {
+                    match serde_json::from_slice::<SagaUpdate>(&delivery.data) {
+                        Ok(update) => deliver_update(&watchers, update),
+                        Err(e) => warn!("Discarding an invalid saga update: {:?}", e),
+                    }
+                }
+            }
+        });
+
+        Ok(SagaTracker {
+            channel,
+            queue_name,
+            watchers,
+        })
+    }
+}
+
+/// Passes the update to its handle and forgets the saga once it finished.
+fn deliver_update(watchers: &Watchers, update: SagaUpdate) {
+    let Some(correlation_id) = update.correlation_id.clone() else {
+        return;
+    };
+    let mut watchers = watchers.lock().unwrap();
+    let Some(sender) = watchers.get(&correlation_id) else {
+        return;
+    };
+    let finished = update.status.is_finished();
+    if sender.send(Some(update)).is_err() || finished {
+        watchers.remove(&correlation_id);
+    }
+}
+
+#[cfg(test)]
+mod test_saga_handle {
+    use super::*;
+
+    fn update(status: SagaStatus, current_command: Option<StepCommand>) -> SagaUpdate {
+        SagaUpdate {
+            correlation_id: Some("corr-1".to_string()),
+            saga_id: 4,
+            title: SagaTitle::TransferCryptoRewardToMissionWinner,
+            status,
+            current_command,
+            failure_reason: None,
+            progress_detail: None,
+        }
+    }
+
+    fn tracked() -> (Watchers, SagaHandle) {
+        let (sender, updates) = watch::channel(None);
+        let watchers = Watchers::default();
+        watchers
+            .lock()
+            .unwrap()
+            .insert("corr-1".to_string(), sender);
+        let handle = SagaHandle::new(
+            "corr-1".to_string(),
+            SagaTitle::TransferCryptoRewardToMissionWinner,
+            updates,
+            watchers.clone(),
+        );
+        (watchers, handle)
+    }
+
+    #[tokio::test]
+    async fn follows_the_updates_until_the_saga_completes() {
+        let (watchers, mut handle) = tracked();
+        assert_eq!(handle.saga_id(), None);
+
+        deliver_update(
+            &watchers,
+            update(SagaStatus::Running, Some(StepCommand::MintImage)),
+        );
+        assert_eq!(handle.saga_id(), Some(4));
+        assert_eq!(handle.current_command(), Some(StepCommand::MintImage));
+        assert!(matches!(
+            handle.finished(Duration::from_millis(10)).await,
+            Err(RabbitMQError::TimeoutError)
+        ));
+
+        deliver_update(&watchers, update(SagaStatus::Completed, None));
+        let completed = handle.completed(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(completed.status, SagaStatus::Completed);
+        assert!(watchers.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn compensated_saga_is_not_completed() {
+        let (watchers, mut handle) = tracked();
+        let mut compensated = update(SagaStatus::Compensated, None);
+        compensated.failure_reason = Some("boom".to_string());
+        deliver_update(&watchers, compensated);
+
+        assert_eq!(
+            handle
+                .finished(Duration::from_secs(1))
+                .await
+                .unwrap()
+                .status,
+            SagaStatus::Compensated
+        );
+        match handle.completed(Duration::from_secs(1)).await {
+            Err(RabbitMQError::SagaNotCompleted(update)) => {
+                assert_eq!(update.failure_reason.as_deref(), Some("boom"))
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn dropped_handles_are_forgotten() {
+        // Orchestrators that never report leave no watcher behind
+        let (watchers, handle) = tracked();
+        let clone = handle.clone();
+        drop(handle);
+        assert_eq!(watchers.lock().unwrap().len(), 1);
+        drop(clone);
+        assert!(watchers.lock().unwrap().is_empty());
+
+        deliver_update(
+            &watchers,
+            update(SagaStatus::Running, Some(StepCommand::MintImage)),
+        );
+        assert!(watchers.lock().unwrap().is_empty());
+    }
+}
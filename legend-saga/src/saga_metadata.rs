@@ -0,0 +1,298 @@
+use crate::raw_payload::RawPayload;
+use once_cell::sync::OnceCell;
+use serde::de::{DeserializeOwned, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Payload keys starting with this prefix are saga metadata: every step forwards them
+/// to the next one.
+pub const METADATA_PREFIX: &str = "__";
+/// Maximum size of the metadata of a saga, as serialized JSON keys and values.
+pub const MAX_METADATA_BYTES: usize = 16 * 1024;
+
+#[derive(Error, Debug)]
+pub enum SagaMetadataError {
+    #[error("invalid metadata key {0:?}: it must not be empty nor start with '_'")]
+    InvalidKey(String),
+    #[error("metadata {key:?} does not have the expected type: {source}")]
+    Parse {
+        key: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("metadata {key:?} cannot be serialized: {source}")]
+    Serialize {
+        key: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("saga metadata would take {size} bytes, the limit is {limit}")]
+    TooLarge { size: usize, limit: usize },
+    #[error("metadata {0:?} is set both with set_metadata and in the payload of the step")]
+    Collision(String),
+}
+
+/// Metadata of the saga as seen by a step: what the previous steps forwarded and what
+/// this step sets. Keys are stored without [`METADATA_PREFIX`].
+#[derive(Debug, Clone)]
+pub(crate) struct SagaMetadata {
+    previous_payload: RawPayload,
+    /// Read from the previous payload the first time it is needed.
+    inherited: OnceCell<HashMap<String, Value>>,
+    set: HashMap<String, Value>,
+}
+
+impl SagaMetadata {
+    pub(crate) fn from_payload(previous_payload: RawPayload) -> Self {
+        Self {
+            previous_payload,
+            inherited: OnceCell::new(),
+            set: HashMap::new(),
+        }
+    }
+
+    /// The metadata forwarded by the previous steps, without parsing the rest of the payload.
+    fn inherited(&self) -> &HashMap<String, Value> {
+        self.inherited.get_or_init(|| {
+            self.previous_payload
+                .parse::<Inherited>()
+                .expect("the payload was checked to be a JSON object")
+                .0
+        })
+    }
+
+    /// The value set by this step or, failing that, forwarded by the previous ones.
+    pub(crate) fn get<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, SagaMetadataError> {
+        let Some(value) = self.set.get(key).or_else(|| self.inherited().get(key)) else {
+            return Ok(None);
+        };
+        serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|source| SagaMetadataError::Parse {
+                key: key.to_string(),
+                source,
+            })
+    }
+
+    /// Sets `key` for this and the next steps, replacing the forwarded value if any.
+    pub(crate) fn set<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), SagaMetadataError> {
+        if key.is_empty() || key.starts_with('_') {
+            return Err(SagaMetadataError::InvalidKey(key.to_string()));
+        }
+        let value = serde_json::to_value(value).map_err(|source| SagaMetadataError::Serialize {
+            key: key.to_string(),
+            source,
+        })?;
+
+        let previous = self.set.insert(key.to_string(), value);
+        let size = self.size();
+        if size > MAX_METADATA_BYTES {
+            // The metadata is left as it was
+            match previous {
+                Some(previous) => self.set.insert(key.to_string(), previous),
+                None => self.set.remove(key),
+            };
+            return Err(SagaMetadataError::TooLarge {
+                size,
+                limit: MAX_METADATA_BYTES,
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes the metadata into the payload of the next step. Forwarded values are
+    /// overridden by `__` keys of the payload, which in turn cannot override the values set
+    /// with [`SagaMetadata::set`].
+    pub(crate) fn apply_to(
+        &self,
+        payload: &mut HashMap<String, Value>,
+    ) -> Result<(), SagaMetadataError> {
+        let inherited = self.inherited();
+        for (key, value) in inherited {
+            payload
+                .entry(format!("{METADATA_PREFIX}{key}"))
+                .or_insert_with(|| value.clone());
+        }
+        for (key, value) in &self.set {
+            let prefixed = format!("{METADATA_PREFIX}{key}");
+            if let Some(in_payload) = payload.get(&prefixed) {
+                if in_payload != value && !inherited.get(key).is_some_and(|v| v == in_payload)
+                {
+                    return Err(SagaMetadataError::Collision(key.clone()));
+                }
+            }
+            payload.insert(prefixed, value.clone());
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let mut effective: HashMap<&String, &Value> = self.inherited().iter().collect();
+        effective.extend(self.set.iter());
+        effective
+            .into_iter()
+            .map(|(key, value)| {
+                METADATA_PREFIX.len() + key.len() + serde_json::to_vec(value).map_or(0, |v| v.len())
+            })
+            .sum()
+    }
+}
+
+/// The metadata key of a payload key, `None` for regular keys.
+fn metadata_key(key: &str) -> Option<&str> {
+    key.strip_prefix(METADATA_PREFIX)
+        .filter(|key| !key.is_empty())
+}
+
+/// The metadata keys of a payload, the values of the other keys are skipped unparsed.
+struct Inherited(HashMap<String, Value>);
+
+impl<'de> Deserialize<'de> for Inherited {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct InheritedVisitor;
+
+        impl<'de> Visitor<'de> for InheritedVisitor {
+            type Value = Inherited;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Inherited, A::Error> {
+                let mut inherited = HashMap::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    match metadata_key(&key) {
+                        Some(key) => {
+                            inherited.insert(key.to_string(), map.next_value()?);
+                        }
+                        None => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Inherited(inherited))
+            }
+        }
+
+        deserializer.deserialize_map(InheritedVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test_saga_metadata {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Trace {
+        id: String,
+    }
+
+    fn metadata() -> SagaMetadata {
+        SagaMetadata::from_payload(
+            RawPayload::from_json(
+                br#"{"__trace": {"id": "abc"}, "__": "not metadata", "userId": "user1"}"#,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn reads_the_forwarded_metadata() {
+        let metadata = metadata();
+        // Only read once asked for
+        assert!(metadata.inherited.get().is_none());
+        assert_eq!(
+            metadata.get::<Trace>("trace").unwrap(),
+            Some(Trace {
+                id: "abc".to_string()
+            })
+        );
+        assert_eq!(metadata.get::<String>("userId").unwrap(), None);
+        assert_eq!(metadata.get::<String>("").unwrap(), None);
+        assert!(matches!(
+            metadata.get::<u32>("trace"),
+            Err(SagaMetadataError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn set_values_win_and_are_forwarded() {
+        let mut metadata = metadata();
+        metadata
+            .set(
+                "trace",
+                &Trace {
+                    id: "def".to_string(),
+                },
+            )
+            .unwrap();
+        metadata.set("attempt", &2).unwrap();
+        assert_eq!(metadata.get::<u32>("attempt").unwrap(), Some(2));
+
+        let mut payload = HashMap::from([("imageId".to_string(), json!(7))]);
+        metadata.apply_to(&mut payload).unwrap();
+        assert_eq!(payload["__trace"], json!({"id": "def"}));
+        assert_eq!(payload["__attempt"], json!(2));
+        assert_eq!(payload["imageId"], json!(7));
+        assert!(!payload.contains_key("userId"));
+    }
+
+    #[test]
+    fn payload_keys_override_only_forwarded_values() {
+        let mut payload = HashMap::from([("__trace".to_string(), json!({"id": "payload"}))]);
+        metadata().apply_to(&mut payload).unwrap();
+        assert_eq!(payload["__trace"], json!({"id": "payload"}));
+
+        let mut metadata = metadata();
+        metadata
+            .set(
+                "trace",
+                &Trace {
+                    id: "def".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            metadata.apply_to(&mut payload),
+            Err(SagaMetadataError::Collision(key)) if key == "trace"
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_keys_and_oversized_values() {
+        let mut metadata = metadata();
+        assert!(matches!(
+            metadata.set("", &1),
+            Err(SagaMetadataError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            metadata.set("_private", &1),
+            Err(SagaMetadataError::InvalidKey(_))
+        ));
+
+        let big = "x".repeat(MAX_METADATA_BYTES);
+        assert!(matches!(
+            metadata.set("trace", &big),
+            Err(SagaMetadataError::TooLarge { .. })
+        ));
+        // The rejected value is not kept
+        assert_eq!(
+            metadata.get::<Trace>("trace").unwrap(),
+            Some(Trace {
+                id: "abc".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use crate::commence_saga::SagaTitle;
+use crate::orchestrator::SagaStatus;
+use crate::saga::StepCommand;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SagaStoreError {
+    #[error("saga {saga_id} was modified since version {version} was read")]
+    Conflict { saga_id: i32, version: u64 },
+    #[error("saga {0} not found")]
+    NotFound(i32),
+    #[error("saga store error: {0}")]
+    Backend(String),
+}
+
+/// A step that succeeded, kept to compensate it if a later step fails.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedStep {
+    pub input: HashMap<String, Value>,
+    pub output: HashMap<String, Value>,
+}
+
+/// An entry of the status history of a saga.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaTransition {
+    pub step: usize,
+    pub command: StepCommand,
+    pub status: SagaStatus,
+    /// UNIX timestamp in milliseconds.
+    pub at: u64,
+}
+
+/// Durable state of a saga run by the orchestrator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaRecord {
+    /// Assigned by the store on [`SagaStore::create`].
+    pub saga_id: i32,
+    pub title: SagaTitle,
+    pub status: SagaStatus,
+    /// Index of the step being run, or being compensated while compensating.
+    pub current_step: usize,
+    /// Payload sent to the current step, including the `__` metadata keys the steps forward.
+    pub input: HashMap<String, Value>,
+    /// Steps that succeeded, in order. Compensated steps are removed.
+    pub completed: Vec<CompletedStep>,
+    pub failure_reason: Option<String>,
+    pub operation_id: Option<String>,
+    pub history: Vec<SagaTransition>,
+    /// When the current step was first dispatched (UNIX timestamp in milliseconds).
+    #[serde(default)]
+    pub step_started_at: u64,
+    /// When the reply of the current step is due, `None` for steps without timeout. While
+    /// the step is [`SagaRecord::dispatch_pending`], when it is sent again.
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// The current step is stored but its publication was not confirmed yet.
+    #[serde(default)]
+    pub dispatch_pending: bool,
+    /// Times the current step was dispatched again after its timeout expired.
+    #[serde(default)]
+    pub timeout_retries: u32,
+    /// When the current step last reported progress (UNIX timestamp in milliseconds).
+    #[serde(default)]
+    pub last_progress_at: Option<u64>,
+    /// Detail of the last progress of the current step.
+    #[serde(default)]
+    pub progress_detail: Option<String>,
+    /// Id the commencer tracks the saga with, see [`crate::saga_handle::SagaHandle`].
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    /// Queue the changes of the saga are sent to.
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    /// Incremented by the store on every update, see [`SagaStore::update`].
+    pub version: u64,
+}
+
+/// Persistence of the orchestrator. Implementations must be safe to share between the
+/// consumers of `commence_saga` and `reply_to_saga`. The methods may block, the orchestrator
+/// calls them on the blocking thread pool of tokio.
+///
+/// Replicas sharing a store do not overwrite each other's changes thanks to
+/// [`SagaStore::update`], but there is no leasing: every replica dispatches the pending step
+/// of every unfinished saga again when it starts, so participants may receive a step twice.
+pub trait SagaStore: Send + Sync {
+    /// Stores a new saga, returning it with its `saga_id` and version 1.
+    fn create(&self, record: SagaRecord) -> Result<SagaRecord, SagaStoreError>;
+
+    fn get(&self, saga_id: i32) -> Result<Option<SagaRecord>, SagaStoreError>;
+
+    /// Replaces the saga only if the stored version is still `record.version`, otherwise
+    /// fails with [`SagaStoreError::Conflict`]. Returns the record with its new version.
+    fn update(&self, record: &SagaRecord) -> Result<SagaRecord, SagaStoreError>;
+
+    /// Sagas that have not finished, ordered by id, to resume them on restart.
+    fn unfinished(&self) -> Result<Vec<SagaRecord>, SagaStoreError>;
+
+    /// Unfinished sagas whose deadline is at or before `now` (UNIX timestamp in
+    /// milliseconds), ordered by id. Called every second, stores that keep many sagas should
+    /// look them up by deadline rather than filter [`SagaStore::unfinished`].
+    fn expired(&self, now: u64) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        Ok(self
+            .unfinished()?
+            .into_iter()
+            .filter(|record| record.deadline.is_some_and(|deadline| deadline <= now))
+            .collect())
+    }
+}
+
+/// Calls a [`SagaStore`] on the blocking thread pool, keeping its I/O off the workers that
+/// run the consumers.
+#[derive(Clone)]
+pub(crate) struct BlockingSagaStore(Arc<dyn SagaStore>);
+
+impl BlockingSagaStore {
+    pub(crate) fn new(store: Arc<dyn SagaStore>) -> Self {
+        Self(store)
+    }
+
+    async fn run<T, F>(&self, call: F) -> Result<T, SagaStoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn SagaStore) -> Result<T, SagaStoreError> + Send + 'static,
+    {
+        let store = self.0.clone();
+        tokio::task::spawn_blocking(move || call(store.as_ref()))
+            .await
+            .map_err(|e| SagaStoreError::Backend(e.to_string()))?
+    }
+
+    pub(crate) async fn create(&self, record: SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        self.run(move |store| store.create(record)).await
+    }
+
+    pub(crate) async fn get(&self, saga_id: i32) -> Result<Option<SagaRecord>, SagaStoreError> {
+        self.run(move |store| store.get(saga_id)).await
+    }
+
+    pub(crate) async fn update(&self, record: &SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        let record = record.clone();
+        self.run(move |store| store.update(&record)).await
+    }
+
+    pub(crate) async fn unfinished(&self) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        self.run(|store| store.unfinished()).await
+    }
+
+    pub(crate) async fn expired(&self, now: u64) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        self.run(move |store| store.expired(now)).await
+    }
+}
+
+#[derive(Debug, Default)]
+struct InMemoryState {
+    last_id: i32,
+    records: HashMap<i32, SagaRecord>,
+}
+
+/// Store that lives as long as the process, the sagas are lost on restart.
+#[derive(Debug, Default)]
+pub struct InMemorySagaStore {
+    state: Mutex<InMemoryState>,
+}
+
+impl InMemorySagaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SagaStore for InMemorySagaStore {
+    fn create(&self, mut record: SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        let mut state = self.state.lock().unwrap();
+        state.last_id += 1;
+        record.saga_id = state.last_id;
+        record.version = 1;
+        state.records.insert(record.saga_id, record.clone());
+        Ok(record)
+    }
+
+    fn get(&self, saga_id: i32) -> Result<Option<SagaRecord>, SagaStoreError> {
+        Ok(self.state.lock().unwrap().records.get(&saga_id).cloned())
+    }
+
+    fn update(&self, record: &SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        let mut state = self.state.lock().unwrap();
+        let stored = state
+            .records
+            .get_mut(&record.saga_id)
+            .ok_or(SagaStoreError::NotFound(record.saga_id))?;
+        if stored.version != record.version {
+            return Err(SagaStoreError::Conflict {
+                saga_id: record.saga_id,
+                version: record.version,
+            });
+        }
+        *stored = record.clone();
+        stored.version += 1;
+        Ok(stored.clone())
+    }
+
+    fn unfinished(&self) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        let state = self.state.lock().unwrap();
+        let mut records: Vec<SagaRecord> = state
+            .records
+            .values()
+            .filter(|record| !record.status.is_finished())
+            .cloned()
+            .collect();
+        records.sort_by_key(|record| record.saga_id);
+        Ok(records)
+    }
+}
+
+/// Contract every [`SagaStore`] must fulfil, shared by the tests of each implementation.
+#[cfg(test)]
+pub(crate) fn check_store_contract(store: &dyn SagaStore) {
+    let record = SagaRecord {
+        saga_id: 0,
+        title: SagaTitle::TransferCryptoRewardToMissionWinner,
+        status: SagaStatus::Running,
+        current_step: 0,
+        input: HashMap::from([("__trace".to_string(), Value::from("abc"))]),
+        completed: Vec::new(),
+        failure_reason: None,
+        operation_id: Some("op-1".to_string()),
+        history: Vec::new(),
+        step_started_at: 0,
+        deadline: None,
+        dispatch_pending: false,
+        timeout_retries: 0,
+        last_progress_at: None,
+        progress_detail: None,
+        correlation_id: None,
+        reply_to: None,
+        version: 0,
+    };
+
+    let first = store.create(record.clone()).unwrap();
+    let second = store
+        .create(SagaRecord {
+            deadline: Some(1_000),
+            ..record
+        })
+        .unwrap();
+    assert_ne!(first.saga_id, second.saga_id);
+    assert_eq!(first.version, 1);
+    assert_eq!(store.get(first.saga_id).unwrap(), Some(first.clone()));
+    assert_eq!(store.get(9999).unwrap(), None);
+
+    let mut advanced = first.clone();
+    advanced.current_step = 1;
+    let advanced = store.update(&advanced).unwrap();
+    assert_eq!(advanced.version, 2);
+    assert_eq!(store.get(first.saga_id).unwrap().unwrap().current_step, 1);
+
+    // A writer that read version 1 loses
+    assert_eq!(
+        store.update(&first),
+        Err(SagaStoreError::Conflict {
+            saga_id: first.saga_id,
+            version: 1
+        })
+    );
+
+    let mut finished = advanced;
+    finished.status = SagaStatus::Completed;
+    store.update(&finished).unwrap();
+    let unfinished = store.unfinished().unwrap();
+    assert_eq!(unfinished.len(), 1);
+    assert_eq!(unfinished[0].saga_id, second.saga_id);
+    assert_eq!(unfinished[0].input["__trace"], Value::from("abc"));
+
+    assert!(store.expired(999).unwrap().is_empty());
+    let expired = store.expired(1_000).unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].saga_id, second.saga_id);
+
+    let mut missing = second;
+    missing.saga_id = 9999;
+    assert_eq!(store.update(&missing), Err(SagaStoreError::NotFound(9999)));
+}
+
+#[cfg(test)]
+mod test_saga_store {
+    use super::*;
+
+    #[test]
+    fn in_memory_store() {
+        check_store_contract(&InMemorySagaStore::new());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::saga_store::{SagaRecord, SagaStore, SagaStoreError};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// [`SagaStore`] backed by a SQLite database, the sagas survive restarts of the orchestrator.
+/// Every saga is a row holding the JSON of its [`SagaRecord`], the version used for the
+/// optimistic concurrency of [`SagaStore::update`] and the deadline the sweeper looks up.
+pub struct SqliteSagaStore {
+    connection: Mutex<Connection>,
+}
+
+impl From<rusqlite::Error> for SagaStoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        SagaStoreError::Backend(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SagaStoreError {
+    fn from(err: serde_json::Error) -> Self {
+        SagaStoreError::Backend(err.to_string())
+    }
+}
+
+impl SqliteSagaStore {
+    /// Opens (or creates) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SagaStoreError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// A database that lives in memory, mainly for tests.
+    pub fn open_in_memory() -> Result<Self, SagaStoreError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, SagaStoreError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sagas (
+                saga_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                version INTEGER NOT NULL,
+                finished INTEGER NOT NULL,
+                deadline INTEGER,
+                record TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS sagas_finished ON sagas (finished);
+            CREATE INDEX IF NOT EXISTS sagas_deadline ON sagas (finished, deadline);",
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl SagaStore for SqliteSagaStore {
+    fn create(&self, mut record: SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction()?;
+        tx.execute(
+            "INSERT INTO sagas (version, finished, deadline, record) VALUES (1, ?1, ?2, '')",
+            params![record.status.is_finished(), record.deadline],
+        )?;
+        record.saga_id = i32::try_from(tx.last_insert_rowid())
+            .map_err(|e| SagaStoreError::Backend(e.to_string()))?;
+        record.version = 1;
+        tx.execute(
+            "UPDATE sagas SET record = ?1 WHERE saga_id = ?2",
+            params![serde_json::to_string(&record)?, record.saga_id],
+        )?;
+        tx.commit()?;
+        Ok(record)
+    }
+
+    fn get(&self, saga_id: i32) -> Result<Option<SagaRecord>, SagaStoreError> {
+        let connection = self.connection.lock().unwrap();
+        let record: Option<String> = connection
+            .query_row(
+                "SELECT record FROM sagas WHERE saga_id = ?1",
+                params![saga_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(record.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    fn update(&self, record: &SagaRecord) -> Result<SagaRecord, SagaStoreError> {
+        let mut updated = record.clone();
+        updated.version += 1;
+
+        let connection = self.connection.lock().unwrap();
+        let changed = connection.execute(
+            "UPDATE sagas SET version = ?1, finished = ?2, deadline = ?3, record = ?4
+             WHERE saga_id = ?5 AND version = ?6",
+            params![
+                updated.version,
+                updated.status.is_finished(),
+                updated.deadline,
+                serde_json::to_string(&updated)?,
+                record.saga_id,
+                record.version
+            ],
+        )?;
+        if changed == 1 {
+            return Ok(updated);
+        }
+
+        let exists: Option<i32> = connection
+            .query_row(
+                "SELECT saga_id FROM sagas WHERE saga_id = ?1",
+                params![record.saga_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match exists {
+            Some(_) => Err(SagaStoreError::Conflict {
+                saga_id: record.saga_id,
+                version: record.version,
+            }),
+            None => Err(SagaStoreError::NotFound(record.saga_id)),
+        }
+    }
+
+    fn unfinished(&self) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement =
+            connection.prepare("SELECT record FROM sagas WHERE finished = 0 ORDER BY saga_id")?;
+        let records = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|json| Ok(serde_json::from_str(&json?)?))
+            .collect();
+        records
+    }
+
+    fn expired(&self, now: u64) -> Result<Vec<SagaRecord>, SagaStoreError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT record FROM sagas WHERE finished = 0 AND deadline <= ?1 ORDER BY saga_id",
+        )?;
+        let records = statement
+            .query_map(params![now], |row| row.get::<_, String>(0))?
+            .map(|json| Ok(serde_json::from_str(&json?)?))
+            .collect();
+        records
+    }
+}
+
+#[cfg(test)]
+mod test_sqlite_saga_store {
+    use super::*;
+    use crate::saga_store::check_store_contract;
+
+    #[test]
+    fn sqlite_store() {
+        check_store_contract(&SqliteSagaStore::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn sagas_survive_reopening_the_database() {
+        let path = std::env::temp_dir().join(format!("sagas-{}.db", uuid::Uuid::now_v7()));
+        let store = SqliteSagaStore::open(&path).unwrap();
+        check_store_contract(&store);
+        drop(store);
+
+        let reopened = SqliteSagaStore::open(&path).unwrap();
+        let unfinished = reopened.unfinished().unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].version, 1);
+        drop(reopened);
+        let _ = std::fs::remove_file(&path);
+    }
+}
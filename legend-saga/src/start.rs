@@ -20,6 +20,7 @@ impl RabbitMQClient {
         // Create audit logging resources, this feature is related only to "events", that is why we
         // create it here
         self.create_audit_logging_resources().await?;
+        self.create_quarantine_resources().await?;
 
         let emitter = self.start_consuming_events().await;
 
@@ -55,12 +56,22 @@ impl RabbitMQClient {
             exchange: Exchange::COMMANDS,
         }])
         .await?;
+        self.create_quarantine_resources().await?;
+        // The saga steps are audited too, the audit exchange must exist before publishing to it
+        self.create_audit_logging_resources().await?;
 
         let emitter = self.start_consuming_saga_commands().await;
 
         Ok(emitter)
     }
 
+    /// Handlers that undo a saga command when a later step of the saga fails, registered by
+    /// the command they undo. The steps arrive through [`RabbitMQClient::connect_to_saga_commands`],
+    /// those of a command without a compensation handler are replied as failed.
+    pub fn saga_compensations(&self) -> SagaEmitter {
+        self.compensation_emitter.clone()
+    }
+
     pub(crate) async fn start_consuming_saga_commands(&self) -> SagaEmitter {
         let mut emitter_guard = self.saga_emitter.lock().await;
         let emitter = emitter_guard.get_or_insert_with(Emitter::new).clone();
@@ -143,6 +154,18 @@ impl RabbitMQClient {
             }
         });
 
+        // Spawn consumer for audit.saga_step_* events
+        tokio::spawn({
+            let client = self.clone();
+            let emitter = emitter.clone();
+
+            async move {
+                if let Err(e) = client.consume_audit_saga_step_events(emitter).await {
+                    error!("Error consuming audit saga step events: {:?}", e);
+                }
+            }
+        });
+
         emitter
     }
 }
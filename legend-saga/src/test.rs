@@ -235,6 +235,7 @@ pub(crate) mod setup {
         ///   guaranteed by `RabbitMQClient::get_connection`.
         /// - The `topology` parameter is optional; if not provided, the current connection's topology will
         ///   be used.
+        #[allow(clippy::doc_overindented_list_items)]
         pub(crate) async fn clean_topology(&self, t: Option<TopologyDefinition>) {
             let conn = self
                 .client
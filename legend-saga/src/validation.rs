@@ -0,0 +1,101 @@
+//! Validation of the event payloads, see [`Validate`]. `publish_event` validates every
+//! payload it sends, the consumers only when
+//! [`RabbitMQClient::validate_consumed_events`] is enabled.
+
+use crate::connection::{RabbitMQClient, RabbitMQError};
+use crate::events::{MicroserviceEvent, PayloadEvent, Validate};
+use crate::raw_payload::RawPayload;
+use std::sync::atomic::Ordering;
+
+impl RabbitMQClient {
+    /// Validates the payloads of the events received, once upcast, before they reach the
+    /// handler. Payloads that do not parse or fail the validation are quarantined like
+    /// undecodable deliveries. Disabled by default.
+    pub fn validate_consumed_events(&self, enabled: bool) {
+        self.validate_consumed.store(enabled, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn validate_payload<T: PayloadEvent + Validate>(
+    payload: &T,
+) -> Result<(), RabbitMQError> {
+    payload.validate().map_err(|e| {
+        RabbitMQError::InvalidPayload(format!("{} payload: {e}", payload.event_type().as_ref()))
+    })
+}
+
+/// Validates a received payload of `event`.
+pub(crate) fn validate_consumed(
+    event: MicroserviceEvent,
+    payload: &RawPayload,
+) -> Result<(), RabbitMQError> {
+    validate_payload(&payload.typed(event)?)
+}
+
+#[cfg(test)]
+mod test_validation {
+    use super::*;
+    use crate::events::{BillingPaymentCreatedPayload, ValidationError};
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    fn payment(status: &str) -> BillingPaymentCreatedPayload {
+        BillingPaymentCreatedPayload {
+            payment_id: "pay_1".to_string(),
+            user_id: "user1".to_string(),
+            amount: 1000,
+            currency: "usd".to_string(),
+            status: status.to_string(),
+            metadata: HashMap::new(),
+            occurred_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn payloads_are_validated_from_the_schema() {
+        assert_eq!(payment("pending").validate(), Ok(()));
+        assert_eq!(payment("paid").validate().unwrap_err().field, "status");
+        let mut blank = payment("processing");
+        blank.user_id = "  ".to_string();
+        assert_eq!(
+            blank.validate(),
+            Err(ValidationError {
+                field: "user_id",
+                reason: "must not be empty".to_string(),
+            })
+        );
+
+        let error = validate_payload(&payment("paid")).unwrap_err();
+        assert!(matches!(&error, RabbitMQError::InvalidPayload(message)
+            if message.starts_with("billing.payment_created payload: status")));
+    }
+
+    /// A payload defined by a microservice for an event of the schema.
+    #[derive(Serialize, Deserialize, PayloadEvent)]
+    #[event(AuthDeletedUser)]
+    struct DownstreamPayload {
+        user_id: String,
+    }
+
+    #[test]
+    fn derived_payloads_are_valid() {
+        let payload = DownstreamPayload {
+            user_id: String::new(),
+        };
+        validate_payload(&payload).unwrap();
+    }
+
+    #[test]
+    fn invalid_consumed_payloads_are_quarantined() {
+        let event = MicroserviceEvent::AuthDeletedUser;
+        let payload = |value: Value| RawPayload::from_value(json!({ "userId": value })).unwrap();
+        validate_consumed(event, &payload(json!("user1"))).unwrap();
+
+        let empty = validate_consumed(event, &payload(json!(""))).unwrap_err();
+        assert!(matches!(empty, RabbitMQError::InvalidPayload(_)));
+        assert!(empty.is_decode_error());
+        let unparsable = validate_consumed(event, &payload(json!(1))).unwrap_err();
+        assert!(matches!(unparsable, RabbitMQError::SerializationError(_)));
+    }
+}
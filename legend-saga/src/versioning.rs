@@ -0,0 +1,224 @@
+//! Versions of the event payloads. `publish_event` sends the version of the payload in
+//! [`SCHEMA_VERSION_HEADER`], and the consumers upcast the payloads of older versions to the
+//! current one before their handler sees them. Payloads without the header are taken as the
+//! current version and are never upcast.
+
+use crate::connection::{RabbitMQClient, RabbitMQError};
+use crate::events::MicroserviceEvent;
+use crate::nack::header_as_i64;
+use crate::raw_payload::RawPayload;
+use lapin::types::{AMQPValue, FieldTable};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Header with the schema version of the payload of an event.
+pub const SCHEMA_VERSION_HEADER: &str = "x-schema-version";
+
+/// Transforms a payload of a version into the next one.
+type Upcaster = Arc<dyn Fn(&mut HashMap<String, Value>) -> Result<(), String> + Send + Sync>;
+
+/// What to do with a payload published with a version newer than the one this
+/// microservice knows, e.g. while it has not been upgraded yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FutureVersionPolicy {
+    /// The payload reaches the handler as is, the fields the handler does not know are
+    /// ignored when parsing it.
+    #[default]
+    Accept,
+    /// The delivery is quarantined like an undecodable one, to be replayed once the
+    /// microservice is upgraded.
+    Quarantine,
+}
+
+#[derive(Default)]
+struct VersioningState {
+    upcasters: HashMap<(MicroserviceEvent, u32), Upcaster>,
+    future_version_policy: FutureVersionPolicy,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Versioning {
+    state: Arc<RwLock<VersioningState>>,
+}
+
+impl Versioning {
+    /// Brings `payload`, published with `version`, to the current version of `event`. The
+    /// payload is only parsed when an upcaster applies to it.
+    pub(crate) fn upcast(
+        &self,
+        event: MicroserviceEvent,
+        version: u32,
+        payload: RawPayload,
+    ) -> Result<RawPayload, RabbitMQError> {
+        let current = event.schema_version();
+        let state = self.state.read().unwrap();
+        if version > current {
+            return match state.future_version_policy {
+                FutureVersionPolicy::Accept => Ok(payload),
+                FutureVersionPolicy::Quarantine => Err(RabbitMQError::UnsupportedSchemaVersion {
+                    event: event.as_ref().to_string(),
+                    version,
+                    current,
+                }),
+            };
+        }
+        // Without an upcaster the change was compatible, e.g. a field with a default
+        let upcasters: Vec<(u32, &Upcaster)> = (version..current)
+            .filter_map(|from| Some((from, state.upcasters.get(&(event, from))?)))
+            .collect();
+        if upcasters.is_empty() {
+            return Ok(payload);
+        }
+        let mut map = payload.as_map().clone();
+        for (from, upcaster) in upcasters {
+            upcaster(&mut map).map_err(|reason| RabbitMQError::UpcastError {
+                event: event.as_ref().to_string(),
+                version: from,
+                reason,
+            })?;
+        }
+        Ok(RawPayload::from_map(map))
+    }
+}
+
+/// The schema version of a delivery of `event`. Publishers that do not send the header, like
+/// the libraries of other languages, publish the current payloads: without the header the
+/// payload is taken as the current version, upcasting it as the first one would corrupt it.
+pub(crate) fn schema_version_from_headers(headers: &FieldTable, event: MicroserviceEvent) -> u32 {
+    headers
+        .inner()
+        .get(SCHEMA_VERSION_HEADER)
+        .and_then(|value| match value {
+            AMQPValue::LongString(s) => s.to_string().parse().ok(),
+            value => header_as_i64(value).and_then(|n| u32::try_from(n).ok()),
+        })
+        .unwrap_or_else(|| event.schema_version())
+}
+
+pub(crate) fn apply_schema_version_header(headers: &mut FieldTable, event: MicroserviceEvent) {
+    headers.insert(
+        SCHEMA_VERSION_HEADER.into(),
+        AMQPValue::LongUInt(event.schema_version()),
+    );
+}
+
+impl RabbitMQClient {
+    /// Registers the transformation of the payloads of `event` published with
+    /// `from_version` into `from_version + 1`. Payloads of older versions go through every
+    /// upcaster up to the current version, a missing one leaves the payload unchanged.
+    pub fn register_upcaster<F>(&self, event: MicroserviceEvent, from_version: u32, upcaster: F)
+    where
+        F: Fn(&mut HashMap<String, Value>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        if from_version >= event.schema_version() {
+            warn!(
+                "Upcaster of {} from version {} is never used, the current version is {}",
+                event.as_ref(),
+                from_version,
+                event.schema_version()
+            );
+        }
+        self.versioning
+            .state
+            .write()
+            .unwrap()
+            .upcasters
+            .insert((event, from_version), Arc::new(upcaster));
+    }
+
+    /// Sets what happens to the payloads of versions newer than the current one.
+    pub fn set_future_version_policy(&self, policy: FutureVersionPolicy) {
+        self.versioning.state.write().unwrap().future_version_policy = policy;
+    }
+}
+
+#[cfg(test)]
+mod test_versioning {
+    use super::*;
+    use serde_json::json;
+
+    const EVENT: MicroserviceEvent = MicroserviceEvent::BillingSubscriptionCreated;
+
+    fn versioning() -> Versioning {
+        let versioning = Versioning::default();
+        versioning.state.write().unwrap().upcasters.insert(
+            (EVENT, 1),
+            Arc::new(|payload: &mut HashMap<String, Value>| {
+                payload.insert("features".to_string(), json!(["legacy"]));
+                Ok(())
+            }),
+        );
+        versioning
+    }
+
+    fn empty() -> RawPayload {
+        RawPayload::from_json(b"{}").unwrap()
+    }
+
+    #[test]
+    fn older_payloads_are_upcast() {
+        assert_eq!(EVENT.schema_version(), 2);
+        let payload = versioning().upcast(EVENT, 1, empty()).unwrap();
+        assert_eq!(payload.as_map()["features"], json!(["legacy"]));
+
+        let current = versioning().upcast(EVENT, 2, empty()).unwrap();
+        assert!(current.as_map().is_empty());
+    }
+
+    #[test]
+    fn failing_upcasters_and_future_versions() {
+        let versioning = versioning();
+        versioning.state.write().unwrap().upcasters.insert(
+            (EVENT, 1),
+            Arc::new(|_: &mut HashMap<String, Value>| Err("no plan".to_string())),
+        );
+        assert!(matches!(
+            versioning.upcast(EVENT, 1, empty()),
+            Err(RabbitMQError::UpcastError { version: 1, .. })
+        ));
+
+        // Accepted as is by default
+        versioning.upcast(EVENT, 3, empty()).unwrap();
+        versioning.state.write().unwrap().future_version_policy = FutureVersionPolicy::Quarantine;
+        let error = versioning.upcast(EVENT, 3, empty()).unwrap_err();
+        assert!(matches!(
+            error,
+            RabbitMQError::UnsupportedSchemaVersion {
+                version: 3,
+                current: 2,
+                ..
+            }
+        ));
+        assert!(error.is_decode_error());
+    }
+
+    #[test]
+    fn reads_the_version_header() {
+        let mut headers = FieldTable::default();
+        headers.insert(SCHEMA_VERSION_HEADER.into(), AMQPValue::LongUInt(1));
+        assert_eq!(schema_version_from_headers(&headers, EVENT), 1);
+        apply_schema_version_header(&mut headers, EVENT);
+        assert_eq!(schema_version_from_headers(&headers, EVENT), 2);
+        headers.insert(
+            SCHEMA_VERSION_HEADER.into(),
+            AMQPValue::LongString("3".into()),
+        );
+        assert_eq!(schema_version_from_headers(&headers, EVENT), 3);
+    }
+
+    #[test]
+    fn payloads_without_version_are_current() {
+        let headers = FieldTable::default();
+        let version = schema_version_from_headers(&headers, EVENT);
+        assert_eq!(version, EVENT.schema_version());
+
+        let mut current = empty().as_map().clone();
+        current.insert("features".to_string(), json!(["pro"]));
+        let payload = versioning()
+            .upcast(EVENT, version, RawPayload::from_map(current))
+            .unwrap();
+        assert_eq!(payload.as_map()["features"], json!(["pro"]));
+    }
+}
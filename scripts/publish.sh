@@ -21,9 +21,12 @@ current_version=$(grep '^version' legend-saga/Cargo.toml | sed 's/version = "\(.
 # Increment patch version
 new_version=$(increment_version "$current_version" 2)
 
-# Update version in Cargo.toml
-sed -i "s/^version = \"$current_version\"/version = \"$new_version\"/" legend-saga/Cargo.toml
+# Update version in Cargo.toml, legend-saga-derive is released in lockstep
+sed -i "s/^version = \"$current_version\"/version = \"$new_version\"/" legend-saga/Cargo.toml legend-saga-derive/Cargo.toml
+sed -i "s/legend-saga-derive = { version = \"=$current_version\"/legend-saga-derive = { version = \"=$new_version\"/" legend-saga/Cargo.toml
 
+# The derive macros are published first, legend-saga depends on them
+cargo publish -p legend-saga-derive --allow-dirty --token "$CARGO_REGISTRY_TOKEN"
 # Package the crate
 cargo package -p legend-saga --allow-dirty
 # Publish the crate